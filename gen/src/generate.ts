@@ -13,10 +13,12 @@ interface Type {
 export interface Config {
   baseName: string;
   types: Type[];
+  // Whether to generate a visitor trait and the accept method for the enum
+  visitor?: boolean;
 }
 
 const createEnumDefinition = (config: Config): string => {
-  let definition = `#[derive(Debug)]
+  let definition = `#[derive(Debug, Clone)]
 pub enum ${config.baseName} {\n`;
 
   definition += config.types
@@ -58,7 +60,7 @@ const createVisitorImpl = (config: Config): string => {
     )}) => visitor.${functionName}(${argNames.join(", ")}),`;
   };
 
-  return `impl Expr {
+  return `impl ${config.baseName} {
     pub fn accept<R>(&self, visitor: &impl Visitor<R>) -> R {
         match self {
 ${config.types.map(createMatchArm).join("\n")}
@@ -80,11 +82,16 @@ export const createFile = (
     keepIndex = existingContent.length;
   }
 
+  const visitor =
+    config.visitor === false
+      ? ""
+      : `${createVisitorDefinition(config)}
+${createVisitorImpl(config)}`;
+
   let content = `${imports}
 
 ${createEnumDefinition(config)}
-${createVisitorDefinition(config)}
-${createVisitorImpl(config)}
+${visitor}
 
 ${existingContent.slice(keepIndex)}
 `;
@@ -1,12 +1,12 @@
 import { createFile, type Config } from "./generate";
 
-const config: Config = {
+const exprConfig: Config = {
   baseName: "Expr",
   types: [
     {
       name: "Unary",
       parts: [
-        { name: "op", type: "Token" },
+        { name: "op", type: "Operator" },
         { name: "expr", type: "Box<Expr>" },
       ],
     },
@@ -14,7 +14,7 @@ const config: Config = {
       name: "Binary",
       parts: [
         { name: "left", type: "Box<Expr>" },
-        { name: "op", type: "Token" },
+        { name: "op", type: "Operator" },
         { name: "right", type: "Box<Expr>" },
       ],
     },
@@ -26,13 +26,361 @@ const config: Config = {
       name: "Literal",
       parts: [{ name: "value", type: "Token" }],
     },
+    {
+      name: "Variable",
+      parts: [{ name: "name", type: "Token" }],
+    },
+    {
+      name: "Assign",
+      parts: [
+        { name: "name", type: "Token" },
+        { name: "value", type: "Box<Expr>" },
+      ],
+    },
+    {
+      name: "Call",
+      parts: [
+        { name: "callee", type: "Box<Expr>" },
+        { name: "paren", type: "Token" },
+        { name: "args", type: "Vec<Expr>" },
+      ],
+    },
+    {
+      name: "Logical",
+      parts: [
+        { name: "left", type: "Box<Expr>" },
+        { name: "op", type: "Operator" },
+        { name: "right", type: "Box<Expr>" },
+      ],
+    },
+    {
+      name: "List",
+      parts: [
+        { name: "bracket", type: "Token" },
+        { name: "elements", type: "Vec<Expr>" },
+      ],
+    },
+    {
+      name: "Index",
+      parts: [
+        { name: "object", type: "Box<Expr>" },
+        { name: "bracket", type: "Token" },
+        { name: "index", type: "Box<Expr>" },
+      ],
+    },
+    {
+      name: "IndexSet",
+      parts: [
+        { name: "object", type: "Box<Expr>" },
+        { name: "bracket", type: "Token" },
+        { name: "index", type: "Box<Expr>" },
+        { name: "value", type: "Box<Expr>" },
+      ],
+    },
+    {
+      name: "Map",
+      parts: [
+        { name: "brace", type: "Token" },
+        { name: "entries", type: "Vec<(Expr, Expr)>" },
+      ],
+    },
+    {
+      name: "Tuple",
+      parts: [
+        { name: "paren", type: "Token" },
+        { name: "elements", type: "Vec<Expr>" },
+      ],
+    },
+    {
+      name: "Get",
+      parts: [
+        { name: "object", type: "Box<Expr>" },
+        { name: "name", type: "Token" },
+      ],
+    },
+    {
+      name: "Spread",
+      parts: [
+        { name: "ellipsis", type: "Token" },
+        { name: "expr", type: "Box<Expr>" },
+      ],
+    },
+    {
+      name: "Block",
+      parts: [
+        { name: "brace", type: "Token" },
+        { name: "body", type: "BlockBody" },
+        { name: "value", type: "Option<Box<Expr>>" },
+      ],
+    },
+  ],
+};
+
+const stmtConfig: Config = {
+  baseName: "Stmt",
+  // Surface statements are lowered into the IR instead of being visited
+  visitor: false,
+  types: [
+    {
+      name: "Expression",
+      parts: [{ name: "expr", type: "Expr" }],
+    },
+    {
+      name: "Print",
+      parts: [{ name: "exprs", type: "Vec<Expr>" }],
+    },
+    {
+      name: "Assert",
+      parts: [
+        { name: "keyword", type: "Token" },
+        { name: "condition", type: "Expr" },
+        { name: "text", type: "String" },
+        { name: "message", type: "Option<Expr>" },
+      ],
+    },
+    {
+      name: "Var",
+      parts: [
+        { name: "name", type: "Token" },
+        { name: "initializer", type: "Option<Expr>" },
+      ],
+    },
+    {
+      name: "Const",
+      parts: [
+        { name: "name", type: "Token" },
+        { name: "initializer", type: "Expr" },
+      ],
+    },
+    {
+      name: "VarTuple",
+      parts: [
+        { name: "paren", type: "Token" },
+        { name: "names", type: "Vec<Token>" },
+        { name: "initializer", type: "Expr" },
+      ],
+    },
+    {
+      name: "Block",
+      parts: [{ name: "stmts", type: "Vec<Stmt>" }],
+    },
+    {
+      name: "If",
+      parts: [
+        { name: "condition", type: "Expr" },
+        { name: "then_branch", type: "Box<Stmt>" },
+        { name: "else_branch", type: "Option<Box<Stmt>>" },
+      ],
+    },
+    {
+      name: "While",
+      parts: [
+        { name: "condition", type: "Expr" },
+        { name: "body", type: "Box<Stmt>" },
+        { name: "increment", type: "Option<Expr>" },
+        { name: "label", type: "Option<Token>" },
+      ],
+    },
+    {
+      name: "For",
+      parts: [
+        { name: "keyword", type: "Token" },
+        { name: "initializer", type: "Option<Box<Stmt>>" },
+        { name: "condition", type: "Option<Expr>" },
+        { name: "increment", type: "Option<Expr>" },
+        { name: "body", type: "Box<Stmt>" },
+        { name: "label", type: "Option<Token>" },
+      ],
+    },
+    {
+      name: "DoWhile",
+      parts: [
+        { name: "keyword", type: "Token" },
+        { name: "body", type: "Box<Stmt>" },
+        { name: "condition", type: "Expr" },
+        { name: "label", type: "Option<Token>" },
+      ],
+    },
+    {
+      name: "ForIn",
+      parts: [
+        { name: "name", type: "Token" },
+        { name: "keyword", type: "Token" },
+        { name: "iterable", type: "Expr" },
+        { name: "body", type: "Box<Stmt>" },
+        { name: "label", type: "Option<Token>" },
+      ],
+    },
+    {
+      name: "Switch",
+      parts: [
+        { name: "keyword", type: "Token" },
+        { name: "subject", type: "Expr" },
+        { name: "cases", type: "Vec<(Vec<Expr>, Stmt)>" },
+        { name: "default", type: "Option<Box<Stmt>>" },
+      ],
+    },
+    {
+      name: "Throw",
+      parts: [
+        { name: "keyword", type: "Token" },
+        { name: "value", type: "Expr" },
+      ],
+    },
+    {
+      name: "Try",
+      parts: [
+        { name: "body", type: "Vec<Stmt>" },
+        { name: "catch", type: "Option<(Token, Vec<Stmt>)>" },
+        { name: "finally", type: "Option<Vec<Stmt>>" },
+      ],
+    },
+    {
+      name: "Break",
+      parts: [
+        { name: "keyword", type: "Token" },
+        { name: "label", type: "Option<Token>" },
+      ],
+    },
+    {
+      name: "Continue",
+      parts: [
+        { name: "keyword", type: "Token" },
+        { name: "label", type: "Option<Token>" },
+      ],
+    },
+  ],
+};
+
+const irConfig: Config = {
+  baseName: "Stmt",
+  types: [
+    {
+      name: "Expression",
+      parts: [{ name: "expr", type: "Expr" }],
+    },
+    {
+      name: "Print",
+      parts: [{ name: "exprs", type: "Vec<Expr>" }],
+    },
+    {
+      name: "Assert",
+      parts: [
+        { name: "keyword", type: "Token" },
+        { name: "condition", type: "Expr" },
+        { name: "text", type: "String" },
+        { name: "message", type: "Option<Expr>" },
+      ],
+    },
+    {
+      name: "Var",
+      parts: [
+        { name: "name", type: "Token" },
+        { name: "initializer", type: "Option<Expr>" },
+      ],
+    },
+    {
+      name: "Const",
+      parts: [
+        { name: "name", type: "Token" },
+        { name: "initializer", type: "Expr" },
+      ],
+    },
+    {
+      name: "VarTuple",
+      parts: [
+        { name: "paren", type: "Token" },
+        { name: "names", type: "Vec<Token>" },
+        { name: "initializer", type: "Expr" },
+      ],
+    },
+    {
+      name: "Block",
+      parts: [{ name: "stmts", type: "Vec<Stmt>" }],
+    },
+    {
+      name: "If",
+      parts: [
+        { name: "condition", type: "Expr" },
+        { name: "then_branch", type: "Box<Stmt>" },
+        { name: "else_branch", type: "Option<Box<Stmt>>" },
+      ],
+    },
+    {
+      name: "While",
+      parts: [
+        { name: "condition", type: "Expr" },
+        { name: "body", type: "Box<Stmt>" },
+        { name: "increment", type: "Option<Expr>" },
+        { name: "label", type: "Option<Token>" },
+      ],
+    },
+    {
+      name: "ForIn",
+      parts: [
+        { name: "name", type: "Token" },
+        { name: "keyword", type: "Token" },
+        { name: "iterable", type: "Expr" },
+        { name: "body", type: "Box<Stmt>" },
+        { name: "label", type: "Option<Token>" },
+      ],
+    },
+    {
+      name: "Throw",
+      parts: [
+        { name: "keyword", type: "Token" },
+        { name: "value", type: "Expr" },
+      ],
+    },
+    {
+      name: "Try",
+      parts: [
+        { name: "body", type: "Vec<Stmt>" },
+        { name: "catch", type: "Option<(Token, Vec<Stmt>)>" },
+        { name: "finally", type: "Option<Vec<Stmt>>" },
+      ],
+    },
+    {
+      name: "Break",
+      parts: [
+        { name: "keyword", type: "Token" },
+        { name: "label", type: "Option<Token>" },
+      ],
+    },
+    {
+      name: "Continue",
+      parts: [
+        { name: "keyword", type: "Token" },
+        { name: "label", type: "Option<Token>" },
+      ],
+    },
   ],
 };
 
-const outPath = "../src/ast/expr.rs";
 const targetComment = "// Custom implementations";
 
-const imports = `use crate::lexer::token::Token;
-use crate::lexer::types::TokenType;`;
+createFile(
+  exprConfig,
+  "../src/ast/expr.rs",
+  `use super::error::Span;
+use super::{ir, stmt};
+use crate::lexer::token::Token;
+use crate::lexer::types::TokenType;`,
+  targetComment
+);
+
+createFile(
+  stmtConfig,
+  "../src/ast/stmt.rs",
+  `use super::expr::Expr;
+use crate::lexer::token::Token;`,
+  targetComment
+);
 
-createFile(config, outPath, imports, targetComment);
+createFile(
+  irConfig,
+  "../src/ast/ir.rs",
+  `use super::expr::Expr;
+use crate::lexer::token::Token;`,
+  targetComment
+);
@@ -0,0 +1,266 @@
+use super::expr::{BlockBody, Expr};
+use super::ir;
+use super::stmt::Stmt;
+use super::transform::{walk_expr, walk_stmt, Transformer};
+use crate::lexer::token::Token;
+use crate::lexer::types::TokenType;
+
+/// Lowers the syntactic sugar of the surface language into the core forms understood by the
+/// interpreter. A `for` loop is rewritten as a `while` loop, which keeps the increment aside
+/// so that it also runs after the iterations that are cut short by a `continue`:
+///
+/// ```text
+/// for (init; cond; incr) body    =>    { init; while (cond) body (incr) }
+/// ```
+///
+/// A `do` loop is rewritten as a `while` loop whose first check of the condition is skipped by
+/// a flag that can not be named by the user. The flag is cleared by the increment, so that a
+/// `continue` in the first iteration also checks the condition:
+///
+/// ```text
+/// do body while (cond);    =>    { var $first = true; while ($first or cond) body ($first = false) }
+/// ```
+///
+/// A `switch` is rewritten as a chain of `if` statements, which compare the subject evaluated
+/// once into a variable that can not be named by the user:
+///
+/// ```text
+/// switch (x) { case 1, 2: a case 3: b default: c }
+///     =>    { var $subject = x; if ($subject == 1 or $subject == 2) a else if ($subject == 3) b else c }
+/// ```
+///
+/// The statements of a block expression are lowered into the IR as well, as expressions are
+/// shared by the surface language and the IR.
+pub struct Desugarer;
+
+impl Desugarer {
+    fn desugar_do_while(
+        &mut self,
+        keyword: Token,
+        body: Stmt,
+        condition: Expr,
+        label: Option<Token>,
+    ) -> Stmt {
+        let name = keyword.synthesize(TokenType::Identifier("$first".to_string()), "$first");
+        let first = Stmt::Var(
+            name.clone(),
+            Some(Expr::Literal(keyword.synthesize(TokenType::TRUE, "true"))),
+        );
+
+        let condition = Expr::new_logical_expr(
+            Expr::Variable(name.clone()),
+            keyword.synthesize(TokenType::OR, "or"),
+            self.transform_expr(condition),
+        );
+        let increment = Expr::Assign(
+            name,
+            Box::new(Expr::Literal(keyword.synthesize(TokenType::FALSE, "false"))),
+        );
+        let body = Box::new(self.transform_stmt(body));
+
+        Stmt::Block(vec![
+            first,
+            Stmt::While(condition, body, Some(increment), label),
+        ])
+    }
+
+    fn desugar_switch(
+        &mut self,
+        keyword: Token,
+        subject: Expr,
+        cases: Vec<(Vec<Expr>, Stmt)>,
+        default: Option<Box<Stmt>>,
+    ) -> Stmt {
+        let name = keyword.synthesize(TokenType::Identifier("$subject".to_string()), "$subject");
+        let subject = Stmt::Var(name.clone(), Some(self.transform_expr(subject)));
+
+        // The chain is built from the last case, as each case is the else branch of the previous one
+        let mut chain = default.map(|stmt| self.transform_stmt(*stmt));
+        for (patterns, body) in cases.into_iter().rev() {
+            let condition = patterns
+                .into_iter()
+                .map(|pattern| {
+                    Expr::new_binary_expr(
+                        Expr::Variable(name.clone()),
+                        keyword.synthesize(TokenType::EqualEqual, "=="),
+                        self.transform_expr(pattern),
+                    )
+                })
+                .reduce(|left, right| {
+                    Expr::new_logical_expr(left, keyword.synthesize(TokenType::OR, "or"), right)
+                })
+                .expect("Every case should have at least one pattern");
+
+            let body = Box::new(self.transform_stmt(body));
+            chain = Some(Stmt::If(condition, body, chain.map(Box::new)));
+        }
+
+        Stmt::Block(std::iter::once(subject).chain(chain).collect())
+    }
+}
+
+impl Transformer for Desugarer {
+    fn transform_stmt(&mut self, stmt: Stmt) -> Stmt {
+        match stmt {
+            Stmt::For(keyword, initializer, condition, increment, body, label) => {
+                let body = self.transform_stmt(*body);
+                let increment = increment.map(|expr| self.transform_expr(expr));
+
+                // A missing condition loops forever
+                let condition = match condition {
+                    Some(condition) => self.transform_expr(condition),
+                    None => Expr::Literal(keyword.synthesize(TokenType::TRUE, "true")),
+                };
+                let mut stmt = Stmt::While(condition, Box::new(body), increment, label);
+
+                if let Some(initializer) = initializer {
+                    stmt = Stmt::Block(vec![self.transform_stmt(*initializer), stmt]);
+                }
+
+                stmt
+            }
+
+            Stmt::DoWhile(keyword, body, condition, label) => {
+                self.desugar_do_while(keyword, *body, condition, label)
+            }
+
+            Stmt::Switch(keyword, subject, cases, default) => {
+                self.desugar_switch(keyword, subject, cases, default)
+            }
+
+            stmt => walk_stmt(self, stmt),
+        }
+    }
+
+    fn transform_expr(&mut self, expr: Expr) -> Expr {
+        match expr {
+            Expr::Block(brace, BlockBody::Surface(stmts), value) => {
+                let stmts = lower(stmts);
+                let value = value.map(|value| Box::new(self.transform_expr(*value)));
+                Expr::Block(brace, BlockBody::Lowered(stmts), value)
+            }
+            expr => walk_expr(self, expr),
+        }
+    }
+}
+
+/// Lowers all the syntactic sugar in the program into core forms.
+pub fn desugar(stmts: Vec<Stmt>) -> Vec<Stmt> {
+    Desugarer.transform_stmts(stmts)
+}
+
+/// Desugars the program and converts it into the core IR that is consumed by the interpreter.
+pub fn lower(stmts: Vec<Stmt>) -> Vec<ir::Stmt> {
+    desugar(stmts).into_iter().map(lower_stmt).collect()
+}
+
+/// Lowers the statements of the block expressions in an expression that is evaluated on its own.
+pub fn lower_expr(expr: Expr) -> Expr {
+    Desugarer.transform_expr(expr)
+}
+
+/// Converts a desugared statement into its IR equivalent.
+fn lower_stmt(stmt: Stmt) -> ir::Stmt {
+    let lower_boxed = |stmt: Box<Stmt>| Box::new(lower_stmt(*stmt));
+    let lower_stmts = |stmts: Vec<Stmt>| stmts.into_iter().map(lower_stmt).collect::<Vec<_>>();
+
+    match stmt {
+        Stmt::Expression(expr) => ir::Stmt::Expression(expr),
+        Stmt::Print(exprs) => ir::Stmt::Print(exprs),
+        Stmt::Assert(keyword, condition, text, message) => {
+            ir::Stmt::Assert(keyword, condition, text, message)
+        }
+        Stmt::Var(name, initializer) => ir::Stmt::Var(name, initializer),
+        Stmt::Const(name, initializer) => ir::Stmt::Const(name, initializer),
+        Stmt::VarTuple(paren, names, initializer) => ir::Stmt::VarTuple(paren, names, initializer),
+        Stmt::Block(stmts) => ir::Stmt::Block(lower_stmts(stmts)),
+        Stmt::If(condition, then_branch, else_branch) => ir::Stmt::If(
+            condition,
+            lower_boxed(then_branch),
+            else_branch.map(lower_boxed),
+        ),
+        Stmt::While(condition, body, increment, label) => {
+            ir::Stmt::While(condition, lower_boxed(body), increment, label)
+        }
+        Stmt::ForIn(name, keyword, iterable, body, label) => {
+            ir::Stmt::ForIn(name, keyword, iterable, lower_boxed(body), label)
+        }
+        Stmt::Throw(keyword, value) => ir::Stmt::Throw(keyword, value),
+        Stmt::Try(body, catch, finally) => ir::Stmt::Try(
+            lower_stmts(body),
+            catch.map(|(name, handler)| (name, lower_stmts(handler))),
+            finally.map(lower_stmts),
+        ),
+        Stmt::Break(keyword, label) => ir::Stmt::Break(keyword, label),
+        Stmt::Continue(keyword, label) => ir::Stmt::Continue(keyword, label),
+        Stmt::For(..) => unreachable!("For loops are desugared into while loops before lowering"),
+        Stmt::DoWhile(..) => {
+            unreachable!("Do loops are desugared into while loops before lowering")
+        }
+        Stmt::Switch(..) => {
+            unreachable!("Switches are desugared into if statements before lowering")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::syntax_tree::SyntaxTree;
+    use crate::lexer::lexer::Lexer;
+
+    fn desugar_source(source: &str) -> Vec<Stmt> {
+        let tokens = Lexer::new(source).get_tokens();
+        desugar(SyntaxTree::new(tokens).parse().unwrap())
+    }
+
+    #[test]
+    fn test_desugar_for() {
+        let stmts = desugar_source("for (var i = 0; i < 3; i = i + 1) print i;");
+
+        match &stmts[..] {
+            [Stmt::Block(block)] => match &block[..] {
+                [Stmt::Var(name, Some(_)), Stmt::While(_, body, Some(Expr::Assign(..)), None)] => {
+                    assert_eq!(name.lexeme, "i");
+                    assert!(matches!(**body, Stmt::Print(_)));
+                }
+                _ => panic!("Unexpected block: {:?}", block),
+            },
+            _ => panic!("Unexpected statements: {:?}", stmts),
+        }
+
+        // Without an initializer and an increment, the loop is not wrapped in blocks
+        let stmts = desugar_source("for (; false;) print 1;");
+        assert!(
+            matches!(&stmts[..], [Stmt::While(_, body, None, None)] if matches!(**body, Stmt::Print(_)))
+        );
+    }
+
+    #[test]
+    fn test_desugar_nested_for() {
+        let stmts = desugar_source("while (true) { for (;;) {} }");
+
+        match &stmts[..] {
+            [Stmt::While(_, body, ..)] => {
+                assert!(
+                    matches!(&**body, Stmt::Block(body) if matches!(&body[..], [Stmt::While(..)]))
+                )
+            }
+            _ => panic!("Unexpected statements: {:?}", stmts),
+        }
+    }
+
+    #[test]
+    fn test_synthesized_condition_position() {
+        let stmts = desugar_source("\n  for (;;) {}");
+
+        match &stmts[..] {
+            [Stmt::While(Expr::Literal(token), ..)] => {
+                assert_eq!(token.token_type, TokenType::TRUE);
+                assert_eq!(token.line, 2);
+                assert!(token.synthetic);
+            }
+            _ => panic!("Unexpected statements: {:?}", stmts),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use anyhow::Error;
+
+use super::ordered_map::OrderedMap;
+use super::value::Value;
+use crate::lexer::token::Token;
+
+/// Stores the values bound to the variable names of a scope of a program.
+/// Lookups that fail in a scope continue in the enclosing scope.
+#[derive(Debug, Default)]
+pub struct Environment {
+    /// The bindings of the scope, in the order the names were first defined.
+    values: OrderedMap<String, Value>,
+    /// The names of the scope that are bound by a `const` declaration, and can not be assigned.
+    constants: HashSet<String>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new scope nested inside the provided enclosing scope.
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Environment {
+            values: OrderedMap::new(),
+            constants: HashSet::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    /// Binds the name to the value, overwriting any previous binding with the same name.
+    pub fn define(&mut self, name: &str, value: Value) {
+        self.constants.remove(name);
+        self.values.insert(name.to_string(), value);
+    }
+
+    /// Binds the name declared by the token like `define`, unless the scope already has a constant
+    /// with that name, which can not be replaced by another declaration.
+    pub fn declare(&mut self, name: &Token, value: Value) -> Result<(), Error> {
+        if self.constants.contains(name.name()) {
+            return Err(Error::msg(format!(
+                "[line {}] Cannot redeclare constant '{}'.",
+                name.line,
+                name.name()
+            )));
+        }
+
+        self.define(name.name(), value);
+        Ok(())
+    }
+
+    /// Declares the name like `declare`, but the binding can not be assigned afterwards.
+    pub fn declare_constant(&mut self, name: &Token, value: Value) -> Result<(), Error> {
+        self.declare(name, value)?;
+        self.constants.insert(name.name().to_string());
+        Ok(())
+    }
+
+    /// Returns the value bound to the variable referred by the token.
+    pub fn get(&self, name: &Token) -> Result<Value, Error> {
+        match (self.values.get(name.name()), &self.enclosing) {
+            (Some(value), _) => Ok(value.clone()),
+            (None, Some(enclosing)) => enclosing.borrow().get(name),
+            (None, None) => Err(Error::msg(format!(
+                "[line {}] Undefined variable '{}'.",
+                name.line,
+                name.name()
+            ))),
+        }
+    }
+
+    /// Returns the names of all the variables visible from this scope, including the ones
+    /// defined in the enclosing scopes.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = match &self.enclosing {
+            Some(enclosing) => enclosing.borrow().names(),
+            None => Vec::new(),
+        };
+        names.extend(self.values.keys().cloned());
+        names
+    }
+
+    /// Updates the value of an already defined variable.
+    pub fn assign(&mut self, name: &Token, value: Value) -> Result<(), Error> {
+        match (self.values.get_mut(name.name()), &self.enclosing) {
+            (Some(_), _) if self.constants.contains(name.name()) => Err(Error::msg(format!(
+                "[line {}] Cannot assign to constant '{}'.",
+                name.line,
+                name.name()
+            ))),
+            (Some(slot), _) => {
+                *slot = value;
+                Ok(())
+            }
+            (None, Some(enclosing)) => enclosing.borrow_mut().assign(name, value),
+            (None, None) => Err(Error::msg(format!(
+                "[line {}] Undefined variable '{}'.",
+                name.line,
+                name.name()
+            ))),
+        }
+    }
+}
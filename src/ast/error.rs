@@ -0,0 +1,235 @@
+use thiserror::Error;
+
+use super::expr::Operator;
+use super::value::Value;
+use crate::lexer::token::Token;
+use crate::lexer::types::TokenType;
+
+/// The location in the source code that a runtime error is reported at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    pub character: usize,
+}
+
+impl From<&Token> for Span {
+    fn from(token: &Token) -> Self {
+        Span {
+            line: token.line,
+            character: token.character,
+        }
+    }
+}
+
+impl Span {
+    /// Creates a synthetic token positioned at the span, like `Token::synthesize`.
+    pub fn synthesize(self, token_type: TokenType, lexeme: &str) -> Token {
+        let mut token = Token::new(token_type, lexeme, self.line, self.character);
+        token.synthetic = true;
+        token
+    }
+}
+
+/// The syntax that runtime errors are reported at, which is either a token or the operator of an
+/// expression.
+pub trait Located {
+    /// The syntax as it is quoted in the error messages.
+    fn lexeme(&self) -> &str;
+    fn span(&self) -> Span;
+}
+
+impl Located for Token {
+    fn lexeme(&self) -> &str {
+        &self.lexeme
+    }
+
+    fn span(&self) -> Span {
+        Span::from(self)
+    }
+}
+
+impl Located for Operator {
+    fn lexeme(&self) -> &str {
+        self.op.lexeme()
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// The runtime errors with a structured kind, which are wrapped in the `anyhow::Error` returned
+/// by the interpreter. Callers that need more than the message can recover the kind with
+/// `err.downcast_ref::<RuntimeError>()`.
+#[derive(Debug, Error, PartialEq)]
+pub enum RuntimeError {
+    /// An operator was applied to operands of types it does not support.
+    #[error(
+        "[line {}] Invalid {} for '{operator}': expected {expected}, got {}.",
+        .span.line,
+        operand_noun(.found),
+        .found.join(" and ")
+    )]
+    TypeMismatch {
+        /// A description of the operands the operator supports, such as "two numbers".
+        expected: String,
+        /// The debug representations of the operands the operator was applied to. Values are not
+        /// stored directly, as they can hold shared references that errors can not carry.
+        found: Vec<String>,
+        operator: String,
+        span: Span,
+    },
+
+    /// A native was called with an argument of a type it does not support.
+    #[error(
+        "[line {}] Invalid argument {position} for '{native}': expected {expected}, got {found}.",
+        .span.line
+    )]
+    InvalidArgument {
+        native: String,
+        /// The position of the argument, starting from 1.
+        position: usize,
+        expected: String,
+        found: String,
+        span: Span,
+    },
+
+    /// A native failed for a reason other than the types of its arguments, such as a file that
+    /// could not be read.
+    #[error("[line {}] {message}", .span.line)]
+    NativeFailed {
+        native: String,
+        message: String,
+        span: Span,
+    },
+
+    /// A number was used as an index or a count, but is not a non negative integer in range.
+    #[error("[line {}] Invalid {role} for '{operator}': {value} {problem}.", .span.line)]
+    InvalidIndex {
+        /// What the number is used as, such as "index" or "count".
+        role: String,
+        operator: String,
+        /// The debug representation of the number.
+        value: String,
+        /// Why the number is invalid, such as "is negative".
+        problem: &'static str,
+        span: Span,
+    },
+
+    /// A value was thrown and not caught by any enclosing `try` statement.
+    #[error("[line {}] Uncaught exception: {value}.", .span.line)]
+    Uncaught {
+        /// The printed representation of the thrown value.
+        value: String,
+        span: Span,
+    },
+
+    /// The program asked to end with the exit status by calling `exit`. It is not an error, but
+    /// unwinds the program like one, and is never caught by `try` statements. Applications that
+    /// embed the interpreter receive it instead of their process exiting.
+    #[error("Exited with status {code}.")]
+    Exit { code: i32 },
+
+    /// A value was thrown out of a block expression. Errors can not hold values, so the value is
+    /// kept by the interpreter while this error unwinds the expression, and is thrown again from
+    /// the statement that evaluated it.
+    #[error("[line {}] Uncaught exception.", .span.line)]
+    Thrown { span: Span },
+}
+
+impl RuntimeError {
+    /// Creates a type mismatch error for the operator, wrapped in an `anyhow::Error`.
+    pub fn type_mismatch(
+        expected: &str,
+        found: Vec<Value>,
+        operator: &impl Located,
+    ) -> anyhow::Error {
+        RuntimeError::TypeMismatch {
+            expected: expected.to_string(),
+            found: found.iter().map(Value::debug_string).collect(),
+            operator: operator.lexeme().to_string(),
+            span: operator.span(),
+        }
+        .into()
+    }
+}
+
+/// The ways converting a value for an operation can fail. They hold neither the value nor the
+/// location, so that the operations of `Value` can return them cheaply, and they are only rendered
+/// into a `RuntimeError` by `ValueError::at` once the interpreter reports them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueError {
+    /// The value is not a number.
+    NotANumber,
+    /// The number can not be used as an index or a count, for the reason it holds.
+    InvalidIndex(&'static str),
+}
+
+impl ValueError {
+    /// Renders the error for the value, reported at the token. The role describes what the value
+    /// is used as in the message, such as "count".
+    pub fn at(self, value: &Value, token: &impl Located, role: &str) -> anyhow::Error {
+        match self {
+            ValueError::NotANumber => {
+                RuntimeError::type_mismatch("a number", vec![value.clone()], token)
+            }
+            ValueError::InvalidIndex(problem) => RuntimeError::InvalidIndex {
+                role: role.to_string(),
+                operator: token.lexeme().to_string(),
+                value: value.debug_string(),
+                problem,
+                span: token.span(),
+            }
+            .into(),
+        }
+    }
+}
+
+/// An argument of a native that could not be converted to the type of its parameter. Natives do
+/// not know where they are called from, so the interpreter reports it as an `InvalidArgument`
+/// at the location of the call.
+#[derive(Debug, Error, PartialEq)]
+#[error("Invalid argument {position}: expected {expected}, got {found}.")]
+pub struct ArgumentError {
+    pub position: usize,
+    pub expected: &'static str,
+    pub found: String,
+}
+
+impl ArgumentError {
+    /// Locates the error at the call of the native.
+    pub fn at_call(self, native: &str, paren: &Token) -> RuntimeError {
+        RuntimeError::InvalidArgument {
+            native: native.to_string(),
+            position: self.position,
+            expected: self.expected.to_string(),
+            found: self.found,
+            span: Span::from(paren),
+        }
+    }
+}
+
+/// The failure of a native for a reason other than the types of its arguments, such as an error
+/// of the operating system. Like for `ArgumentError`, the interpreter reports it as a
+/// `NativeFailed` at the location of the call.
+#[derive(Debug, Error, PartialEq)]
+#[error("{0}")]
+pub struct NativeError(pub String);
+
+impl NativeError {
+    /// Locates the error at the call of the native.
+    pub fn at_call(self, native: &str, paren: &Token) -> RuntimeError {
+        RuntimeError::NativeFailed {
+            native: native.to_string(),
+            message: self.0,
+            span: Span::from(paren),
+        }
+    }
+}
+
+fn operand_noun(found: &[String]) -> &'static str {
+    match found.len() {
+        1 => "operand",
+        _ => "operands",
+    }
+}
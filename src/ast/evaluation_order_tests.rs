@@ -0,0 +1,209 @@
+//! An executable specification of the order in which the interpreter evaluates expressions and
+//! performs their side effects. Every program records the values passed to the `record` native,
+//! which returns its argument unchanged, so the log shows exactly what was evaluated and when.
+
+use anyhow::Error;
+
+use super::interpreter::{run_program_with, Interpreter};
+use super::value::{NativeFunction, Value};
+use crate::config::Config;
+
+/// Writes the argument to the output and returns it.
+fn record(interpreter: &Interpreter, args: &[Value]) -> Result<Value, Error> {
+    interpreter.write_line(&args[0].to_string())?;
+    Ok(args[0].clone())
+}
+
+/// Runs the program with the `record` native, and returns the recorded log along with
+/// whether the program failed with a runtime error.
+fn run(source: &str) -> (Vec<String>, bool) {
+    let (output, result) = run_program_with(source, Config::default(), |interpreter| {
+        interpreter.define_native(NativeFunction::new("record", Some(1), record))
+    });
+
+    let log = output.lines().map(|line| line.to_string()).collect();
+    (log, result.is_err())
+}
+
+struct TestCase {
+    description: &'static str,
+    input: &'static str,
+    log: &'static [&'static str],
+    fails: bool,
+}
+
+fn check(test_cases: Vec<TestCase>) {
+    for test in test_cases {
+        let (log, fails) = run(test.input);
+        assert_eq!(log, test.log, "{}", test.description);
+        assert_eq!(fails, test.fails, "{}", test.description);
+    }
+}
+
+#[test]
+fn test_operands_left_to_right() {
+    check(vec![
+        TestCase {
+            description: "Binary operands",
+            input: "record(1) + record(2);",
+            log: &["1", "2"],
+            fails: false,
+        },
+        TestCase {
+            description: "Nested binary operands",
+            input: "(record(1) - record(2)) * (record(3) / record(4));",
+            log: &["1", "2", "3", "4"],
+            fails: false,
+        },
+        TestCase {
+            description: "Comparison and equality operands",
+            input: "record(1) < record(2) == record(true);",
+            log: &["1", "2", "true"],
+            fails: false,
+        },
+        TestCase {
+            description: "Both operands are evaluated before a type error",
+            input: "record(1) + record(\"a\");",
+            log: &["1", "a"],
+            fails: true,
+        },
+        TestCase {
+            description: "Unary operand is evaluated before a type error",
+            input: "-record(\"a\");",
+            log: &["a"],
+            fails: true,
+        },
+        TestCase {
+            description: "Right operand is not evaluated if the left one fails",
+            input: "(-\"a\") + record(1);",
+            log: &[],
+            fails: true,
+        },
+    ]);
+}
+
+#[test]
+fn test_arguments_left_to_right() {
+    check(vec![
+        TestCase {
+            description: "Callee before the arguments",
+            input: "record(record)(record(1));",
+            log: &["<native fn record>", "1", "1"],
+            fails: false,
+        },
+        TestCase {
+            description: "Arguments in order",
+            input: "var f = debug; record(f)(record(1) + record(2));",
+            log: &["<native fn debug>", "1", "2", "integer(3)"],
+            fails: false,
+        },
+        TestCase {
+            description: "All arguments are evaluated before the arity check",
+            input: "debug(record(1), record(2));",
+            log: &["1", "2"],
+            fails: true,
+        },
+        TestCase {
+            description: "List elements in order",
+            input: "record([record(1), record(2)]);",
+            log: &["1", "2", "[1, 2]"],
+            fails: false,
+        },
+        TestCase {
+            description: "Arguments are evaluated before calling a non callable",
+            input: "record(1)(record(2));",
+            log: &["1", "2"],
+            fails: true,
+        },
+    ]);
+}
+
+#[test]
+fn test_assignment_order() {
+    check(vec![
+        TestCase {
+            description: "Value before the target is resolved",
+            input: "undefined = record(1);",
+            log: &["1"],
+            fails: true,
+        },
+        TestCase {
+            description: "Chained assignments from right to left",
+            input: "var a; var b; a = b = record(1); record(a); record(b);",
+            log: &["1", "1", "1"],
+            fails: false,
+        },
+        TestCase {
+            description: "Assignment happens before the rest of the expression",
+            input: "var a = 1; record((a = 2) + a);",
+            log: &["4"],
+            fails: false,
+        },
+        TestCase {
+            description: "Index assignment evaluates all its parts before checking them",
+            input: "record([])[record(0)] = record(1);",
+            log: &["[]", "0", "1"],
+            fails: true,
+        },
+        TestCase {
+            description: "Initializer is evaluated before the variable is defined",
+            input: "var a = record(1); var a = record(a + 1);",
+            log: &["1", "2"],
+            fails: false,
+        },
+    ]);
+}
+
+#[test]
+fn test_short_circuit() {
+    check(vec![
+        TestCase {
+            description: "And skips the right operand",
+            input: "record(false) and record(1);",
+            log: &["false"],
+            fails: false,
+        },
+        TestCase {
+            description: "Or skips the right operand",
+            input: "record(1) or record(2);",
+            log: &["1"],
+            fails: false,
+        },
+        TestCase {
+            description: "Right operands are evaluated when needed",
+            input: "record(nil) or record(true) and record(2);",
+            log: &["nil", "true", "2"],
+            fails: false,
+        },
+        TestCase {
+            description: "Skipped operands cannot fail",
+            input: "false and -\"a\"; true or undefined;",
+            log: &[],
+            fails: false,
+        },
+    ]);
+}
+
+#[test]
+fn test_statement_order() {
+    check(vec![
+        TestCase {
+            description: "Print evaluates all the values before writing",
+            input: "print record(1), record(2);",
+            log: &["1", "2", "1 2"],
+            fails: false,
+        },
+        TestCase {
+            description: "If evaluates only the taken branch",
+            input: "if (record(nil)) record(1); else record(2);",
+            log: &["nil", "2"],
+            fails: false,
+        },
+        TestCase {
+            description: "For evaluates the condition, body and increment in order",
+            input: "for (var i = record(0); record(i < 2); i = record(i + 1)) record(\"body\");",
+            log: &["0", "true", "body", "1", "true", "body", "2", "false"],
+            fails: false,
+        },
+    ]);
+}
@@ -1,19 +1,51 @@
+use super::error::Span;
+use super::{ir, stmt};
 use crate::lexer::token::Token;
 use crate::lexer::types::TokenType;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Expr {
-    Unary(Token, Box<Expr>),
-    Binary(Box<Expr>, Token, Box<Expr>),
+    Unary(Operator, Box<Expr>),
+    Binary(Box<Expr>, Operator, Box<Expr>),
     Grouping(Box<Expr>),
     Literal(Token),
+    Variable(Token),
+    Assign(Token, Box<Expr>),
+    Call(Box<Expr>, Token, Vec<Expr>),
+    Logical(Box<Expr>, Operator, Box<Expr>),
+    List(Token, Vec<Expr>),
+    Index(Box<Expr>, Token, Box<Expr>),
+    IndexSet(Box<Expr>, Token, Box<Expr>, Box<Expr>),
+    Map(Token, Vec<(Expr, Expr)>),
+    Tuple(Token, Vec<Expr>),
+    Get(Box<Expr>, Token),
+    Spread(Token, Box<Expr>),
+    Block(Token, BlockBody, Option<Box<Expr>>),
 }
 
 pub trait Visitor<R> {
-    fn visit_unary_expr(&self, op: &Token, expr: &Box<Expr>) -> R;
-    fn visit_binary_expr(&self, left: &Box<Expr>, op: &Token, right: &Box<Expr>) -> R;
+    fn visit_unary_expr(&self, op: &Operator, expr: &Box<Expr>) -> R;
+    fn visit_binary_expr(&self, left: &Box<Expr>, op: &Operator, right: &Box<Expr>) -> R;
     fn visit_grouping_expr(&self, expr: &Box<Expr>) -> R;
     fn visit_literal_expr(&self, value: &Token) -> R;
+    fn visit_variable_expr(&self, name: &Token) -> R;
+    fn visit_assign_expr(&self, name: &Token, value: &Box<Expr>) -> R;
+    fn visit_call_expr(&self, callee: &Box<Expr>, paren: &Token, args: &Vec<Expr>) -> R;
+    fn visit_logical_expr(&self, left: &Box<Expr>, op: &Operator, right: &Box<Expr>) -> R;
+    fn visit_list_expr(&self, bracket: &Token, elements: &Vec<Expr>) -> R;
+    fn visit_index_expr(&self, object: &Box<Expr>, bracket: &Token, index: &Box<Expr>) -> R;
+    fn visit_index_set_expr(
+        &self,
+        object: &Box<Expr>,
+        bracket: &Token,
+        index: &Box<Expr>,
+        value: &Box<Expr>,
+    ) -> R;
+    fn visit_map_expr(&self, brace: &Token, entries: &Vec<(Expr, Expr)>) -> R;
+    fn visit_tuple_expr(&self, paren: &Token, elements: &Vec<Expr>) -> R;
+    fn visit_get_expr(&self, object: &Box<Expr>, name: &Token) -> R;
+    fn visit_spread_expr(&self, ellipsis: &Token, expr: &Box<Expr>) -> R;
+    fn visit_block_expr(&self, brace: &Token, body: &BlockBody, value: &Option<Box<Expr>>) -> R;
 }
 
 impl Expr {
@@ -23,36 +55,164 @@ impl Expr {
             Expr::Binary(left, op, right) => visitor.visit_binary_expr(left, op, right),
             Expr::Grouping(expr) => visitor.visit_grouping_expr(expr),
             Expr::Literal(value) => visitor.visit_literal_expr(value),
+            Expr::Variable(name) => visitor.visit_variable_expr(name),
+            Expr::Assign(name, value) => visitor.visit_assign_expr(name, value),
+            Expr::Call(callee, paren, args) => visitor.visit_call_expr(callee, paren, args),
+            Expr::Logical(left, op, right) => visitor.visit_logical_expr(left, op, right),
+            Expr::List(bracket, elements) => visitor.visit_list_expr(bracket, elements),
+            Expr::Index(object, bracket, index) => visitor.visit_index_expr(object, bracket, index),
+            Expr::IndexSet(object, bracket, index, value) => {
+                visitor.visit_index_set_expr(object, bracket, index, value)
+            }
+            Expr::Map(brace, entries) => visitor.visit_map_expr(brace, entries),
+            Expr::Tuple(paren, elements) => visitor.visit_tuple_expr(paren, elements),
+            Expr::Get(object, name) => visitor.visit_get_expr(object, name),
+            Expr::Spread(ellipsis, expr) => visitor.visit_spread_expr(ellipsis, expr),
+            Expr::Block(brace, body, value) => visitor.visit_block_expr(brace, body, value),
         }
     }
 }
 
 // Custom implementations for the Expr enum.
+
+/// The statements of a block expression. They are parsed as statements of the surface language,
+/// and lowered into the IR along with the statements around the expression.
+#[derive(Debug, Clone)]
+pub enum BlockBody {
+    Surface(Vec<stmt::Stmt>),
+    Lowered(Vec<ir::Stmt>),
+}
+
+impl BlockBody {
+    /// The lowered statements, which are the only ones that can be evaluated.
+    pub fn lowered(&self) -> &Vec<ir::Stmt> {
+        match self {
+            BlockBody::Lowered(stmts) => stmts,
+            BlockBody::Surface(_) => unreachable!("Block expressions are lowered before they run"),
+        }
+    }
+}
+
+/// The operators of unary, binary and logical expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Bang,
+    Typeof,
+    Minus,
+    Plus,
+    Star,
+    Slash,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    EqualEqual,
+    BangEqual,
+    Is,
+    And,
+    Or,
+}
+
+impl Op {
+    /// Returns the operator written as the token type, or `None` if it is not an operator.
+    pub fn from_token_type(token_type: &TokenType) -> Option<Op> {
+        match token_type {
+            TokenType::Bang => Some(Op::Bang),
+            TokenType::TYPEOF => Some(Op::Typeof),
+            TokenType::Minus => Some(Op::Minus),
+            TokenType::Plus => Some(Op::Plus),
+            TokenType::Star => Some(Op::Star),
+            TokenType::Slash => Some(Op::Slash),
+            TokenType::Greater => Some(Op::Greater),
+            TokenType::GreaterEqual => Some(Op::GreaterEqual),
+            TokenType::Less => Some(Op::Less),
+            TokenType::LessEqual => Some(Op::LessEqual),
+            TokenType::EqualEqual => Some(Op::EqualEqual),
+            TokenType::BangEqual => Some(Op::BangEqual),
+            TokenType::IS => Some(Op::Is),
+            TokenType::AND => Some(Op::And),
+            TokenType::OR => Some(Op::Or),
+            _ => None,
+        }
+    }
+
+    /// Returns the operator as it is written in the source code.
+    pub fn lexeme(self) -> &'static str {
+        match self {
+            Op::Bang => "!",
+            Op::Typeof => "typeof",
+            Op::Minus => "-",
+            Op::Plus => "+",
+            Op::Star => "*",
+            Op::Slash => "/",
+            Op::Greater => ">",
+            Op::GreaterEqual => ">=",
+            Op::Less => "<",
+            Op::LessEqual => "<=",
+            Op::EqualEqual => "==",
+            Op::BangEqual => "!=",
+            Op::Is => "is",
+            Op::And => "and",
+            Op::Or => "or",
+        }
+    }
+}
+
+/// The operator of an expression along with where it was written, which is all the expression
+/// needs from its token. Unlike tokens, operators are small and can be copied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Operator {
+    pub op: Op,
+    pub span: Span,
+}
+
+impl Operator {
+    /// Creates the operator written as the token, which must be an operator.
+    pub fn new(token: &Token) -> Self {
+        Operator {
+            op: Op::from_token_type(&token.token_type)
+                .expect("Operators should only be created from operator tokens"),
+            span: Span::from(token),
+        }
+    }
+
+    pub fn lexeme(&self) -> &'static str {
+        self.op.lexeme()
+    }
+}
+
 impl Expr {
     /// Creates a new unary expression with the given operator and expression.
+    #[cfg(test)]
     pub fn new_string_literal(value: &str) -> Expr {
         Expr::Literal(Token::new_default(
-            TokenType::String(value.to_string()),
+            crate::lexer::types::TokenType::String(value.to_string()),
             value,
         ))
     }
 
     /// Creates a new unary expression with the given operator and expression.
-    pub fn new_number_literal(value: f32) -> Expr {
+    #[cfg(test)]
+    pub fn new_number_literal(value: f64) -> Expr {
         Expr::Literal(Token::new_default(
-            TokenType::Number(value),
+            crate::lexer::types::TokenType::Number(value),
             &value.to_string(),
         ))
     }
 
     /// Creates a new unary expression with the given operator and expression.
     pub fn new_binary_expr(expr1: Expr, op: Token, expr2: Expr) -> Expr {
-        Expr::Binary(Box::new(expr1), op, Box::new(expr2))
+        Expr::Binary(Box::new(expr1), Operator::new(&op), Box::new(expr2))
+    }
+
+    /// Creates a new logical expression with the given operator and expressions.
+    pub fn new_logical_expr(left: Expr, op: Token, right: Expr) -> Expr {
+        Expr::Logical(Box::new(left), Operator::new(&op), Box::new(right))
     }
 
     /// Creates a new unary expression with the given operator and expression.
     pub fn new_unary_expr(op: Token, expr: Expr) -> Expr {
-        Expr::Unary(op, Box::new(expr))
+        Expr::Unary(Operator::new(&op), Box::new(expr))
     }
 
     /// Creates a new unary expression with the given operator and expression.
@@ -60,3 +220,25 @@ impl Expr {
         Expr::Grouping(Box::new(expr))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operators() {
+        let tokens = crate::lexer::lexer::Lexer::new("! typeof - + * / > >= < <= == != is and or")
+            .get_tokens();
+
+        // Every operator is written the same way as the token it was parsed from
+        for token in tokens
+            .iter()
+            .filter(|token| token.token_type != TokenType::EOF)
+        {
+            let operator = Operator::new(token);
+            assert_eq!(operator.lexeme(), token.lexeme);
+            assert_eq!(operator.span, Span::from(token));
+        }
+        assert_eq!(Op::from_token_type(&TokenType::Equal), None);
+    }
+}
@@ -0,0 +1,252 @@
+use std::io::sink;
+
+use super::error::Span;
+use super::expr::{Expr, Op};
+use super::interpreter::Interpreter;
+use super::printer::AstPrinter;
+use super::stmt::Stmt;
+use super::transform::{walk_expr, Transformer};
+use super::value::Value;
+use crate::config::Config;
+use crate::lexer::token::Token;
+use crate::lexer::types::TokenType;
+
+/// An expression that was replaced by its value before the program was run.
+#[derive(Debug, PartialEq)]
+pub struct Folded {
+    pub line: usize,
+    /// The expression as printed by the `AstPrinter`, before it was evaluated.
+    pub expression: String,
+    pub value: String,
+}
+
+impl Folded {
+    pub fn report(&self) -> String {
+        format!(
+            "[line {}] Pre-evaluated {} to {}",
+            self.line, self.expression, self.value
+        )
+    }
+}
+
+/// Evaluates the constant expressions of the program ahead of time, so that they are computed
+/// once instead of every time they are reached, such as the initializers of configuration
+/// variables (`var timeout = 60 * 60 * 1000;`). An expression is constant when it is only made
+/// of literals and operators, which can not have side effects. The expressions whose evaluation
+/// fails are left as they are, so that the error is still reported when they are reached.
+pub struct Folder {
+    /// Evaluates the constant expressions with the configuration of the program, as the flags
+    /// change the behavior of some operators.
+    interpreter: Interpreter,
+    folded: Vec<Folded>,
+}
+
+impl Folder {
+    /// Returns a literal of the value of the expression if it is constant.
+    fn fold(&self, expr: &Expr) -> Option<Token> {
+        let span = match expr {
+            Expr::Unary(op, operand) if is_literal(operand) => op.span,
+            Expr::Binary(left, op, right) | Expr::Logical(left, op, right)
+                if is_literal(left) && is_literal(right) =>
+            {
+                op.span
+            }
+            Expr::Grouping(inner) => match &**inner {
+                Expr::Literal(token) => Span::from(token),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        if is_long_repetition(expr) {
+            return None;
+        }
+
+        let value = self.interpreter.evaluate(expr).ok()?;
+        literal(span, &value)
+    }
+}
+
+impl Transformer for Folder {
+    fn transform_expr(&mut self, expr: Expr) -> Expr {
+        let start = self.folded.len();
+        let expression = match &expr {
+            Expr::Unary(..) | Expr::Binary(..) | Expr::Logical(..) | Expr::Grouping(..) => {
+                AstPrinter::print(&expr)
+            }
+            _ => String::new(),
+        };
+
+        let expr = walk_expr(self, expr);
+        match self.fold(&expr) {
+            Some(literal) => {
+                // Only the outermost constant expression is reported, rather than each of its parts
+                self.folded.truncate(start);
+                self.folded.push(Folded {
+                    line: literal.line,
+                    expression,
+                    value: literal.lexeme.clone(),
+                });
+                Expr::Literal(literal)
+            }
+            None => expr,
+        }
+    }
+}
+
+fn is_literal(expr: &Expr) -> bool {
+    matches!(expr, Expr::Literal(_))
+}
+
+/// The length in bytes of the longest string that repeating a string literal is folded into.
+/// Longer strings are left to the runtime, so that the program does not hold them, nor allocate
+/// them before it runs if they are never reached.
+const MAX_FOLDED_LENGTH: f64 = 1024.0;
+
+/// Returns true if the expression repeats a string literal into a string that is too long to be
+/// folded.
+fn is_long_repetition(expr: &Expr) -> bool {
+    let Expr::Binary(left, op, right) = expr else {
+        return false;
+    };
+    let (Expr::Literal(text), Op::Star, Expr::Literal(count)) = (&**left, op.op, &**right) else {
+        return false;
+    };
+
+    let count = match count.token_type {
+        TokenType::Integer(integer) => integer as f64,
+        TokenType::Number(num) => num,
+        _ => return false,
+    };
+    match &text.token_type {
+        TokenType::String(text) => text.len() as f64 * count > MAX_FOLDED_LENGTH,
+        _ => false,
+    }
+}
+
+/// Returns a literal token for the value, positioned at the provided span, or `None` if the
+/// value can not be written as a literal.
+fn literal(span: Span, value: &Value) -> Option<Token> {
+    let token_type = match value {
+        Value::Number(num) => TokenType::Number(*num),
+        Value::Integer(integer) => TokenType::Integer(*integer),
+        Value::String(str) => TokenType::String(str.clone()),
+        Value::Boolean(true) => TokenType::TRUE,
+        Value::Boolean(false) => TokenType::FALSE,
+        Value::Nil => TokenType::NIL,
+        _ => return None,
+    };
+
+    let lexeme = match value {
+        Value::String(str) => format!("\"{}\"", str),
+        value => value.to_string(),
+    };
+    Some(span.synthesize(token_type, &lexeme))
+}
+
+/// Evaluates the constant expressions of the program, and returns the program along with the
+/// expressions that were evaluated.
+pub fn fold(stmts: Vec<Stmt>, config: &Config) -> (Vec<Stmt>, Vec<Folded>) {
+    let mut folder = Folder {
+        interpreter: Interpreter::with_output(config.clone(), Box::new(sink())),
+        folded: Vec::new(),
+    };
+
+    let stmts = folder.transform_stmts(stmts);
+    (stmts, folder.folded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::desugar::lower;
+    use crate::ast::syntax_tree::SyntaxTree;
+    use crate::lexer::lexer::Lexer;
+
+    /// Folds the program, and returns the lowered program along with the reports.
+    fn fold_source(source: &str, config: Config) -> (String, Vec<String>) {
+        let tokens = Lexer::with_config(source, config.clone()).get_tokens();
+        let (stmts, folded) = fold(SyntaxTree::new(tokens).parse().unwrap(), &config);
+
+        (
+            AstPrinter::print_stmts(&lower(stmts)),
+            folded.iter().map(Folded::report).collect(),
+        )
+    }
+
+    #[test]
+    fn test_fold() {
+        let test_cases = vec![
+            (
+                "var a = 60 * 60 * 1000;",
+                "(var a 3600000.0)",
+                vec!["[line 1] Pre-evaluated (* (* 60.0 60.0) 1000.0) to 3600000"],
+            ),
+            (
+                "print -(1 + 2) / 2, \"a\" + \"b\";",
+                "(print -1.5 ab)",
+                vec![
+                    "[line 1] Pre-evaluated (/ (- (group (+ 1.0 2.0))) 2.0) to -1.5",
+                    "[line 1] Pre-evaluated (+ a b) to \"ab\"",
+                ],
+            ),
+            (
+                "var b = !(1 < 2) or nil == nil;",
+                "(var b true)",
+                vec!["[line 1] Pre-evaluated (or (! (group (< 1.0 2.0))) (== nil nil)) to true"],
+            ),
+            // Only the constant parts of an expression are evaluated
+            (
+                "var c = x + 2 * 3;",
+                "(var c (+ x 6.0))",
+                vec!["[line 1] Pre-evaluated (* 2.0 3.0) to 6"],
+            ),
+            // Expressions with side effects or errors are left to the runtime
+            ("debug(1) + 1;", "(+ (call debug 1.0) 1.0)", vec![]),
+            ("print -\"a\";", "(print (- a))", vec![]),
+            (
+                "print [1 + 1];",
+                "(print (list 2.0))",
+                vec!["[line 1] Pre-evaluated (+ 1.0 1.0) to 2"],
+            ),
+            // The statements of block expressions are folded like the others
+            (
+                "var x = { print 1 + 2; 0 };",
+                "(var x (block-expr (print 3.0) 0.0))",
+                vec!["[line 1] Pre-evaluated (+ 1.0 2.0) to 3"],
+            ),
+        ];
+
+        for (source, expected, reports) in test_cases {
+            let (output, folded) = fold_source(source, Config::default());
+            assert_eq!(output, expected, "{}", source);
+            assert_eq!(folded, reports, "{}", source);
+        }
+
+        // The operators behave as configured for the program
+        let config = Config {
+            string_repetition: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            fold_source("print \"ab\" * 2;", config.clone()).0,
+            "(print abab)"
+        );
+        // Long repetitions are left to the runtime, which reports the counts that are too large
+        assert_eq!(
+            fold_source(
+                "if (false) print \"ab\" * 9223372036854775807;",
+                config.clone()
+            )
+            .1,
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            fold_source("print \"ab\" * 1000;", config).0,
+            "(print (* ab 1000.0))"
+        );
+        assert_eq!(
+            fold_source("print \"ab\" * 2;", Config::default()).0,
+            "(print (* ab 2.0))"
+        );
+    }
+}
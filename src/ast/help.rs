@@ -0,0 +1,528 @@
+/// An entry of the built-in reference, describing a native, a keyword or an operator.
+#[derive(Debug, PartialEq)]
+pub struct HelpEntry {
+    pub name: &'static str,
+    pub signature: &'static str,
+    pub description: &'static str,
+}
+
+impl HelpEntry {
+    /// Returns the signature and the description of the entry on a single line.
+    pub fn describe(&self) -> String {
+        format!("{}: {}", self.signature, self.description)
+    }
+}
+
+/// The reference of the natives, keywords and operators, shown by `help` and `:help`.
+pub const REFERENCE: &[HelpEntry] = &[
+    // Natives
+    HelpEntry {
+        name: "clock",
+        signature: "clock()",
+        description: "Returns the number of seconds since the Unix epoch, as a number with a fractional part.",
+    },
+    HelpEntry {
+        name: "debug",
+        signature: "debug(value)",
+        description: "Prints a developer oriented representation of the value, tagged with its type.",
+    },
+    HelpEntry {
+        name: "exit",
+        signature: "exit(status)",
+        description: "Ends the program with the exit status, from 0 to 255. Not caught by try statements.",
+    },
+    HelpEntry {
+        name: "help",
+        signature: "help(name)",
+        description: "Prints the signature and description of a native, keyword or operator.",
+    },
+    HelpEntry {
+        name: "log",
+        signature: "log.debug(message), log.info(message), log.warn(message), log.error(message)",
+        description: "Logs the message with the level, if it is at least the one set with --log-level.",
+    },
+    HelpEntry {
+        name: "print",
+        signature: "print a, b, ...; or print(a, b, ...)",
+        description: "Prints the values separated by the print separator. A function with --print-function.",
+    },
+    HelpEntry {
+        name: "printf",
+        signature: "printf(format, a, b, ...)",
+        description: "Prints the values formatted like by format, followed by a newline.",
+    },
+    HelpEntry {
+        name: "readLine",
+        signature: "readLine()",
+        description: "Reads a line of the standard input and returns it without its line ending, or nil at the end of the input.",
+    },
+    HelpEntry {
+        name: "source",
+        signature: "source(fn)",
+        description: "Returns the source of the declaration of a function or class, or nil for natives and other values.",
+    },
+    HelpEntry {
+        name: "stacktrace",
+        signature: "stacktrace()",
+        description: "Returns the frames of the call stack as a list of (function, line) tuples, innermost first.",
+    },
+    // Math natives
+    HelpEntry {
+        name: "abs",
+        signature: "abs(x)",
+        description: "Returns the absolute value of the number, an integer if the number is one.",
+    },
+    HelpEntry {
+        name: "ceil",
+        signature: "ceil(x)",
+        description: "Rounds the number up to an integer.",
+    },
+    HelpEntry {
+        name: "floor",
+        signature: "floor(x)",
+        description: "Rounds the number down to an integer.",
+    },
+    HelpEntry {
+        name: "max",
+        signature: "max(a, b)",
+        description: "Returns the largest of the two numbers.",
+    },
+    HelpEntry {
+        name: "min",
+        signature: "min(a, b)",
+        description: "Returns the smallest of the two numbers.",
+    },
+    HelpEntry {
+        name: "pow",
+        signature: "pow(base, exponent)",
+        description: "Raises the base to the exponent, as a float.",
+    },
+    HelpEntry {
+        name: "sqrt",
+        signature: "sqrt(x)",
+        description: "Returns the square root of the number, which is NaN for negative numbers.",
+    },
+    // String natives
+    HelpEntry {
+        name: "contains",
+        signature: "contains(text, part)",
+        description: "Returns true if the part appears in the text.",
+    },
+    HelpEntry {
+        name: "format",
+        signature: "format(format, a, b, ...)",
+        description: "Replaces each {} of the format with the next value as it is printed. {{ and }} are literal braces.",
+    },
+    HelpEntry {
+        name: "len",
+        signature: "len(value)",
+        description: "Returns the number of characters of a string, of elements of a list or tuple, or of entries of a map.",
+    },
+    HelpEntry {
+        name: "replace",
+        signature: "replace(text, pattern, replacement)",
+        description: "Replaces every occurrence of the pattern in the text.",
+    },
+    HelpEntry {
+        name: "split",
+        signature: "split(text, separator)",
+        description: "Splits the text around the separator into a list of strings, or into its characters if the separator is empty.",
+    },
+    HelpEntry {
+        name: "substring",
+        signature: "substring(text, start, end)",
+        description: "Returns the characters of the text from the start index up to the end index, excluded.",
+    },
+    HelpEntry {
+        name: "toLower",
+        signature: "toLower(text)",
+        description: "Returns the text in lowercase.",
+    },
+    HelpEntry {
+        name: "toUpper",
+        signature: "toUpper(text)",
+        description: "Returns the text in uppercase.",
+    },
+    HelpEntry {
+        name: "trim",
+        signature: "trim(text)",
+        description: "Returns the text without the whitespace at its start and end.",
+    },
+    // List natives
+    HelpEntry {
+        name: "push",
+        signature: "push(list, value)",
+        description: "Appends the value to the end of the list.",
+    },
+    HelpEntry {
+        name: "pop",
+        signature: "pop(list)",
+        description: "Removes the last element of the list and returns it, or nil if the list is empty.",
+    },
+    HelpEntry {
+        name: "insert",
+        signature: "insert(list, index, value)",
+        description: "Inserts the value at the index of the list, which can be its length to append it.",
+    },
+    HelpEntry {
+        name: "remove",
+        signature: "remove(collection, key)",
+        description: "Removes the element at the index of a list, or the key of a map, and returns its value.",
+    },
+    HelpEntry {
+        name: "map",
+        signature: "map(list, fn)",
+        description: "Returns a new list with the results of calling the function with each element.",
+    },
+    HelpEntry {
+        name: "filter",
+        signature: "filter(list, fn)",
+        description: "Returns a new list with the elements for which the function returns a truthy value.",
+    },
+    HelpEntry {
+        name: "reduce",
+        signature: "reduce(list, fn, initial)",
+        description: "Combines the elements from the first one by calling fn(result, element), starting from the initial value.",
+    },
+    // Map natives
+    HelpEntry {
+        name: "keys",
+        signature: "keys(map)",
+        description: "Returns a list of the keys of the map, in the order they were inserted.",
+    },
+    HelpEntry {
+        name: "values",
+        signature: "values(map)",
+        description: "Returns a list of the values of the map, in the order of their keys.",
+    },
+    HelpEntry {
+        name: "has",
+        signature: "has(map, key)",
+        description: "Returns true if the map has the key, even if its value is nil.",
+    },
+    // Time natives
+    HelpEntry {
+        name: "now",
+        signature: "now()",
+        description: "Returns the number of milliseconds since the Unix epoch, as an integer.",
+    },
+    HelpEntry {
+        name: "formatTime",
+        signature: "formatTime(millis, format)",
+        description: "Formats the time in milliseconds since the Unix epoch in UTC, with the specifiers %Y %m %d %H %M %S %L and %%.",
+    },
+    // System natives
+    HelpEntry {
+        name: "readFile",
+        signature: "readFile(path)",
+        description: "Returns the contents of the text file at the path. Only defined with --no-sandbox.",
+    },
+    HelpEntry {
+        name: "writeFile",
+        signature: "writeFile(path, contents)",
+        description: "Writes the string to the file at the path, replacing it if it exists. Only defined with --no-sandbox.",
+    },
+    HelpEntry {
+        name: "getEnv",
+        signature: "getEnv(name)",
+        description: "Returns the value of the environment variable, or nil if it is not set. Only defined with --no-sandbox.",
+    },
+    // Type natives
+    HelpEntry {
+        name: "num",
+        signature: "num(text)",
+        description: "Parses the text as a number literal with an optional sign, or returns nil if it is not one.",
+    },
+    HelpEntry {
+        name: "str",
+        signature: "str(value)",
+        description: "Returns the value as it is printed.",
+    },
+    HelpEntry {
+        name: "type",
+        signature: "type(value)",
+        description: "Returns the kind of the value as tested by is, such as \"Number\" or \"List\".",
+    },
+    // Keywords
+    HelpEntry {
+        name: "and",
+        signature: "a and b",
+        description: "Evaluates to a if it is falsy, otherwise to b. b is only evaluated if needed.",
+    },
+    HelpEntry {
+        name: "assert",
+        signature: "assert condition, message;",
+        description: "Stops the program with an error quoting the condition if it is falsy. The message is optional.",
+    },
+    HelpEntry {
+        name: "break",
+        signature: "break [label];",
+        description: "Exits the innermost loop, or the enclosing loop with the label.",
+    },
+    HelpEntry {
+        name: "case",
+        signature: "case a, b: body",
+        description: "An arm of a switch statement, whose body runs if the subject equals one of the literals.",
+    },
+    HelpEntry {
+        name: "catch",
+        signature: "try { body } catch (name) { handler }",
+        description: "Runs the handler with the value thrown in the body, or the message of its runtime error, bound to the name.",
+    },
+    HelpEntry {
+        name: "class",
+        signature: "class Name { ... }",
+        description: "Reserved for class declarations.",
+    },
+    HelpEntry {
+        name: "const",
+        signature: "const name = value;",
+        description: "Declares a constant in the current scope, which is a runtime error to assign.",
+    },
+    HelpEntry {
+        name: "continue",
+        signature: "continue [label];",
+        description: "Skips to the next iteration of the innermost loop, or of the enclosing loop with the label.",
+    },
+    HelpEntry {
+        name: "default",
+        signature: "default: body",
+        description: "The last arm of a switch statement, whose body runs if no case matches.",
+    },
+    HelpEntry {
+        name: "do",
+        signature: "do body while (condition);",
+        description: "Runs the body, then runs it again while the condition is truthy.",
+    },
+    HelpEntry {
+        name: "else",
+        signature: "if (condition) a else b",
+        description: "The branch of an if statement that runs when the condition is falsy.",
+    },
+    HelpEntry {
+        name: "false",
+        signature: "false",
+        description: "The false boolean value.",
+    },
+    HelpEntry {
+        name: "finally",
+        signature: "try { body } finally { cleanup }",
+        description: "Runs the cleanup after the body and the catch handler, however they end.",
+    },
+    HelpEntry {
+        name: "for",
+        signature: "for (init; condition; increment) body",
+        description: "Runs the initializer, then the body and the increment while the condition is truthy.",
+    },
+    HelpEntry {
+        name: "fun",
+        signature: "fun name(params) { ... }",
+        description: "Reserved for function declarations.",
+    },
+    HelpEntry {
+        name: "if",
+        signature: "if (condition) a [else b]",
+        description: "Runs a if the condition is truthy, otherwise b.",
+    },
+    HelpEntry {
+        name: "in",
+        signature: "for (name in value) body",
+        description: "Runs the body for each element of a list, tuple or string, or each key of a map.",
+    },
+    HelpEntry {
+        name: "typeof",
+        signature: "typeof value",
+        description: "Returns the kind of the value in lowercase, such as \"number\", \"string\" or \"nil\".",
+    },
+    HelpEntry {
+        name: "is",
+        signature: "value is Type",
+        description: "Tests the kind of a value: Boolean, Function, List, Map, Nil, Number, String or Tuple.",
+    },
+    HelpEntry {
+        name: "nil",
+        signature: "nil",
+        description: "The absence of a value.",
+    },
+    HelpEntry {
+        name: "or",
+        signature: "a or b",
+        description: "Evaluates to a if it is truthy, otherwise to b. b is only evaluated if needed.",
+    },
+    HelpEntry {
+        name: "return",
+        signature: "return [value];",
+        description: "Reserved for returning from functions.",
+    },
+    HelpEntry {
+        name: "super",
+        signature: "super.method",
+        description: "Reserved for accessing the methods of a superclass.",
+    },
+    HelpEntry {
+        name: "switch",
+        signature: "switch (subject) { case a: body ... default: body }",
+        description: "Runs the body of the first case with a literal equal to the subject, or the default body. Cases do not fall through.",
+    },
+    HelpEntry {
+        name: "this",
+        signature: "this",
+        description: "Reserved for accessing the instance in a method.",
+    },
+    HelpEntry {
+        name: "throw",
+        signature: "throw value;",
+        description: "Stops the program with the value, unless an enclosing try statement catches it.",
+    },
+    HelpEntry {
+        name: "true",
+        signature: "true",
+        description: "The true boolean value.",
+    },
+    HelpEntry {
+        name: "try",
+        signature: "try { body } catch (name) { handler } finally { cleanup }",
+        description: "Runs the body, catching its thrown values and runtime errors with the handler. Either clause can be omitted, but not both.",
+    },
+    HelpEntry {
+        name: "var",
+        signature: "var name [= value];",
+        description: "Declares a variable in the current scope, initialized to nil if no value is given.",
+    },
+    HelpEntry {
+        name: "while",
+        signature: "while (condition) body",
+        description: "Runs the body while the condition is truthy.",
+    },
+    // Operators
+    HelpEntry {
+        name: "+",
+        signature: "a + b",
+        description: "Adds two numbers or concatenates two strings, or a string and a number with --conversions=lenient.",
+    },
+    HelpEntry {
+        name: "-",
+        signature: "a - b or -a",
+        description: "Subtracts two numbers, or negates a number.",
+    },
+    HelpEntry {
+        name: "*",
+        signature: "a * b",
+        description: "Multiplies two numbers, or repeats a string with --string-repetition.",
+    },
+    HelpEntry {
+        name: "/",
+        signature: "a / b",
+        description: "Divides two numbers.",
+    },
+    HelpEntry {
+        name: "!",
+        signature: "!a",
+        description: "Evaluates to true if a is falsy, otherwise to false.",
+    },
+    HelpEntry {
+        name: "==",
+        signature: "a == b",
+        description: "Evaluates to true if the values are equal. Lists and maps are equal only to themselves.",
+    },
+    HelpEntry {
+        name: "!=",
+        signature: "a != b",
+        description: "Evaluates to true if the values are not equal.",
+    },
+    HelpEntry {
+        name: "<",
+        signature: "a < b",
+        description: "Compares two numbers, or two strings with --string-comparison.",
+    },
+    HelpEntry {
+        name: "<=",
+        signature: "a <= b",
+        description: "Compares two numbers, or two strings with --string-comparison.",
+    },
+    HelpEntry {
+        name: ">",
+        signature: "a > b",
+        description: "Compares two numbers, or two strings with --string-comparison.",
+    },
+    HelpEntry {
+        name: ">=",
+        signature: "a >= b",
+        description: "Compares two numbers, or two strings with --string-comparison.",
+    },
+    HelpEntry {
+        name: "=",
+        signature: "name = value",
+        description: "Assigns the value to a variable, list element or map key, and evaluates to it.",
+    },
+    HelpEntry {
+        name: "[]",
+        signature: "[a, b, ...] or xs[index]",
+        description: "Creates a list, or reads the element of a list or the key of a map.",
+    },
+    HelpEntry {
+        name: "{}",
+        signature: "{key: value, ...} or { statements; value }",
+        description: "Creates a map or evaluates a block expression in expression position, or opens a block at the start of a statement.",
+    },
+    HelpEntry {
+        name: "...",
+        signature: "[...xs] or f(...args)",
+        description: "Expands a list or tuple into the elements of a list or the arguments of a call.",
+    },
+    HelpEntry {
+        name: ".",
+        signature: "map.key",
+        description: "Reads the string key of a map, like map[\"key\"].",
+    },
+];
+
+/// Returns the reference entry with the provided name.
+pub fn lookup(name: &str) -> Option<&'static HelpEntry> {
+    REFERENCE.iter().find(|entry| entry.name == name)
+}
+
+/// Returns the help text for the provided name, which lists all the names with an entry
+/// if the name is unknown.
+pub fn help_text(name: &str) -> String {
+    match lookup(name) {
+        Some(entry) => entry.describe(),
+        None => format!(
+            "No help for '{}'. Help is available for: {}",
+            name,
+            REFERENCE
+                .iter()
+                .map(|entry| entry.name)
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup("debug").unwrap().signature, "debug(value)");
+        assert_eq!(lookup("<=").unwrap().signature, "a <= b");
+        assert_eq!(lookup("missing"), None);
+
+        // Every entry is described, and every keyword has an entry
+        for entry in REFERENCE {
+            assert!(!entry.description.is_empty(), "{}", entry.name);
+        }
+        for keyword in crate::lexer::types::KEYWORDS {
+            assert!(lookup(keyword).is_some(), "{}", keyword);
+        }
+    }
+
+    #[test]
+    fn test_help_text() {
+        assert_eq!(help_text("/"), "a / b: Divides two numbers.");
+        assert!(help_text("missing").starts_with(
+            "No help for 'missing'. Help is available for: clock debug exit help log"
+        ));
+    }
+}
@@ -0,0 +1,568 @@
+use std::collections::HashSet;
+
+use super::error::Span;
+use super::expr::{BlockBody, Expr, Op};
+use super::ir::Stmt;
+use super::transform::{walk_expr, Transformer};
+use crate::lexer::token::Token;
+use crate::lexer::types::TokenType;
+
+/// Moves the loop invariant expressions out of the loops of a lowered program, so that they are
+/// evaluated once before the loop instead of on every iteration. The expressions are stored in
+/// variables that can not be named by the user:
+///
+/// ```text
+/// while (i < n * 2) body    =>    { var $invariant0 = n * 2; while (i < $invariant0) body }
+/// ```
+///
+/// An expression is invariant when it is only made of literals, operators and variables that are
+/// neither assigned nor declared anywhere in the loop. Calls, indexing and collection literals are
+/// never hoisted, as their result can change or has an identity.
+///
+/// Hoisting must not change which error a program reports, or whether it reports one at all. The
+/// condition of a loop is evaluated as soon as the loop is reached, so any of its invariant parts
+/// can be hoisted, as long as they are evaluated on every check (not on the right of `and` or
+/// `or`) and everything evaluated before them can not fail. Elsewhere in the loop, only the
+/// invariant expressions that can not fail are hoisted, such as comparisons for equality.
+pub struct Hoister {
+    /// The variables declared in the enclosing scopes, which can always be read without an error.
+    scopes: Vec<HashSet<String>>,
+    /// The number of variables created so far, which keeps their names unique in the program.
+    count: usize,
+}
+
+/// The invariant expressions of the loop that is being optimized.
+struct Invariants {
+    /// The variables assigned or declared in the loop, which expressions can not depend on.
+    variant: HashSet<String>,
+    hoisted: Vec<Stmt>,
+}
+
+impl Hoister {
+    pub fn new() -> Self {
+        Hoister {
+            scopes: vec![HashSet::new()],
+            count: 0,
+        }
+    }
+
+    /// Hoists the loop invariant expressions of the statements. The declarations of the
+    /// statements are remembered, so that a program can be hoisted in several parts.
+    pub fn hoist_stmts(&mut self, stmts: Vec<Stmt>) -> Vec<Stmt> {
+        stmts
+            .into_iter()
+            .map(|stmt| self.hoist_stmt(stmt))
+            .collect()
+    }
+
+    fn hoist_stmt(&mut self, stmt: Stmt) -> Stmt {
+        match stmt {
+            Stmt::Expression(expr) => Stmt::Expression(self.transform_expr(expr)),
+            Stmt::Print(exprs) => Stmt::Print(
+                exprs
+                    .into_iter()
+                    .map(|expr| self.transform_expr(expr))
+                    .collect(),
+            ),
+            Stmt::Assert(keyword, condition, text, message) => Stmt::Assert(
+                keyword,
+                self.transform_expr(condition),
+                text,
+                message.map(|message| self.transform_expr(message)),
+            ),
+            Stmt::Var(name, initializer) => {
+                let initializer = initializer.map(|initializer| self.transform_expr(initializer));
+                self.declare(&name);
+                Stmt::Var(name, initializer)
+            }
+            Stmt::Const(name, initializer) => {
+                let initializer = self.transform_expr(initializer);
+                self.declare(&name);
+                Stmt::Const(name, initializer)
+            }
+            Stmt::VarTuple(paren, names, initializer) => {
+                let initializer = self.transform_expr(initializer);
+                names.iter().for_each(|name| self.declare(name));
+                Stmt::VarTuple(paren, names, initializer)
+            }
+            Stmt::Block(stmts) => {
+                self.scopes.push(HashSet::new());
+                let stmts = self.hoist_stmts(stmts);
+                self.scopes.pop();
+                Stmt::Block(stmts)
+            }
+            Stmt::If(condition, then_branch, else_branch) => Stmt::If(
+                self.transform_expr(condition),
+                Box::new(self.hoist_stmt(*then_branch)),
+                else_branch.map(|stmt| Box::new(self.hoist_stmt(*stmt))),
+            ),
+            Stmt::ForIn(name, keyword, iterable, body, label) => {
+                let iterable = self.transform_expr(iterable);
+                self.scopes.push(HashSet::from([name.name().to_string()]));
+                let body = self.hoist_stmt(*body);
+                self.scopes.pop();
+                Stmt::ForIn(name, keyword, iterable, Box::new(body), label)
+            }
+            Stmt::Try(body, catch, finally) => Stmt::Try(
+                self.hoist_scope(body, None),
+                catch.map(|(name, handler)| {
+                    let handler = self.hoist_scope(handler, Some(&name));
+                    (name, handler)
+                }),
+                finally.map(|finally| self.hoist_scope(finally, None)),
+            ),
+            Stmt::While(condition, body, increment, label) => {
+                // Nested loops are optimized first, so that their invariants can move further out
+                let condition = self.transform_expr(condition);
+                let body = self.hoist_stmt(*body);
+                let increment = increment.map(|increment| self.transform_expr(increment));
+                self.hoist_loop(condition, body, increment, label)
+            }
+            Stmt::Throw(keyword, value) => Stmt::Throw(keyword, self.transform_expr(value)),
+            stmt => stmt,
+        }
+    }
+
+    /// Hoists the statements of a block that runs in its own scope, where the name is declared.
+    fn hoist_scope(&mut self, stmts: Vec<Stmt>, name: Option<&Token>) -> Vec<Stmt> {
+        self.scopes.push(HashSet::new());
+        if let Some(name) = name {
+            self.declare(name);
+        }
+        let stmts = self.hoist_stmts(stmts);
+        self.scopes.pop();
+        stmts
+    }
+
+    fn hoist_loop(
+        &mut self,
+        condition: Expr,
+        body: Stmt,
+        increment: Option<Expr>,
+        label: Option<Token>,
+    ) -> Stmt {
+        let mut variant = HashSet::new();
+        variant_in_expr(&condition, &mut variant);
+        variant_in_stmt(&body, &mut variant);
+        if let Some(increment) = &increment {
+            variant_in_expr(increment, &mut variant);
+        }
+
+        let mut invariants = Invariants {
+            variant,
+            hoisted: Vec::new(),
+        };
+        let condition = self.hoist_expr(condition, true, &mut invariants);
+        let body = self.hoist_body(body, &mut invariants);
+
+        let stmt = Stmt::While(condition, Box::new(body), increment, label);
+        match invariants.hoisted.is_empty() {
+            true => stmt,
+            false => Stmt::Block(invariants.hoisted.into_iter().chain([stmt]).collect()),
+        }
+    }
+
+    /// Hoists the invariant expressions of the body that can not fail, as the body may not be
+    /// reached, or only after other statements.
+    fn hoist_body(&mut self, stmt: Stmt, invariants: &mut Invariants) -> Stmt {
+        let mut expr = |expr| self.hoist_expr(expr, false, invariants);
+
+        match stmt {
+            Stmt::Expression(e) => Stmt::Expression(expr(e)),
+            Stmt::Print(exprs) => Stmt::Print(exprs.into_iter().map(expr).collect()),
+            Stmt::Assert(keyword, condition, text, message) => {
+                Stmt::Assert(keyword, expr(condition), text, message.map(expr))
+            }
+            Stmt::Var(name, initializer) => Stmt::Var(name, initializer.map(expr)),
+            Stmt::Const(name, initializer) => Stmt::Const(name, expr(initializer)),
+            Stmt::VarTuple(paren, names, initializer) => {
+                Stmt::VarTuple(paren, names, expr(initializer))
+            }
+            Stmt::Block(stmts) => Stmt::Block(
+                stmts
+                    .into_iter()
+                    .map(|stmt| self.hoist_body(stmt, invariants))
+                    .collect(),
+            ),
+            Stmt::If(condition, then_branch, else_branch) => Stmt::If(
+                expr(condition),
+                Box::new(self.hoist_body(*then_branch, invariants)),
+                else_branch.map(|stmt| Box::new(self.hoist_body(*stmt, invariants))),
+            ),
+            Stmt::ForIn(name, keyword, iterable, body, label) => Stmt::ForIn(
+                name,
+                keyword,
+                self.hoist_expr(iterable, false, invariants),
+                Box::new(self.hoist_body(*body, invariants)),
+                label,
+            ),
+            Stmt::Throw(keyword, value) => Stmt::Throw(keyword, expr(value)),
+            Stmt::Try(body, catch, finally) => {
+                let mut block = |stmts: Vec<Stmt>| {
+                    stmts
+                        .into_iter()
+                        .map(|stmt| self.hoist_body(stmt, invariants))
+                        .collect()
+                };
+                Stmt::Try(
+                    block(body),
+                    catch.map(|(name, handler)| (name, block(handler))),
+                    finally.map(block),
+                )
+            }
+            Stmt::While(condition, body, increment, label) => Stmt::While(
+                self.hoist_expr(condition, false, invariants),
+                Box::new(self.hoist_body(*body, invariants)),
+                increment.map(|expr| self.hoist_expr(expr, false, invariants)),
+                label,
+            ),
+            stmt => stmt,
+        }
+    }
+
+    /// Replaces the invariant parts of the expression with variables. An expression is `reached`
+    /// when it is evaluated as soon as the loop is, before anything that can fail.
+    fn hoist_expr(&mut self, expr: Expr, reached: bool, invariants: &mut Invariants) -> Expr {
+        if self.is_invariant(&expr, &invariants.variant)
+            && !is_trivial(&expr)
+            && (reached || self.is_infallible(&expr))
+        {
+            return self.hoist(expr, invariants);
+        }
+
+        match expr {
+            Expr::Unary(op, operand) => {
+                Expr::Unary(op, Box::new(self.hoist_expr(*operand, reached, invariants)))
+            }
+            Expr::Grouping(inner) => {
+                Expr::Grouping(Box::new(self.hoist_expr(*inner, reached, invariants)))
+            }
+            Expr::Binary(left, op, right) => {
+                let left = self.hoist_expr(*left, reached, invariants);
+                let reached = reached && self.is_infallible(&left);
+                let right = self.hoist_expr(*right, reached, invariants);
+                Expr::Binary(Box::new(left), op, Box::new(right))
+            }
+            // The right operand is not evaluated when the left one decides the result
+            Expr::Logical(left, op, right) => {
+                let left = self.hoist_expr(*left, reached, invariants);
+                let right = self.hoist_expr(*right, false, invariants);
+                Expr::Logical(Box::new(left), op, Box::new(right))
+            }
+            expr => expr,
+        }
+    }
+
+    /// Stores the expression in a new variable declared before the loop, and returns a read of it.
+    fn hoist(&mut self, expr: Expr, invariants: &mut Invariants) -> Expr {
+        let lexeme = format!("$invariant{}", self.count);
+        self.count += 1;
+
+        let token = first_span(&expr).synthesize(TokenType::Identifier(lexeme.clone()), &lexeme);
+        // The variable is defined before the loop, so reading it inside the loop can not fail
+        self.declare(&token);
+        invariants
+            .hoisted
+            .push(Stmt::Var(token.clone(), Some(expr)));
+        Expr::Variable(token)
+    }
+
+    fn declare(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.name().to_string());
+        }
+    }
+
+    fn is_declared(&self, name: &Token) -> bool {
+        self.scopes.iter().any(|scope| scope.contains(name.name()))
+    }
+
+    fn is_invariant(&self, expr: &Expr, variant: &HashSet<String>) -> bool {
+        match expr {
+            Expr::Literal(_) => true,
+            Expr::Variable(name) => !variant.contains(name.name()),
+            Expr::Unary(_, operand) | Expr::Grouping(operand) => {
+                self.is_invariant(operand, variant)
+            }
+            Expr::Binary(left, _, right) | Expr::Logical(left, _, right) => {
+                self.is_invariant(left, variant) && self.is_invariant(right, variant)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether evaluating the expression can never fail, whatever the values involved.
+    fn is_infallible(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Literal(_) => true,
+            Expr::Variable(name) => self.is_declared(name),
+            Expr::Grouping(operand) => self.is_infallible(operand),
+            Expr::Unary(op, operand) => {
+                matches!(op.op, Op::Bang | Op::Typeof) && self.is_infallible(operand)
+            }
+            Expr::Binary(left, op, right) => {
+                matches!(op.op, Op::EqualEqual | Op::BangEqual | Op::Is)
+                    && self.is_infallible(left)
+                    && self.is_infallible(right)
+            }
+            Expr::Logical(left, _, right) => self.is_infallible(left) && self.is_infallible(right),
+            _ => false,
+        }
+    }
+}
+
+/// Hoists the loops in the statements of block expressions, which are the only statements that
+/// can be nested in an expression.
+impl Transformer for Hoister {
+    fn transform_expr(&mut self, expr: Expr) -> Expr {
+        match expr {
+            Expr::Block(brace, BlockBody::Lowered(stmts), value) => {
+                let stmts = self.hoist_scope(stmts, None);
+                let value = value.map(|value| Box::new(self.transform_expr(*value)));
+                Expr::Block(brace, BlockBody::Lowered(stmts), value)
+            }
+            expr => walk_expr(self, expr),
+        }
+    }
+}
+
+/// Returns whether the expression is as cheap to evaluate as a read of the variable that would
+/// replace it.
+fn is_trivial(expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal(_) | Expr::Variable(_) => true,
+        Expr::Grouping(inner) => is_trivial(inner),
+        _ => false,
+    }
+}
+
+/// Returns where the expression starts, which locates the variable holding it.
+fn first_span(expr: &Expr) -> Span {
+    match expr {
+        Expr::Literal(token) | Expr::Variable(token) => Span::from(token),
+        Expr::Unary(op, _) => op.span,
+        Expr::Binary(left, _, _) | Expr::Logical(left, _, _) | Expr::Grouping(left) => {
+            first_span(left)
+        }
+        _ => unreachable!("Only operators and their operands are hoisted"),
+    }
+}
+
+/// Collects the names of the variables assigned in the expression.
+fn variant_in_expr(expr: &Expr, variant: &mut HashSet<String>) {
+    match expr {
+        Expr::Assign(name, value) => {
+            variant.insert(name.name().to_string());
+            variant_in_expr(value, variant);
+        }
+        Expr::Unary(_, operand)
+        | Expr::Grouping(operand)
+        | Expr::Get(operand, _)
+        | Expr::Spread(_, operand) => variant_in_expr(operand, variant),
+        Expr::Binary(left, _, right)
+        | Expr::Logical(left, _, right)
+        | Expr::Index(left, _, right) => {
+            variant_in_expr(left, variant);
+            variant_in_expr(right, variant);
+        }
+        Expr::IndexSet(object, _, index, value) => {
+            variant_in_expr(object, variant);
+            variant_in_expr(index, variant);
+            variant_in_expr(value, variant);
+        }
+        Expr::Call(callee, _, args) => {
+            variant_in_expr(callee, variant);
+            args.iter().for_each(|arg| variant_in_expr(arg, variant));
+        }
+        Expr::List(_, elements) | Expr::Tuple(_, elements) => elements
+            .iter()
+            .for_each(|element| variant_in_expr(element, variant)),
+        Expr::Map(_, entries) => entries.iter().for_each(|(key, value)| {
+            variant_in_expr(key, variant);
+            variant_in_expr(value, variant);
+        }),
+        Expr::Block(_, body, value) => {
+            body.lowered()
+                .iter()
+                .for_each(|stmt| variant_in_stmt(stmt, variant));
+            if let Some(value) = value {
+                variant_in_expr(value, variant);
+            }
+        }
+        Expr::Literal(_) | Expr::Variable(_) => {}
+    }
+}
+
+/// Collects the names of the variables assigned or declared in the statement.
+fn variant_in_stmt(stmt: &Stmt, variant: &mut HashSet<String>) {
+    match stmt {
+        Stmt::Expression(expr) => variant_in_expr(expr, variant),
+        Stmt::Print(exprs) => exprs.iter().for_each(|expr| variant_in_expr(expr, variant)),
+        Stmt::Assert(_, condition, _, message) => {
+            variant_in_expr(condition, variant);
+            if let Some(message) = message {
+                variant_in_expr(message, variant);
+            }
+        }
+        Stmt::Var(name, initializer) => {
+            variant.insert(name.name().to_string());
+            if let Some(initializer) = initializer {
+                variant_in_expr(initializer, variant);
+            }
+        }
+        Stmt::Const(name, initializer) => {
+            variant.insert(name.name().to_string());
+            variant_in_expr(initializer, variant);
+        }
+        Stmt::VarTuple(_, names, initializer) => {
+            variant.extend(names.iter().map(|name| name.name().to_string()));
+            variant_in_expr(initializer, variant);
+        }
+        Stmt::Block(stmts) => stmts.iter().for_each(|stmt| variant_in_stmt(stmt, variant)),
+        Stmt::If(condition, then_branch, else_branch) => {
+            variant_in_expr(condition, variant);
+            variant_in_stmt(then_branch, variant);
+            if let Some(else_branch) = else_branch {
+                variant_in_stmt(else_branch, variant);
+            }
+        }
+        Stmt::While(condition, body, increment, _) => {
+            variant_in_expr(condition, variant);
+            variant_in_stmt(body, variant);
+            if let Some(increment) = increment {
+                variant_in_expr(increment, variant);
+            }
+        }
+        Stmt::ForIn(name, _, iterable, body, _) => {
+            variant.insert(name.name().to_string());
+            variant_in_expr(iterable, variant);
+            variant_in_stmt(body, variant);
+        }
+        Stmt::Throw(_, value) => variant_in_expr(value, variant),
+        Stmt::Try(body, catch, finally) => {
+            body.iter().for_each(|stmt| variant_in_stmt(stmt, variant));
+            if let Some((name, handler)) = catch {
+                variant.insert(name.name().to_string());
+                handler
+                    .iter()
+                    .for_each(|stmt| variant_in_stmt(stmt, variant));
+            }
+            if let Some(finally) = finally {
+                finally
+                    .iter()
+                    .for_each(|stmt| variant_in_stmt(stmt, variant));
+            }
+        }
+        Stmt::Break(..) | Stmt::Continue(..) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::desugar::lower;
+    use crate::ast::interpreter::{Interpreter, SharedOutput};
+    use crate::ast::printer::AstPrinter;
+    use crate::ast::syntax_tree::SyntaxTree;
+    use crate::config::Config;
+    use crate::lexer::lexer::Lexer;
+
+    fn hoist_source(source: &str) -> String {
+        let tokens = Lexer::new(source).get_tokens();
+        let stmts = lower(SyntaxTree::new(tokens).parse().unwrap());
+        AstPrinter::print_stmts(&Hoister::new().hoist_stmts(stmts))
+    }
+
+    #[test]
+    fn test_hoist() {
+        let test_cases = vec![
+            (
+                "var n = 3; var i = 0; while (i < n * 2) i = i + 1;",
+                "(var n 3.0)\n(var i 0.0)\n(block (var $invariant0 (* n 2.0)) (while (< i $invariant0) (= i (+ i 1.0))))",
+            ),
+            // Expressions that depend on a variable assigned or declared in the loop stay in it
+            (
+                "var n = 3; var i = 0; while (i < n * 2) n = n - 1;",
+                "(var n 3.0)\n(var i 0.0)\n(while (< i (* n 2.0)) (= n (- n 1.0)))",
+            ),
+            (
+                "var n = 3; for (var i = 0; i < n + 1; i = i + 1) { var n = i; }",
+                "(var n 3.0)\n(block (var i 0.0) (while (< i (+ n 1.0)) (block (var n i)) (= i (+ i 1.0))))",
+            ),
+            // Calls, indexing and collections are never hoisted
+            (
+                "var xs = [1]; var i = 0; while (i < xs[0] + 1) i = i + 1;",
+                "(var xs (list 1.0))\n(var i 0.0)\n(while (< i (+ (index xs 0.0) 1.0)) (= i (+ i 1.0)))",
+            ),
+            (
+                "var a = 1; var b = 2; var i = 0; while (i < a * 2 + (i - b * 3)) i = i - 1;",
+                "(var a 1.0)\n(var b 2.0)\n(var i 0.0)\n(block (var $invariant0 (* a 2.0)) (var $invariant1 (* b 3.0)) (while (< i (+ $invariant0 (group (- i $invariant1)))) (= i (- i 1.0))))",
+            ),
+            // Only what is evaluated on every check of the condition is hoisted from it
+            (
+                "var a = 1; var i = 0; while (i < 3 and a * 2 > 1) i = i + 1;",
+                "(var a 1.0)\n(var i 0.0)\n(while (and (< i 3.0) (> (* a 2.0) 1.0)) (= i (+ i 1.0)))",
+            ),
+            (
+                "var a = 1; var i = 0; while (-i < a * 2) i = i + 1;",
+                "(var a 1.0)\n(var i 0.0)\n(while (< (- i) (* a 2.0)) (= i (+ i 1.0)))",
+            ),
+            // The body only loses the expressions that can not fail
+            (
+                "var a = 1; var b = 2; var i = 0; while (i < 2) { i = i + 1; print a == b, a + b; }",
+                "(var a 1.0)\n(var b 2.0)\n(var i 0.0)\n(block (var $invariant0 (== a b)) (while (< i 2.0) (block (= i (+ i 1.0)) (print $invariant0 (+ a b)))))",
+            ),
+            // Invariants of nested loops move out of all the loops they do not depend on
+            (
+                "var n = 2; var i = 0; while (i < n) { var j = 0; while (j < n - 1) j = j + 1; i = i + 1; }",
+                "(var n 2.0)\n(var i 0.0)\n(while (< i n) (block (var j 0.0) (block (var $invariant0 (- n 1.0)) (while (< j $invariant0) (= j (+ j 1.0)))) (= i (+ i 1.0))))",
+            ),
+            // Loops in block expressions are optimized like the others
+            (
+                "var n = 2; var x = { var i = 0; while (i < n * 2) i = i + 1; i };",
+                "(var n 2.0)\n(var x (block-expr (var i 0.0) (block (var $invariant0 (* n 2.0)) (while (< i $invariant0) (= i (+ i 1.0)))) i))",
+            ),
+        ];
+
+        for (source, expected) in test_cases {
+            assert_eq!(hoist_source(source), expected, "{}", source);
+        }
+    }
+
+    /// Runs the program with and without hoisting, and returns the output and error of both.
+    fn run_both(source: &str) -> [(String, Option<String>); 2] {
+        [false, true].map(|optimize| {
+            let tokens = Lexer::new(source).get_tokens();
+            let mut stmts = lower(SyntaxTree::new(tokens).parse().unwrap());
+            if optimize {
+                stmts = Hoister::new().hoist_stmts(stmts);
+            }
+
+            let output = SharedOutput::default();
+            let interpreter = Interpreter::with_output(Config::default(), Box::new(output.clone()));
+            let result = interpreter.interpret(&stmts);
+            (output.contents(), result.err().map(|err| err.to_string()))
+        })
+    }
+
+    #[test]
+    fn test_hoisted_behavior() {
+        // Hoisting changes neither the output nor the errors of a program
+        let test_cases = vec![
+            "var n = 3; var i = 0; while (i < n * 2) { print i; i = i + 1; }",
+            "var s = \"a\"; while (s - 1 > 0) print 1;",
+            "var s = \"a\"; var i = 0; while (i < 1) { i = i + 1; print i; print s - 1; }",
+            "var s = \"a\"; while (false and s - 1) print 1; print 2;",
+            "var s = \"a\"; var i = 0; while (i < 2) { if (i == 1) print s * 2; i = i + 1; }",
+            "var s = \"a\"; while (undefined < s + 1) print 1;",
+            "var a = 1; var b = 2; var i = 0; while (i < 2) { i = i + 1; print a == b, a + b; }",
+            "var n = 2; var i = 0; while (i < n) { var j = 0; while (j < n - 1) { print i, j; j = j + 1; } i = i + 1; }",
+            "var n = 2; print { var i = 0; while (i < n * 2) i = i + 1; i };",
+        ];
+
+        for source in test_cases {
+            let [original, hoisted] = run_both(source);
+            assert_eq!(hoisted, original, "{}", source);
+        }
+    }
+}
@@ -1,68 +1,382 @@
-use std::fmt::Display;
+use std::cell::RefCell;
+use std::io::{stderr, stdin, stdout, Write};
+use std::rc::Rc;
 
 use anyhow::Error;
 
-use super::expr::Expr;
-use super::expr::Visitor;
+use super::environment::Environment;
+use super::error::{ArgumentError, NativeError, RuntimeError, Span, ValueError};
+use super::expr::{BlockBody, Expr, Op, Operator, Visitor as ExprVisitor};
+use super::ir::{Stmt, Visitor as StmtVisitor};
+use super::native::{log_natives, natives};
+use super::ordered_map::OrderedMap;
+use super::value::{Callable, MapKey, NativeFunction, Value};
+use crate::config::{Config, Conversions, LogLevel};
 use crate::lexer::{token::Token, types::TokenType};
 
-pub struct Interpreter {
-    expr: Expr,
+/// The ways in which the execution of a statement can be cut short.
+#[derive(Debug)]
+pub enum Interrupt {
+    /// A runtime error, which aborts the program unless a `try` statement catches it.
+    Error(Error),
+    /// A `throw` statement, which unwinds to the innermost enclosing `try` statement with the
+    /// thrown value.
+    Throw(Value, Span),
+    /// A `break` statement, which exits the loop with the provided label, or the innermost
+    /// enclosing loop if no label was given.
+    Break(Option<String>),
+    /// A `continue` statement, which skips to the next iteration of the loop with the provided
+    /// label, or of the innermost enclosing loop if no label was given.
+    Continue(Option<String>),
 }
 
-impl Interpreter {
-    pub fn new(expr: Expr) -> Self {
-        Interpreter { expr }
+impl Interrupt {
+    /// Returns true if the label of a `break` or `continue` refers to the loop with the provided label.
+    fn targets(label: &Option<String>, loop_label: &Option<Token>) -> bool {
+        match (label, loop_label) {
+            (None, _) => true,
+            (Some(label), Some(loop_label)) => label == loop_label.name(),
+            (Some(_), None) => false,
+        }
     }
+}
 
-    pub fn evaluate(&self) -> Result<Value, Error> {
-        self.expr.accept(self)
+impl From<Error> for Interrupt {
+    fn from(err: Error) -> Self {
+        Interrupt::Error(err)
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub enum Value {
-    Number(f32),
-    String(String),
-    Boolean(bool),
-    Nil,
+/// Receives the messages logged by the program that pass the level filter of the configuration.
+pub type Logger = Box<dyn FnMut(LogLevel, &str)>;
+
+/// Reads the next line of the input of the program, without its line ending, or returns `None`
+/// at the end of the input.
+pub type Input = Box<dyn FnMut() -> std::io::Result<Option<String>>>;
+
+pub struct Interpreter {
+    config: Config,
+    /// The innermost scope of the code being executed.
+    environment: RefCell<Rc<RefCell<Environment>>>,
+    output: RefCell<Box<dyn Write>>,
+    logger: RefCell<Logger>,
+    input: RefCell<Input>,
+    /// The functions being called, with the location of each call, from the outermost call.
+    calls: RefCell<Vec<(&'static str, Span)>>,
+    /// The values thrown out of the block expressions being unwound, from the outermost one.
+    thrown: RefCell<Vec<Value>>,
+    /// The natives defined in the global environment, which are defined again whenever the
+    /// interpreter is reset.
+    registered: RefCell<Vec<NativeFunction>>,
 }
 
-impl Value {
-    fn expect_number(&self) -> Result<f32, Error> {
-        match self {
-            Value::Number(num) => Ok(*num),
-            _ => Err(Error::msg(format!("Expected number value, got {:?}", self))),
+impl Interpreter {
+    /// Creates a new interpreter that writes the output of the program to the standard output.
+    pub fn new(config: Config) -> Self {
+        Self::with_output(config, Box::new(stdout()))
+    }
+
+    /// Creates a new interpreter that writes the output of the program to the provided writer,
+    /// and the messages it logs to the standard error.
+    pub fn with_output(config: Config, output: Box<dyn Write>) -> Self {
+        let logger = Box::new(|level, message: &str| {
+            writeln!(stderr(), "[{}] {}", level, message).expect("Failed to write to stderr");
+        });
+        Self::with_logger(config, output, logger)
+    }
+
+    /// Creates a new interpreter that writes the output of the program to the provided writer,
+    /// and passes the messages it logs to the logger, so that a host application can route them
+    /// to its own logging.
+    pub fn with_logger(config: Config, output: Box<dyn Write>, logger: Logger) -> Self {
+        Self::with_io(config, Box::new(read_stdin_line), output, logger)
+    }
+
+    /// Creates a new interpreter that reads the input of the program from the provided input,
+    /// such as the lines returned by `readLine`, and otherwise behaves like `with_logger`.
+    pub fn with_io(config: Config, input: Input, output: Box<dyn Write>, logger: Logger) -> Self {
+        let interpreter = Interpreter {
+            config,
+            environment: RefCell::new(Rc::new(RefCell::new(Environment::new()))),
+            output: RefCell::new(output),
+            logger: RefCell::new(logger),
+            input: RefCell::new(input),
+            calls: RefCell::new(Vec::new()),
+            thrown: RefCell::new(Vec::new()),
+            registered: RefCell::new(Vec::new()),
+        };
+
+        // The built-in natives are registered like the ones of the application
+        for native in natives(&interpreter) {
+            interpreter.register_native(native);
         }
+        interpreter.reset();
+        interpreter
     }
 
-    fn expect_boolean(&self) -> Result<bool, Error> {
-        match self {
-            Value::Boolean(b) => Ok(*b),
-            _ => Err(Error::msg(format!(
-                "Expected boolean value, got {:?}",
-                self
-            ))),
+    /// Removes all the variables defined by the programs run so far, leaving only the natives.
+    pub fn reset(&self) {
+        self.environment
+            .replace(Rc::new(RefCell::new(Environment::new())));
+
+        self.define_variable("log", log_natives());
+        for native in self.registered.borrow().iter() {
+            self.define_native(native.clone());
         }
     }
-}
 
-impl Display for Value {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Value::Number(num) => write!(f, "{}", num),
-            Value::String(str) => write!(f, "{}", str),
-            Value::Boolean(b) => write!(f, "{}", b),
-            Value::Nil => write!(f, "nil"),
+    /// Registers a native that is defined in the global environment from now on, including after
+    /// the interpreter is reset. This is how the built-in natives are set up, and how
+    /// applications that embed the interpreter add their own. Registering a native with the name
+    /// of another global replaces it.
+    pub fn register_native(&self, native: NativeFunction) {
+        self.define_native(native.clone());
+        self.registered.borrow_mut().push(native);
+    }
+
+    /// Returns the frames of the functions being executed, from the innermost one, each with the
+    /// line it is executing. The innermost frame is the caller of the native that asks for them,
+    /// and the outermost one is the top level of the program, named `script`.
+    pub fn stack_frames(&self) -> Vec<(&'static str, usize)> {
+        let calls = self.calls.borrow();
+        let callers = std::iter::once("script").chain(calls.iter().map(|(name, _)| *name));
+        let mut frames = callers
+            .zip(calls.iter().map(|(_, span)| span.line))
+            .collect::<Vec<_>>();
+        frames.reverse();
+        frames
+    }
+
+    /// Defines the native function as a global variable, replacing any existing global with the same name.
+    pub fn define_native(&self, native: NativeFunction) {
+        self.define_variable(native.name, Value::NativeFunction(native));
+    }
+
+    /// Defines a variable in the scope being executed, which is the global scope between programs.
+    pub fn define_variable(&self, name: &str, value: Value) {
+        self.environment.borrow().borrow_mut().define(name, value);
+    }
+
+    /// Returns the names of all the variables visible from the scope being executed.
+    pub fn variable_names(&self) -> Vec<String> {
+        self.environment.borrow().borrow().names()
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Evaluates a single expression and returns the resulting value. The output is flushed
+    /// afterwards, like by `interpret`.
+    pub fn evaluate(&self, expr: &Expr) -> Result<Value, Error> {
+        let result = expr
+            .accept(self)
+            .map_err(|err| self.uncaught(Interrupt::Error(err)));
+        self.flush(result)
+    }
+
+    /// Executes the statements of a program in order, stopping at the first runtime error.
+    /// However the program ends, everything it printed has been flushed to the output when this
+    /// returns, so that a host can exit right after it.
+    pub fn interpret(&self, stmts: &[Stmt]) -> Result<(), Error> {
+        let result = self
+            .execute(stmts)
+            .map_err(|interrupt| self.uncaught(interrupt));
+        self.flush(result)
+    }
+
+    /// Flushes the output at the end of a program. A failure to flush is only reported if the
+    /// program succeeded, since the error that ended it is more useful otherwise.
+    fn flush<T>(&self, result: Result<T, Error>) -> Result<T, Error> {
+        let flushed = self.output.borrow_mut().flush();
+        let value = result?;
+        flushed?;
+        Ok(value)
+    }
+
+    /// Returns the error that the interrupt ends the program with, when nothing catches it.
+    fn uncaught(&self, interrupt: Interrupt) -> Error {
+        match self.rethrow(interrupt) {
+            Interrupt::Error(err) => err,
+            Interrupt::Throw(value, span) => RuntimeError::Uncaught {
+                value: value.to_string(),
+                span,
+            }
+            .into(),
+            Interrupt::Break(_) | Interrupt::Continue(_) => {
+                panic!(
+                    "Break and continue statements outside of their loops should not be allowed by the AST parser."
+                )
+            }
+        }
+    }
+
+    /// Turns a value thrown out of a block expression, which unwinds the expression as an error,
+    /// back into a throw. Other interrupts are returned as they are.
+    fn rethrow(&self, interrupt: Interrupt) -> Interrupt {
+        match interrupt {
+            Interrupt::Error(err) => match err.downcast_ref::<RuntimeError>() {
+                Some(RuntimeError::Thrown { span }) => Interrupt::Throw(
+                    self.thrown
+                        .borrow_mut()
+                        .pop()
+                        .expect("Every thrown error should have its value"),
+                    *span,
+                ),
+                _ => Interrupt::Error(err),
+            },
+            interrupt => interrupt,
+        }
+    }
+
+    /// Executes the statements in order, stopping at the first one that is interrupted.
+    fn execute(&self, stmts: &[Stmt]) -> Result<(), Interrupt> {
+        for stmt in stmts {
+            stmt.accept(self)?;
+        }
+
+        Ok(())
+    }
+
+    /// Executes the statements in the provided scope, restoring the current scope afterwards
+    /// even if a statement fails.
+    fn execute_block(&self, stmts: &[Stmt], environment: Environment) -> Result<(), Interrupt> {
+        let previous = self.environment.replace(Rc::new(RefCell::new(environment)));
+        let result = self.execute(stmts);
+        self.environment.replace(previous);
+
+        result
+    }
+
+    /// Runs the finally block of a try statement, if it has one, and returns the result of the
+    /// try or catch block. An interrupt of the finally block replaces that result.
+    fn finally(
+        &self,
+        finally: &Option<Vec<Stmt>>,
+        result: Result<(), Interrupt>,
+    ) -> Result<(), Interrupt> {
+        if let Some(finally) = finally {
+            let environment = Environment::with_enclosing(self.environment.borrow().clone());
+            self.execute_block(finally, environment)?;
+        }
+
+        result
+    }
+
+    /// Writes the values to the output of the program, separated by the configured separator.
+    pub fn write_values(&self, values: &[Value]) -> Result<(), Error> {
+        let line = values
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(&self.config.print_separator);
+
+        self.write_line(&line)
+    }
+
+    /// Writes a line to the output of the program.
+    pub fn write_line(&self, line: &str) -> Result<(), Error> {
+        writeln!(self.output.borrow_mut(), "{}", line)?;
+        Ok(())
+    }
+
+    /// Calls the callable with the evaluated arguments, after checking that it was passed as many
+    /// as it expects. The call is on the call stack while it runs.
+    fn call(&self, callable: &dyn Callable, paren: &Token, args: &[Value]) -> Result<Value, Error> {
+        if let Some(arity) = callable.arity() {
+            if args.len() != arity {
+                return Err(Error::msg(format!(
+                    "[line {}] Expected {} arguments but got {}.",
+                    paren.line,
+                    arity,
+                    args.len()
+                )));
+            }
         }
+
+        self.calls
+            .borrow_mut()
+            .push((callable.name(), Span::from(paren)));
+        let result = callable.call(self, args);
+        self.calls.borrow_mut().pop();
+
+        result.map_err(|err| match err.downcast::<ArgumentError>() {
+            Ok(err) => err.at_call(callable.name(), paren).into(),
+            Err(err) => match err.downcast::<NativeError>() {
+                Ok(err) => err.at_call(callable.name(), paren).into(),
+                Err(err) => err,
+            },
+        })
+    }
+
+    /// Calls the function with the arguments on behalf of the native being executed, such as for
+    /// the function passed to `map`. The errors of the call are reported at the call of the native.
+    pub fn call_back(&self, callable: &dyn Callable, args: &[Value]) -> Result<Value, Error> {
+        let span = self.calls.borrow().last().map(|(_, span)| *span);
+        let paren = span
+            .expect("Functions should only be called back from natives")
+            .synthesize(TokenType::RightParen, ")");
+
+        self.call(callable, &paren, args)
+    }
+
+    /// Evaluates the elements of a list or the arguments of a call in order, expanding the
+    /// elements of the lists and tuples that are spread into them.
+    fn evaluate_spread(&self, exprs: &[Expr]) -> Result<Vec<Value>, Error> {
+        let mut values = Vec::with_capacity(exprs.len());
+
+        for expr in exprs {
+            match expr {
+                Expr::Spread(ellipsis, expr) => match expr.accept(self)? {
+                    Value::List(list) => values.extend(list.borrow().iter().cloned()),
+                    Value::Tuple(tuple) => values.extend(tuple.iter().cloned()),
+                    value => {
+                        return Err(RuntimeError::type_mismatch(
+                            "a list or a tuple",
+                            vec![value],
+                            ellipsis,
+                        ))
+                    }
+                },
+                expr => values.push(expr.accept(self)?),
+            }
+        }
+
+        Ok(values)
     }
+
+    /// Passes the message to the logger, unless its level is below the configured one.
+    pub fn log(&self, level: LogLevel, message: &str) {
+        if level >= self.config.log_level {
+            (self.logger.borrow_mut())(level, message);
+        }
+    }
+
+    /// Reads the next line of the input of the program, or returns `None` at its end.
+    pub fn read_line(&self) -> Result<Option<String>, Error> {
+        Ok((self.input.borrow_mut())()?)
+    }
+}
+
+/// Reads the next line of the standard input. The standard input is locked only while the line
+/// is read, so that the REPL can read from it too.
+fn read_stdin_line() -> std::io::Result<Option<String>> {
+    let mut line = String::new();
+    if stdin().read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+
+    let length = line.trim_end_matches(['\n', '\r']).len();
+    line.truncate(length);
+    Ok(Some(line))
 }
 
-impl Visitor<Result<Value, Error>> for Interpreter {
+impl ExprVisitor<Result<Value, Error>> for Interpreter {
     fn visit_literal_expr(&self, value: &Token) -> Result<Value, Error> {
         match &value.token_type {
             TokenType::String(str) => Ok(Value::String(str.to_string())),
             TokenType::Number(num) => Ok(Value::Number(*num)),
+            TokenType::Integer(integer) => Ok(Value::Integer(*integer)),
             TokenType::TRUE => Ok(Value::Boolean(true)),
             TokenType::FALSE => Ok(Value::Boolean(false)),
             TokenType::NIL => Ok(Value::Nil),
@@ -76,17 +390,17 @@ impl Visitor<Result<Value, Error>> for Interpreter {
     fn visit_binary_expr(
         &self,
         left: &Box<Expr>,
-        op: &Token,
+        op: &Operator,
         right: &Box<Expr>,
     ) -> Result<Value, Error> {
         let left = left.accept(self)?;
         let right = right.accept(self)?;
 
-        match op.token_type {
-            TokenType::Plus => match (left, right) {
+        match op.op {
+            Op::Plus => match (left, right) {
                 // Adding two numbers
-                (Value::Number(left_num), Value::Number(right_num)) => {
-                    Ok(Value::Number(left_num + right_num))
+                (left, right) if left.as_number().is_some() && right.as_number().is_some() => {
+                    Ok(arithmetic(&left, &right, i64::checked_add, |l, r| l + r))
                 }
 
                 // Concatenating two strings
@@ -94,73 +408,125 @@ impl Visitor<Result<Value, Error>> for Interpreter {
                     Ok(Value::String(format!("{}{}", left_str, right_str)))
                 }
 
+                // Concatenating a string and a number, which is only allowed in lenient mode
+                (left @ Value::String(_), right) | (left, right @ Value::String(_))
+                    if self.config.conversions == Conversions::Lenient
+                        && (left.as_number().is_some() || right.as_number().is_some()) =>
+                {
+                    Ok(Value::String(format!("{}{}", left, right)))
+                }
+
                 // Handling other cases
-                (left, right) => Err(Error::msg(format!(
-                    "Cannot add values of different types: {:?} and {:?}",
-                    left, right
-                ))),
+                (left, right) => Err(RuntimeError::type_mismatch(
+                    match self.config.conversions {
+                        Conversions::Strict => "two numbers or two strings",
+                        Conversions::Lenient => "two numbers, or a string and a string or number",
+                    },
+                    vec![left, right],
+                    op,
+                )),
             },
 
-            TokenType::Minus => Ok(Value::Number(
-                left.expect_number()? - right.expect_number()?,
-            )),
+            Op::Minus => {
+                expect_numbers(op, &left, &right)?;
+                Ok(arithmetic(&left, &right, i64::checked_sub, |l, r| l - r))
+            }
 
-            TokenType::Star => match (left, right) {
+            Op::Star => match (left, right) {
                 // Multiplying two numbers
-                (Value::Number(left_num), Value::Number(right_num)) => {
-                    Ok(Value::Number(left_num * right_num))
+                (left, right) if left.as_number().is_some() && right.as_number().is_some() => {
+                    Ok(arithmetic(&left, &right, i64::checked_mul, |l, r| l * r))
                 }
 
-                // Multiplication with a string and a number
-                (Value::String(str), Value::Number(num)) => {
-                    Ok(Value::String(str.repeat(num as usize)))
+                // Repeating a string, which is an extension to the language
+                (Value::String(str), count @ (Value::Number(_) | Value::Integer(_)))
+                    if self.config.string_repetition =>
+                {
+                    let times = count.expect_index(op, "count")?;
+                    match str.len().checked_mul(times) {
+                        Some(length) if length <= MAX_STRING_LENGTH => {
+                            Ok(Value::String(str.repeat(times)))
+                        }
+                        _ => Err(ValueError::InvalidIndex("is too large").at(&count, op, "count")),
+                    }
                 }
 
                 // Handling other cases
-                (left, right) => Err(Error::msg(format!(
-                    "Cannot multiply values of different types: {:?} and {:?}",
-                    left, right
-                ))),
+                (left, right) => Err(RuntimeError::type_mismatch(
+                    match self.config.string_repetition {
+                        true => "two numbers, or a string and a count",
+                        false => "two numbers",
+                    },
+                    vec![left, right],
+                    op,
+                )),
             },
 
-            TokenType::Slash => Ok(Value::Number(
-                left.expect_number()? / right.expect_number()?,
-            )),
+            // Division always produces a float, even when both operands are integers
+            Op::Slash => {
+                let (left, right) = expect_numbers(op, &left, &right)?;
+                Ok(Value::Number(left / right))
+            }
 
-            TokenType::Greater
-            | TokenType::GreaterEqual
-            | TokenType::Less
-            | TokenType::LessEqual => match (left, right) {
-                // Comparing two numbers
-                (Value::Number(left_num), Value::Number(right_num)) => match op.token_type {
-                    TokenType::Greater => Ok(Value::Boolean(left_num > right_num)),
-                    TokenType::GreaterEqual => Ok(Value::Boolean(left_num >= right_num)),
-                    TokenType::Less => Ok(Value::Boolean(left_num < right_num)),
-                    TokenType::LessEqual => Ok(Value::Boolean(left_num <= right_num)),
+            Op::Greater | Op::GreaterEqual | Op::Less | Op::LessEqual => match (left, right) {
+                // Comparing two integers, which is exact even for the ones that floats can not hold
+                (Value::Integer(left_num), Value::Integer(right_num)) => match op.op {
+                    Op::Greater => Ok(Value::Boolean(left_num > right_num)),
+                    Op::GreaterEqual => Ok(Value::Boolean(left_num >= right_num)),
+                    Op::Less => Ok(Value::Boolean(left_num < right_num)),
+                    Op::LessEqual => Ok(Value::Boolean(left_num <= right_num)),
                     _ => unreachable!(),
                 },
 
-                // Comparing two strings
-                (Value::String(left_str), Value::String(right_str)) => match op.token_type {
-                    TokenType::Greater => Ok(Value::Boolean(left_str > right_str)),
-                    TokenType::GreaterEqual => Ok(Value::Boolean(left_str >= right_str)),
-                    TokenType::Less => Ok(Value::Boolean(left_str < right_str)),
-                    TokenType::LessEqual => Ok(Value::Boolean(left_str <= right_str)),
-                    _ => unreachable!(),
-                },
+                // Comparing two numbers
+                (left, right) if left.as_number().is_some() && right.as_number().is_some() => {
+                    let (left_num, right_num) = expect_numbers(op, &left, &right)?;
+                    match op.op {
+                        Op::Greater => Ok(Value::Boolean(left_num > right_num)),
+                        Op::GreaterEqual => Ok(Value::Boolean(left_num >= right_num)),
+                        Op::Less => Ok(Value::Boolean(left_num < right_num)),
+                        Op::LessEqual => Ok(Value::Boolean(left_num <= right_num)),
+                        _ => unreachable!(),
+                    }
+                }
 
-                (left, right) => Err(Error::msg(format!(
-                    "Cannot compare values of different types: {:?} and {:?}",
-                    left, right
-                ))),
+                // Comparing two strings, which is an extension to the language
+                (Value::String(left_str), Value::String(right_str))
+                    if self.config.string_comparison =>
+                {
+                    match op.op {
+                        Op::Greater => Ok(Value::Boolean(left_str > right_str)),
+                        Op::GreaterEqual => Ok(Value::Boolean(left_str >= right_str)),
+                        Op::Less => Ok(Value::Boolean(left_str < right_str)),
+                        Op::LessEqual => Ok(Value::Boolean(left_str <= right_str)),
+                        _ => unreachable!(),
+                    }
+                }
+                (left, right) => Err(RuntimeError::type_mismatch(
+                    match (&left, &right, self.config.string_comparison) {
+                        (_, _, true) => "two numbers or two strings",
+                        (Value::String(_), Value::String(_), false) => {
+                            "two numbers, or two strings with the --string-comparison flag"
+                        }
+                        _ => "two numbers",
+                    },
+                    vec![left, right],
+                    op,
+                )),
             },
 
-            TokenType::EqualEqual => Ok(Value::Boolean(left == right)),
-            TokenType::BangEqual => Ok(Value::Boolean(left != right)),
+            Op::EqualEqual => Ok(Value::Boolean(left == right)),
+            Op::BangEqual => Ok(Value::Boolean(left != right)),
+
+            // The parser only allows the name of a kind after `is`, which it stores as a string
+            Op::Is => match right {
+                Value::String(kind) => Ok(Value::Boolean(left.kind() == kind)),
+                right => unreachable!("Expected the name of a kind after 'is', got {:?}", right),
+            },
 
             _ => panic!(
                 "Unexpected operator {:?} which should not be allowed by the AST parser.",
-                op.token_type
+                op.op
             ),
         }
     }
@@ -169,92 +535,1810 @@ impl Visitor<Result<Value, Error>> for Interpreter {
         expr.accept(self)
     }
 
-    fn visit_unary_expr(&self, op: &Token, expr: &Box<Expr>) -> Result<Value, Error> {
+    fn visit_unary_expr(&self, op: &Operator, expr: &Box<Expr>) -> Result<Value, Error> {
         let val = expr.accept(self)?;
 
-        match op.token_type {
-            TokenType::Minus => val.expect_number().map(|num| Value::Number(-num)),
-            TokenType::Bang => match val {
-                Value::Boolean(b) => Ok(Value::Boolean(!b)),
-                Value::Nil => Ok(Value::Boolean(true)),
-                _ => Ok(Value::Boolean(false)),
+        match op.op {
+            Op::Minus => match val {
+                Value::Integer(integer) => Ok(match integer.checked_neg() {
+                    Some(negated) => Value::Integer(negated),
+                    None => Value::Number(-(integer as f64)),
+                }),
+                val => val.expect_number(op).map(|num| Value::Number(-num)),
             },
+            Op::Bang => Ok(Value::Boolean(!val.is_truthy())),
+            Op::Typeof => Ok(Value::String(val.kind().to_lowercase())),
 
             _ => panic!(
                 "Unexpected operator {:?} which should not be allowed by the AST parser.",
-                op.token_type
+                op.op
             ),
         }
     }
+
+    fn visit_logical_expr(
+        &self,
+        left: &Box<Expr>,
+        op: &Operator,
+        right: &Box<Expr>,
+    ) -> Result<Value, Error> {
+        let left = left.accept(self)?;
+
+        // The right operand is only evaluated if the left one does not decide the result
+        match (&op.op, left.is_truthy()) {
+            (Op::Or, true) | (Op::And, false) => Ok(left),
+            _ => right.accept(self),
+        }
+    }
+
+    fn visit_variable_expr(&self, name: &Token) -> Result<Value, Error> {
+        self.environment.borrow().borrow().get(name)
+    }
+
+    fn visit_assign_expr(&self, name: &Token, value: &Box<Expr>) -> Result<Value, Error> {
+        let value = value.accept(self)?;
+        self.environment
+            .borrow()
+            .borrow_mut()
+            .assign(name, value.clone())?;
+        Ok(value)
+    }
+
+    fn visit_call_expr(
+        &self,
+        callee: &Box<Expr>,
+        paren: &Token,
+        args: &Vec<Expr>,
+    ) -> Result<Value, Error> {
+        let callee = callee.accept(self)?;
+        let args = self.evaluate_spread(args)?;
+
+        match callee.as_callable() {
+            Some(callable) => self.call(callable, paren, &args),
+            None => Err(Error::msg(format!(
+                "[line {}] Can only call functions and classes.",
+                paren.line
+            ))),
+        }
+    }
+
+    fn visit_list_expr(&self, _bracket: &Token, elements: &Vec<Expr>) -> Result<Value, Error> {
+        let elements = self.evaluate_spread(elements)?;
+        Ok(Value::List(Rc::new(RefCell::new(elements))))
+    }
+
+    fn visit_index_expr(
+        &self,
+        object: &Box<Expr>,
+        bracket: &Token,
+        index: &Box<Expr>,
+    ) -> Result<Value, Error> {
+        let object = object.accept(self)?;
+        let index = index.accept(self)?;
+
+        match object {
+            Value::List(list) => {
+                let index = expect_in_bounds(bracket, &list.borrow(), &index)?;
+                let value = list.borrow()[index].clone();
+                Ok(value)
+            }
+
+            Value::Tuple(tuple) => {
+                let index = expect_in_bounds(bracket, &tuple, &index)?;
+                Ok(tuple[index].clone())
+            }
+
+            Value::Map(map) => match map.borrow().get(&expect_key(bracket, index.clone())?) {
+                Some(value) => Ok(value.clone()),
+                None if self.config.missing_key_error => Err(Error::msg(format!(
+                    "[line {}] Undefined key {}.",
+                    bracket.line,
+                    index.debug_string()
+                ))),
+                None => Ok(Value::Nil),
+            },
+
+            object => Err(RuntimeError::type_mismatch(
+                "a list, a map or a tuple",
+                vec![object],
+                bracket,
+            )),
+        }
+    }
+
+    fn visit_index_set_expr(
+        &self,
+        object: &Box<Expr>,
+        bracket: &Token,
+        index: &Box<Expr>,
+        value: &Box<Expr>,
+    ) -> Result<Value, Error> {
+        let object = object.accept(self)?;
+        let index = index.accept(self)?;
+        let value = value.accept(self)?;
+
+        match object {
+            Value::List(list) => {
+                let index = expect_in_bounds(bracket, &list.borrow(), &index)?;
+                list.borrow_mut()[index] = value.clone();
+            }
+
+            Value::Map(map) => {
+                let key = expect_key(bracket, index)?;
+                map.borrow_mut().insert(key, value.clone());
+            }
+
+            object => {
+                return Err(RuntimeError::type_mismatch(
+                    "a list or a map",
+                    vec![object],
+                    bracket,
+                ))
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn visit_map_expr(&self, brace: &Token, entries: &Vec<(Expr, Expr)>) -> Result<Value, Error> {
+        let mut map = OrderedMap::new();
+
+        for (key, value) in entries {
+            let key = key.accept(self)?;
+            let value = value.accept(self)?;
+            map.insert(expect_key(brace, key)?, value);
+        }
+
+        Ok(Value::Map(Rc::new(RefCell::new(map))))
+    }
+
+    fn visit_tuple_expr(&self, _paren: &Token, elements: &Vec<Expr>) -> Result<Value, Error> {
+        let elements = elements
+            .iter()
+            .map(|element| element.accept(self))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Value::Tuple(Rc::new(elements)))
+    }
+
+    /// Properties are the string keys of maps, so `m.key` is the same as `m["key"]`.
+    fn visit_get_expr(&self, object: &Box<Expr>, name: &Token) -> Result<Value, Error> {
+        match object.accept(self)? {
+            Value::Map(map) => match map.borrow().get(&MapKey::String(name.name().to_string())) {
+                Some(value) => Ok(value.clone()),
+                None if self.config.missing_key_error => Err(Error::msg(format!(
+                    "[line {}] Undefined key {}.",
+                    name.line,
+                    Value::String(name.name().to_string()).debug_string()
+                ))),
+                None => Ok(Value::Nil),
+            },
+            _ => Err(Error::msg(format!(
+                "[line {}] Only maps have properties.",
+                name.line
+            ))),
+        }
+    }
+
+    fn visit_spread_expr(&self, _ellipsis: &Token, _expr: &Box<Expr>) -> Result<Value, Error> {
+        unreachable!("Spread expressions are only parsed in lists and calls, which expand them")
+    }
+
+    fn visit_block_expr(
+        &self,
+        _brace: &Token,
+        body: &BlockBody,
+        value: &Option<Box<Expr>>,
+    ) -> Result<Value, Error> {
+        let environment = Environment::with_enclosing(self.environment.borrow().clone());
+        let previous = self.environment.replace(Rc::new(RefCell::new(environment)));
+        let result = self.execute(body.lowered()).and_then(|()| match value {
+            Some(value) => Ok(value.accept(self)?),
+            None => Ok(Value::Nil),
+        });
+        self.environment.replace(previous);
+
+        match result {
+            Ok(value) => Ok(value),
+            Err(Interrupt::Error(err)) => Err(err),
+            Err(Interrupt::Throw(value, span)) => {
+                self.thrown.borrow_mut().push(value);
+                Err(RuntimeError::Thrown { span }.into())
+            }
+            Err(Interrupt::Break(_) | Interrupt::Continue(_)) => {
+                unreachable!("Block expressions should not be able to exit the loops around them")
+            }
+        }
+    }
+}
+
+/// The length in bytes of the longest string that repeating a string can create, so that a large
+/// count is reported as an error instead of exhausting the memory.
+const MAX_STRING_LENGTH: usize = 1 << 30;
+
+/// Converts the value into a map key, or returns a type mismatch error for the token.
+fn expect_key(token: &Token, key: Value) -> Result<MapKey, Error> {
+    match MapKey::from_value(&key) {
+        Some(key) => Ok(key),
+        None => Err(RuntimeError::type_mismatch(
+            "a number, string, boolean, nil or tuple key",
+            vec![key],
+            token,
+        )),
+    }
+}
+
+/// Converts the value into an index of an existing element of the list.
+fn expect_in_bounds(bracket: &Token, list: &[Value], index: &Value) -> Result<usize, Error> {
+    let index = index.expect_index(bracket, "index")?;
+
+    match index < list.len() {
+        true => Ok(index),
+        false => Err(Error::msg(format!(
+            "[line {}] Index {} is out of bounds for a list of length {}.",
+            bracket.line,
+            index,
+            list.len()
+        ))),
+    }
+}
+
+/// Returns the numbers held by both operands of a binary operator as floats, or a type mismatch
+/// error reporting both of them.
+fn expect_numbers(op: &Operator, left: &Value, right: &Value) -> Result<(f64, f64), Error> {
+    match (left.as_number(), right.as_number()) {
+        (Some(left), Some(right)) => Ok((left, right)),
+        _ => Err(RuntimeError::type_mismatch(
+            "two numbers",
+            vec![left.clone(), right.clone()],
+            op,
+        )),
+    }
+}
+
+/// Applies an arithmetic operator to two numbers. Two integers produce an integer, unless the
+/// result overflows, in which case it is promoted to a float like when either operand is a float.
+fn arithmetic(
+    left: &Value,
+    right: &Value,
+    integer_op: fn(i64, i64) -> Option<i64>,
+    float_op: fn(f64, f64) -> f64,
+) -> Value {
+    if let (Value::Integer(left), Value::Integer(right)) = (left, right) {
+        if let Some(result) = integer_op(*left, *right) {
+            return Value::Integer(result);
+        }
+    }
+
+    let (left, right) = (left.as_number(), right.as_number());
+    Value::Number(float_op(
+        left.expect("The operands should have been checked to be numbers"),
+        right.expect("The operands should have been checked to be numbers"),
+    ))
+}
+
+impl StmtVisitor<Result<(), Interrupt>> for Interpreter {
+    fn visit_expression_stmt(&self, expr: &Expr) -> Result<(), Interrupt> {
+        expr.accept(self)?;
+        Ok(())
+    }
+
+    fn visit_print_stmt(&self, exprs: &Vec<Expr>) -> Result<(), Interrupt> {
+        // All the values are evaluated from left to right before anything is written
+        let values = exprs
+            .iter()
+            .map(|expr| expr.accept(self))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.write_values(&values)?;
+        Ok(())
+    }
+
+    fn visit_assert_stmt(
+        &self,
+        keyword: &Token,
+        condition: &Expr,
+        text: &String,
+        message: &Option<Expr>,
+    ) -> Result<(), Interrupt> {
+        if condition.accept(self)?.is_truthy() {
+            return Ok(());
+        }
+
+        // The message is only evaluated when the assertion fails
+        let message = match message {
+            Some(message) => format!("{} ({})", message.accept(self)?, text),
+            None => text.clone(),
+        };
+        Err(Error::msg(format!(
+            "[line {}] Assertion failed: {}.",
+            keyword.line, message
+        ))
+        .into())
+    }
+
+    fn visit_var_stmt(&self, name: &Token, initializer: &Option<Expr>) -> Result<(), Interrupt> {
+        let value = match initializer {
+            Some(expr) => expr.accept(self)?,
+            None => Value::Nil,
+        };
+
+        self.environment
+            .borrow()
+            .borrow_mut()
+            .declare(name, value)?;
+        Ok(())
+    }
+
+    fn visit_const_stmt(&self, name: &Token, initializer: &Expr) -> Result<(), Interrupt> {
+        let value = initializer.accept(self)?;
+        self.environment
+            .borrow()
+            .borrow_mut()
+            .declare_constant(name, value)?;
+        Ok(())
+    }
+
+    fn visit_var_tuple_stmt(
+        &self,
+        paren: &Token,
+        names: &Vec<Token>,
+        initializer: &Expr,
+    ) -> Result<(), Interrupt> {
+        let value = initializer.accept(self)?;
+        let tuple = match &value {
+            Value::Tuple(tuple) if tuple.len() == names.len() => tuple,
+            value => {
+                return Err(Error::msg(format!(
+                    "[line {}] Cannot unpack {} into {} variables.",
+                    paren.line,
+                    value.debug_string(),
+                    names.len()
+                ))
+                .into())
+            }
+        };
+
+        let environment = self.environment.borrow();
+        for (name, element) in names.iter().zip(tuple.iter()) {
+            environment.borrow_mut().declare(name, element.clone())?;
+        }
+        Ok(())
+    }
+
+    fn visit_block_stmt(&self, stmts: &Vec<Stmt>) -> Result<(), Interrupt> {
+        let environment = Environment::with_enclosing(self.environment.borrow().clone());
+        self.execute_block(stmts, environment)
+    }
+
+    fn visit_if_stmt(
+        &self,
+        condition: &Expr,
+        then_branch: &Box<Stmt>,
+        else_branch: &Option<Box<Stmt>>,
+    ) -> Result<(), Interrupt> {
+        if condition.accept(self)?.is_truthy() {
+            then_branch.accept(self)
+        } else if let Some(else_branch) = else_branch {
+            else_branch.accept(self)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn visit_while_stmt(
+        &self,
+        condition: &Expr,
+        body: &Box<Stmt>,
+        increment: &Option<Expr>,
+        label: &Option<Token>,
+    ) -> Result<(), Interrupt> {
+        while condition.accept(self)?.is_truthy() {
+            match body.accept(self) {
+                Ok(()) => {}
+                Err(Interrupt::Continue(target)) if Interrupt::targets(&target, label) => {}
+                Err(Interrupt::Break(target)) if Interrupt::targets(&target, label) => break,
+                Err(err) => return Err(err),
+            }
+
+            if let Some(increment) = increment {
+                increment.accept(self)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn visit_for_in_stmt(
+        &self,
+        name: &Token,
+        keyword: &Token,
+        iterable: &Expr,
+        body: &Box<Stmt>,
+        label: &Option<Token>,
+    ) -> Result<(), Interrupt> {
+        // The elements are collected before the first iteration, so that the body can change the
+        // value without changing what is iterated over
+        let elements = match iterable.accept(self)? {
+            Value::List(list) => list.borrow().clone(),
+            Value::Tuple(tuple) => tuple.to_vec(),
+            Value::Map(map) => map.borrow().keys().map(MapKey::to_value).collect(),
+            Value::String(str) => str.chars().map(|c| Value::String(c.to_string())).collect(),
+            value => {
+                return Err(RuntimeError::type_mismatch(
+                    "a list, a map, a string or a tuple",
+                    vec![value],
+                    keyword,
+                )
+                .into())
+            }
+        };
+
+        // Each iteration has its own scope, where the variable is bound to the element
+        for element in elements {
+            let mut environment = Environment::with_enclosing(self.environment.borrow().clone());
+            environment.define(name.name(), element);
+
+            match self.execute_block(std::slice::from_ref(&**body), environment) {
+                Ok(()) => {}
+                Err(Interrupt::Continue(target)) if Interrupt::targets(&target, label) => {}
+                Err(Interrupt::Break(target)) if Interrupt::targets(&target, label) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn visit_throw_stmt(&self, keyword: &Token, value: &Expr) -> Result<(), Interrupt> {
+        Err(Interrupt::Throw(value.accept(self)?, Span::from(keyword)))
+    }
+
+    fn visit_try_stmt(
+        &self,
+        body: &Vec<Stmt>,
+        catch: &Option<(Token, Vec<Stmt>)>,
+        finally: &Option<Vec<Stmt>>,
+    ) -> Result<(), Interrupt> {
+        let scope = || Environment::with_enclosing(self.environment.borrow().clone());
+        let rethrow = |interrupt| self.rethrow(interrupt);
+        let mut result = self.execute_block(body, scope()).map_err(rethrow);
+
+        // Runtime errors are caught as their message, while thrown values are caught as they are.
+        // Exiting the program is never caught
+        if let Some((name, handler)) = catch {
+            let caught = match result {
+                Err(Interrupt::Throw(value, _)) => value,
+                Err(Interrupt::Error(err))
+                    if !matches!(err.downcast_ref(), Some(RuntimeError::Exit { .. })) =>
+                {
+                    Value::String(err.to_string())
+                }
+                result => return self.finally(finally, result),
+            };
+
+            let mut environment = scope();
+            environment.define(name.name(), caught);
+            result = self.execute_block(handler, environment).map_err(rethrow);
+        }
+
+        self.finally(finally, result)
+    }
+
+    fn visit_break_stmt(&self, _keyword: &Token, label: &Option<Token>) -> Result<(), Interrupt> {
+        Err(Interrupt::Break(
+            label.as_ref().map(|label| label.name().to_string()),
+        ))
+    }
+
+    fn visit_continue_stmt(
+        &self,
+        _keyword: &Token,
+        label: &Option<Token>,
+    ) -> Result<(), Interrupt> {
+        Err(Interrupt::Continue(
+            label.as_ref().map(|label| label.name().to_string()),
+        ))
+    }
 }
 
 #[test]
 fn test_interpreter() {
-    struct TestCase {
-        description: &'static str,
-        input: &'static str,
-        expected: Value,
-        should_err: bool,
+    let test_cases = vec![
+        // Addition
+        ("print 12 + 34;", Ok("46\n")),
+        // Comparison
+        ("print 12 > 34;", Ok("false\n")),
+        // Nested expressions
+        ("print 1 + (2 - 3) * 4;", Ok("-3\n")),
+        // String concatenation
+        ("print \"Hello\" + \" \" + \"World\";", Ok("Hello World\n")),
+        // Invalid operation
+        (
+            "print 12 + \"Hello\";",
+            Err(
+                "[line 1] Invalid operands for '+': expected two numbers or two strings, got integer(12) and string(\"Hello\").",
+            ),
+        ),
+        // Invalid boolean arithmetic
+        (
+            "print true + false;",
+            Err(
+                "[line 1] Invalid operands for '+': expected two numbers or two strings, got boolean(true) and boolean(false).",
+            ),
+        ),
+        // Invalid boolean operation
+        (
+            "print (1 == 2) + 3;",
+            Err(
+                "[line 1] Invalid operands for '+': expected two numbers or two strings, got boolean(false) and integer(3).",
+            ),
+        ),
+    ];
+
+    check(test_cases);
+}
+
+/// A writer that shares its buffer, so that tests can inspect the output of a program
+/// after it has been handed over to the interpreter.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct SharedOutput(std::rc::Rc<RefCell<Vec<u8>>>);
+
+#[cfg(test)]
+impl SharedOutput {
+    pub fn contents(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
     }
+}
+
+#[cfg(test)]
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Runs the program with the provided configuration, and returns its output.
+#[cfg(test)]
+pub fn run_program(source: &str, config: Config) -> Result<String, Error> {
+    let (output, result) = run_program_with(source, config, |_| {});
+    result.map(|_| output)
+}
+
+/// Runs the program with the provided configuration after preparing the interpreter with the
+/// provided function. Returns the output of the program even if it fails.
+#[cfg(test)]
+pub fn run_program_with(
+    source: &str,
+    config: Config,
+    prepare: impl FnOnce(&Interpreter),
+) -> (String, Result<(), Error>) {
+    let tokens = crate::lexer::lexer::Lexer::with_config(source, config.clone()).get_tokens();
+    let stmts = match super::syntax_tree::SyntaxTree::new(tokens).parse() {
+        Ok(stmts) => super::desugar::lower(stmts),
+        Err(err) => return (String::new(), Err(Error::msg(err.message))),
+    };
+
+    let output = SharedOutput::default();
+    let interpreter = Interpreter::with_output(config, Box::new(output.clone()));
+    prepare(&interpreter);
+    let result = interpreter.interpret(&stmts);
+
+    (output.contents(), result)
+}
+
+/// Runs each program with the default configuration, and checks its output, or the message of
+/// its error.
+#[cfg(test)]
+pub fn check(test_cases: Vec<(&str, Result<&str, &str>)>) {
+    check_with(test_cases, Config::default(), |_| {});
+}
+
+/// Runs each program like `check`, with the provided configuration and after preparing the
+/// interpreter with the provided function.
+#[cfg(test)]
+pub fn check_with(
+    test_cases: Vec<(&str, Result<&str, &str>)>,
+    config: Config,
+    prepare: impl Fn(&Interpreter),
+) {
+    for (input, expected) in test_cases {
+        let (output, result) = run_program_with(input, config.clone(), &prepare);
+        assert_eq!(
+            result.map(|_| output).map_err(|err| err.to_string()),
+            expected.map(String::from).map_err(String::from),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_print_function() {
+    let test_cases = vec![
+        // Print statement
+        ("print 1 + 2;", Ok("3\n")),
+        // Print is not a value in statement mode
+        (
+            "var p = print;",
+            Err("Unexpected type of token, expected a literal but got PRINT"),
+        ),
+    ];
+
+    check(test_cases);
+
+    let test_cases = vec![
+        // Print function with multiple arguments
+        ("print(1, \"a\", true, nil);", Ok("1 a true nil\n")),
+        // Print function without arguments
+        ("print();", Ok("\n")),
+        // Print function passed around as a value
+        (
+            "var p = print; p(\"hello\"); print(p);",
+            Ok("hello\n<native fn print>\n"),
+        ),
+        // Print function shadowed by a variable
+        (
+            "print = 1; var p = print; p(2);",
+            Err("[line 1] Can only call functions and classes."),
+        ),
+        // Print statement is unavailable in function mode
+        (
+            "print 1;",
+            Err("Expected one of types [Semicolon], but got Integer(1)"),
+        ),
+    ];
+
+    let config = Config {
+        print_function: true,
+        ..Config::default()
+    };
+    check_with(test_cases, config, |_| {});
+}
+
+#[test]
+fn test_print_multiple_values() {
+    let test_cases = vec![
+        // Default separator
+        ("print 1, \"two\", 1 + 2;", Ok("1 two 3\n")),
+        // Values are evaluated from left to right
+        ("var a = 1; print a = 2, a, a = a + 1;", Ok("2 2 3\n")),
+        // Nothing is printed if a value fails to evaluate
+        (
+            "print 1, -\"a\";",
+            Err("[line 1] Invalid operand for '-': expected a number, got string(\"a\")."),
+        ),
+        // Trailing comma
+        (
+            "print 1, ;",
+            Err("Unexpected type of token, expected a literal but got Semicolon"),
+        ),
+    ];
+
+    check(test_cases);
+
+    let test_cases = vec![
+        // Custom separator
+        ("print 1, 2, 3;", Ok("1, 2, 3\n")),
+    ];
+    let config = Config {
+        print_separator: ", ".to_string(),
+        ..Config::default()
+    };
+    check_with(test_cases, config, |_| {});
+
+    let test_cases = vec![
+        // Empty separator
+        ("print \"a\", \"b\";", Ok("ab\n")),
+    ];
+    let config = Config {
+        print_separator: "".to_string(),
+        ..Config::default()
+    };
+    check_with(test_cases, config, |_| {});
+}
+
+#[test]
+fn test_debug_native() {
+    let test_cases = vec![
+        // Numbers are tagged
+        ("debug(1.5);", Ok("number(1.5)\n")),
+        // Strings are quoted and escaped
+        ("debug(\"a\tb\");", Ok("string(\"a\\tb\")\n")),
+        // Booleans and nil
+        ("debug(true); debug(nil);", Ok("boolean(true)\nnil\n")),
+        // Natives show their arity
+        ("debug(debug);", Ok("native fn debug(arity 1)\n")),
+        // Wrong number of arguments
+        (
+            "debug(1, 2);",
+            Err("[line 1] Expected 1 arguments but got 2."),
+        ),
+        // Natives have no source
+        ("print source(debug); print source(1);", Ok("nil\nnil\n")),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_error_value_representation() {
+    let err = run_program("print 1 + \"a\";", Config::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[line 1] Invalid operands for '+': expected two numbers or two strings, got integer(1) and string(\"a\")."
+    );
+
+    let err = run_program("print -debug;", Config::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[line 1] Invalid operand for '-': expected a number, got native fn debug(arity 1)."
+    );
+}
+
+#[test]
+fn test_type_mismatch_kind() {
+    let err = run_program("var a = 1;\nprint a < nil;", Config::default()).unwrap_err();
+    match err.downcast_ref::<RuntimeError>() {
+        Some(RuntimeError::TypeMismatch {
+            expected,
+            found,
+            operator,
+            span,
+        }) => {
+            assert_eq!(expected, "two numbers");
+            assert_eq!(found, &vec!["integer(1)", "nil"]);
+            assert_eq!(operator, "<");
+            assert_eq!(span.line, 2);
+        }
+        kind => panic!("Unexpected error kind: {:?}", kind),
+    }
+
+    let err = run_program("print 2 / \"a\";", Config::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[line 1] Invalid operands for '/': expected two numbers, got integer(2) and string(\"a\")."
+    );
+}
+
+#[test]
+fn test_raw_identifiers() {
+    let config = Config {
+        raw_identifiers: true,
+        ..Config::default()
+    };
+
+    let output = run_program(
+        "var `class` = 1; var `var` = `class` + 1; `class` = `var` * 2; print `class`, `var`;",
+        config.clone(),
+    )
+    .unwrap();
+    assert_eq!(output, "4 2\n");
+
+    // Raw identifiers refer to the same variables as the plain names
+    let output = run_program("var a = 1; print `a`; `a` = 2; print a;", config).unwrap();
+    assert_eq!(output, "1\n2\n");
+}
+
+#[test]
+fn test_string_comparison() {
+    let input = "print \"a\" < \"b\", \"b\" <= \"a\", \"abc\" > \"ab\", \"B\" >= \"a\";";
+
+    // Strict Lox only compares numbers
+    let err = run_program(input, Config::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[line 1] Invalid operands for '<': expected two numbers, or two strings with the --string-comparison flag, got string(\"a\") and string(\"b\")."
+    );
+
+    let config = Config {
+        string_comparison: true,
+        ..Config::default()
+    };
+    let output = run_program(input, config.clone()).unwrap();
+    assert_eq!(output, "true false true false\n");
+
+    // The flag does not allow comparing strings with other types
+    assert!(run_program("print \"1\" < 2;", config).is_err());
+}
+
+#[test]
+fn test_string_repetition() {
+    // Strict Lox only multiplies numbers
+    assert!(run_program("print \"ab\" * 3;", Config::default()).is_err());
+
+    let config = Config {
+        string_repetition: true,
+        ..Config::default()
+    };
 
     let test_cases = vec![
-        TestCase {
-            description: "Addition",
-            input: "12 + 34",
-            expected: Value::Number(46.0),
-            should_err: false,
-        },
-        TestCase {
-            description: "Comparison",
-            input: "12 > 34",
-            expected: Value::Boolean(false),
-            should_err: false,
-        },
-        TestCase {
-            description: "Nested expressions",
-            input: "1 + (2 - 3) * 4",
-            expected: Value::Number(-3.0),
-            should_err: false,
-        },
-        TestCase {
-            description: "String concatenation",
-            input: "\"Hello\" + \" \" + \"World\"",
-            expected: Value::String("Hello World".to_string()),
-            should_err: false,
-        },
-        TestCase {
-            description: "Invalid operation",
-            input: "12 + \"Hello\"",
-            expected: Value::Nil,
-            should_err: true,
-        },
-        TestCase {
-            description: "Invalid boolean arithmetic",
-            input: "true + false",
-            expected: Value::Nil,
-            should_err: true,
-        },
-        TestCase {
-            description: "Invalid boolean operation",
-            input: "(1 == 2) + 3",
-            expected: Value::Nil,
-            should_err: true,
-        },
+        ("print \"ab\" * 3;", Ok("ababab\n")),
+        ("print \"ab\" * 0;", Ok("\n")),
+        ("print \"\" * 5;", Ok("\n")),
+        (
+            "print \"ab\" * 1.5;",
+            Err("[line 1] Invalid count for '*': number(1.5) is not an integer."),
+        ),
+        ("print\n\"ab\" * -2;", Err("[line 2] Invalid count for '*': integer(-2) is negative.")),
+        (
+            "print \"abc\" * 9223372036854775807;",
+            Err("[line 1] Invalid count for '*': integer(9223372036854775807) is too large."),
+        ),
+        (
+            "print \"ab\" * 1073741824;",
+            Err("[line 1] Invalid count for '*': integer(1073741824) is too large."),
+        ),
+        (
+            "print 3 * \"ab\";",
+            Err(
+                "[line 1] Invalid operands for '*': expected two numbers, or a string and a count, got integer(3) and string(\"ab\").",
+            ),
+        ),
+    ];
+
+    check_with(test_cases, config, |_| {});
+}
+
+#[test]
+fn test_control_flow() {
+    let test_cases = vec![
+        // Block scopes shadow outer variables
+        ("var a = 1; { var a = 2; print a; } print a;", Ok("2\n1\n")),
+        // Blocks assign to outer variables
+        ("var a = 1; { a = 2; } print a;", Ok("2\n")),
+        // If and else
+        (
+            "if (1 > 2) print \"then\"; else print \"else\"; if (nil) print 1;",
+            Ok("else\n"),
+        ),
+        // Logical operators return the deciding operand
+        (
+            "print nil or \"yes\", 1 and 2, false and -nil;",
+            Ok("yes 2 false\n"),
+        ),
+        // While loop
+        (
+            "var i = 0; while (i < 3) { print i; i = i + 1; }",
+            Ok("0\n1\n2\n"),
+        ),
+        // For loop
+        (
+            "for (var i = 0; i < 3; i = i + 1) print i;",
+            Ok("0\n1\n2\n"),
+        ),
+        // For loop variable is scoped to the loop
+        (
+            "var i = \"outer\"; for (var i = 0; i < 1; i = i + 1) {} print i;",
+            Ok("outer\n"),
+        ),
     ];
 
-    for test in test_cases {
-        let tokens = crate::lexer::lexer::Lexer::new(test.input).get_tokens();
-        let mut parser = super::syntax_tree::SyntaxTree::new(tokens);
-        let expr = parser.expression().unwrap();
-        let interpreter = Interpreter::new(expr);
-
-        match (interpreter.evaluate(), test.should_err) {
-            (Ok(val), false) => assert_eq!(val, test.expected),
-            (Err(_), true) => {}
-            (res, _) => panic!(
-                "Test failed: {}\nExpected: {:?}\nGot: {:?}",
-                test.description, test.expected, res
+    check(test_cases);
+
+    // The scope of a block is exited even when it fails
+    let output = SharedOutput::default();
+    let interpreter = Interpreter::with_output(Config::default(), Box::new(output.clone()));
+    let tokens = crate::lexer::lexer::Lexer::new("var a = 1; { var a = 2; -nil; }").get_tokens();
+    let stmts = super::desugar::lower(super::syntax_tree::SyntaxTree::new(tokens).parse().unwrap());
+    assert!(interpreter.interpret(&stmts).is_err());
+
+    let tokens = crate::lexer::lexer::Lexer::new("print a;").get_tokens();
+    let stmts = super::desugar::lower(super::syntax_tree::SyntaxTree::new(tokens).parse().unwrap());
+    interpreter.interpret(&stmts).unwrap();
+    assert_eq!(output.contents(), "1\n");
+}
+
+#[test]
+fn test_continue() {
+    let test_cases = vec![
+        // Continue in a while loop
+        ("var i = 0; while (i < 4) { i = i + 1; if (i == 2) continue; print i; }", Ok("1\n3\n4\n")),
+        // Continue in a for loop runs the increment
+        ("for (var i = 0; i < 4; i = i + 1) { if (i == 1) continue; print i; }", Ok("0\n2\n3\n")),
+        // Continue only affects the innermost loop
+        (
+            "for (var i = 0; i < 2; i = i + 1) for (var j = 0; j < 2; j = j + 1) { if (j == 0) continue; print i, j; }",
+            Ok("0 1\n1 1\n"),
+        ),
+        // Continue exits the scopes of the loop body
+        (
+            "var a = 1; for (var i = 0; i < 2; i = i + 1) { var a = 2; { var a = 3; continue; } } print a;",
+            Ok("1\n"),
+        ),
+        // Continue outside of a loop
+        ("continue;", Err("Can't use 'continue' outside of a loop.")),
+        // Continue in a block outside of a loop
+        ("while (false) {} { continue; }", Err("Can't use 'continue' outside of a loop.")),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_break_and_labels() {
+    let test_cases = vec![
+        // Break exits the innermost loop
+        ("for (var i = 0; i < 2; i = i + 1) while (true) { print i; break; }", Ok("0\n1\n")),
+        // Break skips the increment
+        ("var i = 0; for (; i < 5; i = i + 1) if (i == 2) break; print i;", Ok("2\n")),
+        // Labeled break exits the outer loop
+        (
+            "outer: for (var i = 0; i < 3; i = i + 1) for (var j = 0; j < 3; j = j + 1) { if (j == 1) break outer; print i, j; } print \"done\";",
+            Ok("0 0\ndone\n"),
+        ),
+        // Labeled continue skips to the next iteration of the outer loop
+        (
+            "outer: for (var i = 0; i < 2; i = i + 1) { for (var j = 0; j < 3; j = j + 1) { if (j == 1) continue outer; print i, j; } print \"unreachable\"; }",
+            Ok("0 0\n1 0\n"),
+        ),
+        // Label of the innermost loop
+        ("a: while (true) { b: while (true) break b; break a; } print 1;", Ok("1\n")),
+        // Labels can be reused by sibling loops
+        ("a: while (true) break a; a: while (true) break a; print 1;", Ok("1\n")),
+        // Break outside of a loop
+        ("break;", Err("Can't use 'break' outside of a loop.")),
+        // Undefined label
+        ("while (true) break outer;", Err("Undefined label 'outer'.")),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_number_output() {
+    let test_cases = vec![
+        ("print 10 / 2;", "5\n"),
+        ("print 2.5 * 2, 1.5;", "5 1.5\n"),
+        ("print 1 / 0, -1 / 0;", "Infinity -Infinity\n"),
+        ("print 0 / 0;", "NaN\n"),
+        ("debug(4 / 2);", "number(2)\n"),
+        // Numbers are doubles, like in the reference implementation
+        ("print 0.1 + 0.2;", "0.30000000000000004\n"),
+        ("print 16777217;", "16777217\n"),
+    ];
+
+    for (input, expected) in test_cases {
+        assert_eq!(
+            run_program(input, Config::default()).unwrap(),
+            expected,
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_lists() {
+    let test_cases = vec![
+        // List literals
+        ("print [1, \"a\", nil, [true]], [], [1,];", Ok("[1, \"a\", nil, [true]] [] [1]\n")),
+        // Indexing
+        ("var xs = [1, [2, 3]]; print xs[0], xs[1][1], xs[1 - 1];", Ok("1 3 1\n")),
+        // Index assignment returns the assigned value
+        ("var xs = [1, 2]; print xs[1] = 5; print xs;", Ok("5\n[1, 5]\n")),
+        // Lists are shared by reference
+        (
+            "var xs = [1]; var ys = xs; ys[0] = 2; print xs, xs == ys, xs == [2];",
+            Ok("[2] true false\n"),
+        ),
+        // Lists containing themselves
+        ("var xs = [1]; xs[0] = xs; print xs; debug(xs);", Ok("[[...]]\nlist[list[...]]\n")),
+        // Index out of bounds
+        (
+            "var xs = [1, 2];\nprint xs[2];",
+            Err("[line 2] Index 2 is out of bounds for a list of length 2."),
+        ),
+        // Assignment out of bounds
+        (
+            "var xs = []; xs[0] = 1;",
+            Err("[line 1] Index 0 is out of bounds for a list of length 0."),
+        ),
+        // Negative index
+        ("print [1][-1];", Err("[line 1] Invalid index for '[': integer(-1) is negative.")),
+        // Fractional index
+        ("print [1][0.5];", Err("[line 1] Invalid index for '[': number(0.5) is not an integer.")),
+        // Indexing a value that is not a list
+        (
+            "print \"abc\"[0];",
+            Err(
+                "[line 1] Invalid operand for '[': expected a list, a map or a tuple, got string(\"abc\").",
+            ),
+        ),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_maps() {
+    let test_cases = vec![
+        // Map literals
+        ("print {\"a\": [1]}, {}, {1: 2,};", Ok("{\"a\": [1]} {} {1: 2}\n")),
+        // Entries are kept in insertion order
+        (
+            "var m = {\"b\": 1, \"a\": 2}; m[\"c\"] = 3; m[\"b\"] = 4; print m; for (k in m) print k;",
+            Ok("{\"b\": 4, \"a\": 2, \"c\": 3}\nb\na\nc\n"),
+        ),
+        // Reading keys
+        (
+            "var m = {\"a\": 1, 2: \"two\", nil: false}; print m[\"a\"], m[1 + 1], m[nil];",
+            Ok("1 two false\n"),
+        ),
+        // Variables can be keys
+        ("var k = \"x\"; var m = {k: 1}; print m, {k: k};", Ok("{\"x\": 1} {\"x\": \"x\"}\n")),
+        // Missing keys are nil
+        ("print {}[\"a\"];", Ok("nil\n")),
+        // Setting keys
+        ("var m = {}; m[\"a\"] = 1; m[\"a\"] = m[\"a\"] + 1; print m;", Ok("{\"a\": 2}\n")),
+        // Later entries replace earlier ones
+        ("print {0: 1, -0: 2};", Ok("{0: 2}\n")),
+        // Maps are shared by reference
+        ("var m = {}; var n = m; n[1] = 1; print m, m == n, {} == {};", Ok("{1: 1} true false\n")),
+        // Braces at the start of a statement open a block
+        ("{ print {1: 2}[1]; }", Ok("2\n")),
+        // Properties are string keys
+        (
+            "var m = {\"a\": {\"b\": 1}}; print m.a.b, m.a[\"b\"] == m[\"a\"].b, m.c;",
+            Ok("1 true nil\n"),
+        ),
+        // Only maps have properties
+        ("var xs = [1];\nprint xs.length;", Err("[line 2] Only maps have properties.")),
+        // Lists can not be keys
+        (
+            "var m = {}; m[[]] = 1;",
+            Err(
+                "[line 1] Invalid operand for '[': expected a number, string, boolean, nil or tuple key, got list[].",
+            ),
+        ),
+        // Lists can not be keys in literals
+        (
+            "print {[]: 1};",
+            Err(
+                "[line 1] Invalid operand for '{': expected a number, string, boolean, nil or tuple key, got list[].",
             ),
+        ),
+    ];
+
+    check(test_cases);
+
+    let config = Config {
+        missing_key_error: true,
+        ..Config::default()
+    };
+    assert_eq!(
+        run_program("print {\"a\": 1}[\"a\"];", config.clone()).unwrap(),
+        "1\n"
+    );
+    assert_eq!(
+        run_program("var m = {};\nprint m[\"b\"];", config.clone())
+            .unwrap_err()
+            .to_string(),
+        "[line 2] Undefined key string(\"b\")."
+    );
+    assert_eq!(
+        run_program("var m = {};\nprint m.b;", config)
+            .unwrap_err()
+            .to_string(),
+        "[line 2] Undefined key string(\"b\")."
+    );
+}
+
+#[test]
+fn test_switch() {
+    let test_cases = vec![
+        // First matching case
+        (
+            "switch (2) { case 1: print \"one\"; case 2: print \"two\"; case 2: print \"again\"; }",
+            Ok("two\n"),
+        ),
+        // Multiple patterns and negative numbers
+        (
+            "for (var i = -1; i < 3; i = i + 1) switch (i) { case -1, 1: print \"odd\"; default: print \"even\"; }",
+            Ok("odd\neven\nodd\neven\n"),
+        ),
+        // String, boolean and nil patterns
+        (
+            "switch (\"a\") { case nil, false: print 1; case \"a\": { print 2; print 3; } }",
+            Ok("2\n3\n"),
+        ),
+        // No matching case and no default
+        ("switch (5) { case 1: print 1; } print \"done\";", Ok("done\n")),
+        // Subject is evaluated once
+        ("var n = 0; switch (n = n + 1) { case 5: print 5; case 6: print 6; } print n;", Ok("1\n")),
+        // Break exits the enclosing loop
+        (
+            "while (true) { switch (1) { case 1: break; } print \"unreachable\"; } print \"done\";",
+            Ok("done\n"),
+        ),
+        // Nested switches
+        (
+            "switch (1) { case 1: switch (2) { case 2: print \"inner\"; } default: print \"outer\"; }",
+            Ok("inner\n"),
+        ),
+        // Default must be last
+        (
+            "switch (1) { default: print 1; case 1: print 2; }",
+            Err("The default case must be the last case of a switch."),
+        ),
+        // Only literal patterns
+        (
+            "var a = 1; switch (1) { case a: print 1; }",
+            Err("Expected a literal pattern in the switch case."),
+        ),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_do_while() {
+    let test_cases = vec![
+        // Body runs before the condition is checked
+        ("do print 1; while (false);", Ok("1\n")),
+        // Loops while the condition is truthy
+        ("var i = 0; do { print i; i = i + 1; } while (i < 3);", Ok("0\n1\n2\n")),
+        // Continue checks the condition
+        (
+            "var i = 0; do { i = i + 1; if (i < 3) continue; print i; } while (i < 2); print i;",
+            Ok("2\n"),
+        ),
+        // Break and labels
+        (
+            "var i = 0; outer: do { do { i = i + 1; if (i == 2) break outer; } while (true); } while (true); print i;",
+            Ok("2\n"),
+        ),
+        // Nested loops
+        (
+            "var i = 0; do { var j = 0; do { print i + j; j = j + 1; } while (j < 2); i = i + 10; } while (i < 20);",
+            Ok("0\n1\n10\n11\n"),
+        ),
+        // Missing semicolon
+        ("do print 1; while (false)", Err("Expected one of types [Semicolon], but got EOF")),
+        // Missing while
+        ("do print 1;", Err("Expected one of types [WHILE], but got EOF")),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_integers() {
+    let test_cases = vec![
+        (
+            "debug(7); debug(7.0); debug(0x10);",
+            "integer(7)\nnumber(7)\ninteger(16)\n",
+        ),
+        (
+            "debug(1 + 2); debug(1 + 2.0); debug(2 * 3); debug(5 - 7);",
+            "integer(3)\nnumber(3)\ninteger(6)\ninteger(-2)\n",
+        ),
+        // Division always produces a float
+        ("debug(6 / 3); print 7 / 2;", "number(2)\n3.5\n"),
+        // Integers are exact beyond the integers that floats can hold
+        ("print 9007199254740993 + 2;", "9007199254740995\n"),
+        ("print 9007199254740993 > 9007199254740992;", "true\n"),
+        // Overflowing results are promoted to floats
+        (
+            "debug(9223372036854775807 + 1);",
+            "number(9223372036854776000)\n",
+        ),
+        (
+            "debug(-(-9223372036854775807 - 1));",
+            "number(9223372036854776000)\n",
+        ),
+        // Integers and floats are compared and indexed by their values
+        ("print 1 == 1.0, 2 < 2.5, 2.0 >= 2;", "true true true\n"),
+        (
+            "var m = {1: \"a\"}; print m[1.0]; m[2.0] = \"b\"; print m[2];",
+            "a\nb\n",
+        ),
+        ("print [10, 20][1.0];", "20\n"),
+        (
+            "var i = 0; while (i < 3) i = i + 1; debug(i);",
+            "integer(3)\n",
+        ),
+    ];
+
+    for (input, expected) in test_cases {
+        assert_eq!(
+            run_program(input, Config::default()).unwrap(),
+            expected,
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_equality() {
+    let test_cases = vec![
+        ("print nil == nil, nil != nil;", "true false\n"),
+        // Values of different types are never equal, even when they are both falsey
+        (
+            "print nil == false, 0 == false, \"\" == nil, \"1\" == 1;",
+            "false false false false\n",
+        ),
+        (
+            "print debug == debug, debug == help, debug == \"debug\", [] == nil, {} == [];",
+            "true false false false false\n",
+        ),
+        ("print nil != false, [] != {};", "true true\n"),
+    ];
+
+    for (input, expected) in test_cases {
+        assert_eq!(
+            run_program(input, Config::default()).unwrap(),
+            expected,
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_const() {
+    let test_cases = vec![
+        ("const a = 1 + 2; print a;", Ok("3\n")),
+        (
+            "const a = 1; a = 2;",
+            Err("[line 1] Cannot assign to constant 'a'."),
+        ),
+        (
+            "const a = 1;\n{ a = 2; }",
+            Err("[line 2] Cannot assign to constant 'a'."),
+        ),
+        // Inner scopes can declare their own variable with the same name
+        (
+            "const a = 1; { var a = 2; a = 3; print a; } print a;",
+            Ok("3\n1\n"),
+        ),
+        // The constant can not be replaced by another declaration in the same scope
+        (
+            "const a = 1; var a = 2; print a;",
+            Err("[line 1] Cannot redeclare constant 'a'."),
+        ),
+        (
+            "const a = 1;\nconst a = 2;",
+            Err("[line 2] Cannot redeclare constant 'a'."),
+        ),
+        (
+            "const a = 1; var (a, b) = (2, 3);",
+            Err("[line 1] Cannot redeclare constant 'a'."),
+        ),
+        // Only the binding is constant, not the value it refers to
+        ("const xs = [1]; xs[0] = 2; print xs;", Ok("[2]\n")),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_tuples() {
+    let test_cases = vec![
+        ("print (1, \"a\", nil), (), (1,), (1);", Ok("(1, \"a\", nil) () (1,) 1\n")),
+        ("debug((1, (2.5,)));", Ok("tuple(integer(1), tuple(number(2.5),))\n")),
+        ("var t = (1, 2); print t[0] + t[1];", Ok("3\n")),
+        // Tuples are compared by their elements, and can be used as keys
+        ("print (1, 2) == (1, 2.0), (1, 2) == (2, 1), () == ();", Ok("true false true\n")),
+        ("var m = {(0, 1): \"a\"}; print m[(0, 1)];", Ok("a\n")),
+        (
+            "var m = {([], 1): \"a\"};",
+            Err("[line 1] Invalid operand for '{': expected a number, string, boolean, nil or tuple key, got tuple(list[], integer(1))."),
+        ),
+        (
+            "var t = (1, 2); t[0] = 3;",
+            Err("[line 1] Invalid operand for '[': expected a list or a map, got tuple(integer(1), integer(2))."),
+        ),
+        // Tuples are unpacked into variables of the current scope
+        ("var (x, y) = (1, 2); print x, y;", Ok("1 2\n")),
+        ("var (a, b) = (1, 2); var (a, b) = (b, a); print a, b;", Ok("2 1\n")),
+        ("var () = (); { var (x,) = (1,); } print \"ok\";", Ok("ok\n")),
+        (
+            "var (x, y) = (1, 2, 3);",
+            Err("[line 1] Cannot unpack tuple(integer(1), integer(2), integer(3)) into 2 variables."),
+        ),
+        (
+            "var (x, y) = [1, 2];",
+            Err("[line 1] Cannot unpack list[integer(1), integer(2)] into 2 variables."),
+        ),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_destructuring() {
+    let test_cases = vec![
+        ("var [a, b] = [1, 2, 3]; print a, b;", Ok("1 2\n")),
+        (
+            "var {x, y} = {\"x\": 1, \"y\": 2}; print x, y;",
+            Ok("1 2\n"),
+        ),
+        // The value is evaluated once, before any of the variables is declared
+        (
+            "var xs = [1, 2]; var [b, a] = [xs[1], xs[0]]; print a, b;",
+            Ok("1 2\n"),
+        ),
+        ("var {x, z} = {\"x\": 1}; print x, z;", Ok("1 nil\n")),
+        (
+            "var [a, b] = [1];",
+            Err("[line 1] Index 1 is out of bounds for a list of length 1."),
+        ),
+        (
+            "var [a] = nil;",
+            Err("[line 1] Invalid operand for '[': expected a list, a map or a tuple, got nil."),
+        ),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_for_in() {
+    let test_cases = vec![
+        ("for (x in [1, 2, 3]) print x;", Ok("1\n2\n3\n")),
+        ("for (x in (1, \"a\")) print x;", Ok("1\na\n")),
+        ("for (c in \"ab\") print c;", Ok("a\nb\n")),
+        ("for (k in {\"a\": 1}) print k;", Ok("a\n")),
+        ("for (x in []) print x; print \"done\";", Ok("done\n")),
+        // The elements are read once, before the first iteration
+        (
+            "var xs = [1, 2]; for (x in xs) { xs[1] = 5; print x; }",
+            Ok("1\n2\n"),
+        ),
+        // Each iteration binds a new variable, which is not visible after the loop
+        (
+            "var x = 0; for (x in [1, 2]) {} print x;",
+            Ok("0\n"),
+        ),
+        (
+            "outer: for (x in [1, 2, 3]) { for (y in [10, 20]) { if (y == 20) continue outer; if (x == 3) break outer; print x + y; } }",
+            Ok("11\n12\n"),
+        ),
+        (
+            "for (x in 1) print x;",
+            Err("[line 1] Invalid operand for 'in': expected a list, a map, a string or a tuple, got integer(1)."),
+        ),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_try() {
+    let test_cases = vec![
+        (
+            "try { print 1; throw \"oops\"; print 2; } catch (e) { print e; }",
+            Ok("1\noops\n"),
+        ),
+        // Runtime errors are caught as their message
+        (
+            "try { print x; } catch (e) { print e; }",
+            Ok("[line 1] Undefined variable 'x'.\n"),
+        ),
+        ("try { throw (1, 2); } catch (e) { print e[1]; }", Ok("2\n")),
+        (
+            "try { print 1; } catch (e) { print e; } finally { print 2; }",
+            Ok("1\n2\n"),
+        ),
+        (
+            "try { try { throw 1; } finally { print \"inner\"; } } catch (e) { print e; }",
+            Ok("inner\n1\n"),
+        ),
+        (
+            "try { throw 1; } catch (e) { throw e + 1; } finally { print \"finally\"; }",
+            Err("[line 1] Uncaught exception: 2."),
+        ),
+        // Jumps out of the body run the finally block on their way out
+        (
+            "for (i in [1, 2, 3]) { try { if (i == 2) continue; if (i == 3) break; print i; } finally { print \"f\"; } }",
+            Ok("1\nf\nf\nf\n"),
+        ),
+        // A jump out of the finally block discards the thrown value
+        (
+            "while (true) { try { throw 1; } finally { break; } } print \"done\";",
+            Ok("done\n"),
+        ),
+        // The caught variable is only visible in the handler
+        (
+            "var e = 0; try { throw 1; } catch (e) {} print e;",
+            Ok("0\n"),
+        ),
+        (
+            "print 1;\nthrow \"bye\";",
+            Err("[line 2] Uncaught exception: bye."),
+        ),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_assert() {
+    let test_cases = vec![
+        ("assert 1 == 1; print \"ok\";", Ok("ok\n")),
+        (
+            "var xs = [1];\nassert xs[0] > 1;",
+            Err("[line 2] Assertion failed: xs[0] > 1."),
+        ),
+        (
+            "var x = 1; assert x == 2, \"x is \" + \"wrong\";",
+            Err("[line 1] Assertion failed: x is wrong (x == 2)."),
+        ),
+        // The message is only evaluated when the assertion fails
+        ("assert true, missing; print 1;", Ok("1\n")),
+        (
+            "try { assert nil; } catch (e) { print e; }",
+            Ok("[line 1] Assertion failed: nil.\n"),
+        ),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_stacktrace() {
+    let test_cases =
+        vec![
+        ("print stacktrace();", Ok("[(\"script\", 1)]\n")),
+        (
+            "var frames = stacktrace();\n\nvar (name, line) = stacktrace()[0];\nprint name, line;",
+            Ok("script 3\n"),
+        ),
+        // The arguments of a call are evaluated before the call, so its frame is not included
+        ("debug(stacktrace()[0][1]);", Ok("integer(1)\n")),
+        ("stacktrace(1);", Err("[line 1] Expected 0 arguments but got 1.")),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_logging() {
+    let run = |source: &str, config: Config| {
+        let logged = Rc::new(RefCell::new(Vec::new()));
+        let messages = logged.clone();
+        let logger = Box::new(move |level: LogLevel, message: &str| {
+            messages.borrow_mut().push(format!("{} {}", level, message));
+        });
+
+        let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+        let stmts = super::syntax_tree::SyntaxTree::new(tokens).parse().unwrap();
+        let stmts = super::desugar::lower(stmts);
+        let interpreter = Interpreter::with_logger(config, Box::new(std::io::sink()), logger);
+        interpreter.interpret(&stmts)?;
+
+        let logged = logged.borrow().clone();
+        Ok::<_, Error>(logged)
+    };
+
+    let source = "log.debug(\"a\"); log.info(\"b\"); log.warn(1 + 1); log.error([nil]);";
+    assert_eq!(
+        run(source, Config::default()).unwrap(),
+        vec!["info b", "warn 2", "error [nil]"]
+    );
+
+    let config = Config {
+        log_level: LogLevel::Debug,
+        ..Config::default()
+    };
+    assert_eq!(run(source, config).unwrap().len(), 4);
+
+    let config = Config::from_flags(&["--log-level=error".to_string()]).unwrap();
+    assert_eq!(run(source, config).unwrap(), vec!["error [nil]"]);
+    assert!(Config::from_flags(&["--log-level=trace".to_string()]).is_err());
+
+    assert_eq!(
+        run("log.info();", Config::default())
+            .unwrap_err()
+            .to_string(),
+        "[line 1] Expected 1 arguments but got 0."
+    );
+}
+
+#[test]
+fn test_is() {
+    let test_cases = vec![
+        (
+            "print 1 is Number, 1.5 is Number, \"a\" is String, true is Boolean, nil is Nil;",
+            Ok("true true true true true\n"),
+        ),
+        (
+            "print [] is List, {} is Map, (1, 2) is Tuple, debug is Function, [] is Map;",
+            Ok("true true true true false\n"),
+        ),
+        // `is` binds like a comparison
+        ("print 1 + 1 is Number, !(nil is Boolean);", Ok("true true\n")),
+        (
+            "print 1 is Foo;",
+            Err("Unknown type 'Foo', expected one of Boolean, Function, List, Map, Nil, Number, String, Tuple."),
+        ),
+        ("print 1 is \"Number\";", Err("Expected a type name after 'is'.")),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_spread() {
+    let test_cases = vec![
+        (
+            "var xs = [1, 2]; print [...xs, 3], [0, ...(4, 5), ...[]];",
+            Ok("[1, 2, 3] [0, 4, 5]\n"),
+        ),
+        // Spreading copies the elements into a new list
+        (
+            "var xs = [1]; var ys = [...xs]; ys[0] = 2; print xs, ys;",
+            Ok("[1] [2]\n"),
+        ),
+        ("debug(...[\"a\"]);", Ok("string(\"a\")\n")),
+        // The arity is checked after the arguments are expanded
+        (
+            "debug(...[1, 2]);",
+            Err("[line 1] Expected 1 arguments but got 2."),
+        ),
+        (
+            "print [...1];",
+            Err("[line 1] Invalid operand for '...': expected a list or a tuple, got integer(1)."),
+        ),
+        (
+            "print ...[1];",
+            Err("Unexpected type of token, expected a literal but got DotDotDot"),
+        ),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_typeof() {
+    let test_cases = vec![
+        (
+            "print typeof 1, typeof 1.5, typeof \"a\", typeof true, typeof nil;",
+            Ok("number number string boolean nil\n"),
+        ),
+        (
+            "print typeof debug, typeof [], typeof {}, typeof (1,), typeof typeof 1;",
+            Ok("function list map tuple string\n"),
+        ),
+        // `typeof` applies to the operand right after it
+        ("print typeof 1 == \"number\";", Ok("true\n")),
+        (
+            "print typeof 1 + 2;",
+            Err("[line 1] Invalid operands for '+': expected two numbers or two strings, got string(\"number\") and integer(2)."),
+        ),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_block_expressions() {
+    let test_cases = vec![
+        ("var x = { var t = 3; t * 2 }; print x;", Ok("6\n")),
+        ("print { 1; 2 }, { 1; }, {};", Ok("2 nil {}\n")),
+        ("print { print 1; } == nil;", Ok("1\ntrue\n")),
+        // The value of a block expression is the expression that ends it without a semicolon
+        ("print { if (true) { 1; } 2 };", Ok("2\n")),
+        // Inside a block expression a brace opens a nested block, as at the start of a statement
+        ("var x = { { print 1; } 2 }; print x;", Ok("1\n2\n")),
+        // Blocks have their own scope
+        (
+            "var t = 1; var x = { var t = 2; t }; print t, x;",
+            Ok("1 2\n"),
+        ),
+        (
+            "var x = { var t = 2; t }; print t;",
+            Err("[line 1] Undefined variable 't'."),
+        ),
+        // A brace followed by a key and a colon opens a map
+        ("print { \"a\": 1 }, { \"a\" };", Ok("{\"a\": 1} a\n")),
+        // A name and a colon open a map too, unless a loop follows them as its label
+        ("var k = 1; print { k: 2 };", Ok("{1: 2}\n")),
+        ("print { a: while (true) break a; 3 };", Ok("3\n")),
+        // Values thrown inside the block unwind to the enclosing try statement
+        (
+            "try { print { throw 1; 2 }; } catch (e) { print \"caught\", e; }",
+            Ok("caught 1\n"),
+        ),
+        (
+            "try { print { try { throw 1; } catch (e) { print e; } 2 }; } catch (e) { print e; }",
+            Ok("1\n2\n"),
+        ),
+        (
+            "try { print { { throw [1]; } }; } finally { print \"done\"; }",
+            Err("[line 1] Uncaught exception: [1]."),
+        ),
+        (
+            "print { throw \"a\"; };",
+            Err("[line 1] Uncaught exception: a."),
+        ),
+        (
+            "while (true) { var x = { break; }; }",
+            Err("Can't use 'break' outside of a loop."),
+        ),
+        (
+            "print { 1 2 };",
+            Err("Expected ';' or '}' after expression."),
+        ),
+    ];
+
+    check(test_cases);
+}
+
+#[test]
+fn test_unwinding() {
+    // Programs that leave scopes, calls and block expressions in every way they can end
+    let corpus = [
+        "{ var a = 1; { var b = 2; print c; } }",
+        "{ var a = 1; { throw a; } }",
+        "try { { var a = 1; throw a; } } catch (e) { { print e; } }",
+        "try { { print 1 + nil; } } finally { var a = 1; }",
+        "try { throw 1; } finally { { throw 2; } }",
+        "outer: while (true) { { var a = 1; while (true) { { break outer; } } } }",
+        "for (var i = 0; i < 3; i = i + 1) { { var a = i; if (a < 2) continue; } print i; }",
+        "for (x in [1, 2]) { var y = { var z = x; ({ z }) }; }",
+        "var x = { var a = 1; ({ var b = 2; a + nil }) };",
+        "var x = { var a = 1; try { { throw a; } } catch (e) { } { throw 2; } };",
+        "try { var x = { var a = { throw 1; }; }; } catch (e) { print e; }",
+        "print debug({ throw [stacktrace()]; });",
+        "print stacktrace(1);",
+        "debug(debug(1, 2));",
+        "assert { var a = false; a }, { var m = \"message\"; m };",
+        "switch ({ var a = 1; a }) { case 1: { throw 1; } }",
+        "do { var a = 1; { break; } } while (true);",
+    ];
+
+    let output = SharedOutput::default();
+    let interpreter = Interpreter::with_output(Config::default(), Box::new(output));
+    let globals = interpreter.environment.borrow().clone();
+
+    for source in corpus {
+        let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+        let stmts = super::syntax_tree::SyntaxTree::new(tokens).parse().unwrap();
+        let _ = interpreter.interpret(&super::desugar::lower(stmts));
+
+        // However the program ended, its scopes, calls and thrown values have all been popped
+        assert!(
+            Rc::ptr_eq(&interpreter.environment.borrow(), &globals),
+            "{}",
+            source
+        );
+        assert!(interpreter.calls.borrow().is_empty(), "{}", source);
+        assert!(interpreter.thrown.borrow().is_empty(), "{}", source);
+    }
+
+    // Expressions evaluated on their own unwind in the same way
+    let tokens = crate::lexer::lexer::Lexer::new("{ var a = 1; { throw a; } }").get_tokens();
+    let expr = super::syntax_tree::SyntaxTree::new(tokens)
+        .parse_expression()
+        .unwrap();
+    assert_eq!(
+        interpreter
+            .evaluate(&super::desugar::lower_expr(expr))
+            .map_err(|err| err.to_string()),
+        Err("[line 1] Uncaught exception: 1.".to_string())
+    );
+    assert!(Rc::ptr_eq(&interpreter.environment.borrow(), &globals));
+    assert!(interpreter.thrown.borrow().is_empty());
+}
+
+#[test]
+fn test_conversions() {
+    // Strict Lox rejects operands of different types
+    assert_eq!(
+        run_program("print \"a\" + 1;", Config::default()).map_err(|err| err.to_string()),
+        Err("[line 1] Invalid operands for '+': expected two numbers or two strings, got string(\"a\") and integer(1).".to_string())
+    );
+
+    let config = Config::from_flags(&["--conversions=lenient".to_string()]).unwrap();
+    assert_eq!(config.conversions, Conversions::Lenient);
+    assert!(Config::from_flags(&["--conversions=loose".to_string()]).is_err());
+
+    let test_cases = vec![
+        ("print \"a\" + 1, 1.5 + \"b\", \"a\" + \"b\";", Ok("a1 1.5b ab\n")),
+        ("print \"n: \" + 1 + 2, 1 + 2 + \"!\";", Ok("n: 12 3!\n")),
+        // Only numbers are converted, and only when the other operand is a string
+        (
+            "print \"a\" + nil;",
+            Err("[line 1] Invalid operands for '+': expected two numbers, or a string and a string or number, got string(\"a\") and nil."),
+        ),
+        (
+            "print true + 1;",
+            Err("[line 1] Invalid operands for '+': expected two numbers, or a string and a string or number, got boolean(true) and integer(1)."),
+        ),
+        (
+            "print \"a\" - 1;",
+            Err("[line 1] Invalid operands for '-': expected two numbers, got string(\"a\") and integer(1)."),
+        ),
+    ];
+
+    check_with(test_cases, config, |_| {});
+}
+
+#[test]
+fn test_shutdown() {
+    let run = |source: &str| {
+        let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+        let stmts = super::syntax_tree::SyntaxTree::new(tokens).parse().unwrap();
+
+        // The buffered output is kept alive along with the interpreter, so only what was flushed
+        // when the program ended is visible
+        let output = SharedOutput::default();
+        let buffered = std::io::BufWriter::with_capacity(1024, output.clone());
+        let interpreter = Interpreter::with_output(Config::default(), Box::new(buffered));
+        let result = interpreter.interpret(&super::desugar::lower(stmts));
+        (output.contents(), result.map_err(|err| err.to_string()))
+    };
+
+    assert_eq!(run("print 1; print 2;"), ("1\n2\n".to_string(), Ok(())));
+    assert_eq!(
+        run("print 1; print nil + 1;"),
+        (
+            "1\n".to_string(),
+            Err("[line 1] Invalid operands for '+': expected two numbers or two strings, got nil and integer(1).".to_string())
+        )
+    );
+    assert_eq!(
+        run("print 1; throw 2;"),
+        (
+            "1\n".to_string(),
+            Err("[line 1] Uncaught exception: 2.".to_string())
+        )
+    );
+
+    // A failure to flush is an error of the program
+    struct Unflushable;
+    impl Write for Unflushable {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Err(std::io::Error::other("disk full"))
         }
     }
+    let interpreter = Interpreter::with_output(Config::default(), Box::new(Unflushable));
+    assert_eq!(
+        interpreter.interpret(&[]).map_err(|err| err.to_string()),
+        Err("disk full".to_string())
+    );
+}
+
+#[test]
+fn test_register_native() {
+    fn twice(_: &Interpreter, args: &[Value]) -> Result<Value, Error> {
+        Ok(Value::String(args[0].to_string().repeat(2)))
+    }
+
+    let output = SharedOutput::default();
+    let interpreter = Interpreter::with_output(Config::default(), Box::new(output.clone()));
+    interpreter.register_native(NativeFunction::new("twice", Some(1), twice));
+    let run = |source: &str| {
+        let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+        let stmts = super::syntax_tree::SyntaxTree::new(tokens).parse().unwrap();
+        interpreter
+            .interpret(&super::desugar::lower(stmts))
+            .map_err(|err| err.to_string())
+    };
+
+    assert_eq!(run("print twice(\"ab\"), twice;"), Ok(()));
+    assert_eq!(
+        run("twice(1, 2);"),
+        Err("[line 1] Expected 1 arguments but got 2.".to_string())
+    );
+    assert_eq!(run("var twice = 1; print twice;"), Ok(()));
+
+    // Resetting removes the variables of the programs, but defines the natives again
+    interpreter.reset();
+    assert_eq!(run("print twice(1); debug(1);"), Ok(()));
+    assert_eq!(
+        output.contents(),
+        "abab <native fn twice>\n1\n11\ninteger(1)\n"
+    );
 }
@@ -0,0 +1,97 @@
+use super::expr::Expr;
+use crate::lexer::token::Token;
+
+#[derive(Debug, Clone)]
+pub enum Stmt {
+    Expression(Expr),
+    Print(Vec<Expr>),
+    Assert(Token, Expr, String, Option<Expr>),
+    Var(Token, Option<Expr>),
+    Const(Token, Expr),
+    VarTuple(Token, Vec<Token>, Expr),
+    Block(Vec<Stmt>),
+    If(Expr, Box<Stmt>, Option<Box<Stmt>>),
+    While(Expr, Box<Stmt>, Option<Expr>, Option<Token>),
+    ForIn(Token, Token, Expr, Box<Stmt>, Option<Token>),
+    Throw(Token, Expr),
+    Try(Vec<Stmt>, Option<(Token, Vec<Stmt>)>, Option<Vec<Stmt>>),
+    Break(Token, Option<Token>),
+    Continue(Token, Option<Token>),
+}
+
+pub trait Visitor<R> {
+    fn visit_expression_stmt(&self, expr: &Expr) -> R;
+    fn visit_print_stmt(&self, exprs: &Vec<Expr>) -> R;
+    fn visit_assert_stmt(
+        &self,
+        keyword: &Token,
+        condition: &Expr,
+        text: &String,
+        message: &Option<Expr>,
+    ) -> R;
+    fn visit_var_stmt(&self, name: &Token, initializer: &Option<Expr>) -> R;
+    fn visit_const_stmt(&self, name: &Token, initializer: &Expr) -> R;
+    fn visit_var_tuple_stmt(&self, paren: &Token, names: &Vec<Token>, initializer: &Expr) -> R;
+    fn visit_block_stmt(&self, stmts: &Vec<Stmt>) -> R;
+    fn visit_if_stmt(
+        &self,
+        condition: &Expr,
+        then_branch: &Box<Stmt>,
+        else_branch: &Option<Box<Stmt>>,
+    ) -> R;
+    fn visit_while_stmt(
+        &self,
+        condition: &Expr,
+        body: &Box<Stmt>,
+        increment: &Option<Expr>,
+        label: &Option<Token>,
+    ) -> R;
+    fn visit_for_in_stmt(
+        &self,
+        name: &Token,
+        keyword: &Token,
+        iterable: &Expr,
+        body: &Box<Stmt>,
+        label: &Option<Token>,
+    ) -> R;
+    fn visit_throw_stmt(&self, keyword: &Token, value: &Expr) -> R;
+    fn visit_try_stmt(
+        &self,
+        body: &Vec<Stmt>,
+        catch: &Option<(Token, Vec<Stmt>)>,
+        finally: &Option<Vec<Stmt>>,
+    ) -> R;
+    fn visit_break_stmt(&self, keyword: &Token, label: &Option<Token>) -> R;
+    fn visit_continue_stmt(&self, keyword: &Token, label: &Option<Token>) -> R;
+}
+
+impl Stmt {
+    pub fn accept<R>(&self, visitor: &impl Visitor<R>) -> R {
+        match self {
+            Stmt::Expression(expr) => visitor.visit_expression_stmt(expr),
+            Stmt::Print(exprs) => visitor.visit_print_stmt(exprs),
+            Stmt::Assert(keyword, condition, text, message) => {
+                visitor.visit_assert_stmt(keyword, condition, text, message)
+            }
+            Stmt::Var(name, initializer) => visitor.visit_var_stmt(name, initializer),
+            Stmt::Const(name, initializer) => visitor.visit_const_stmt(name, initializer),
+            Stmt::VarTuple(paren, names, initializer) => {
+                visitor.visit_var_tuple_stmt(paren, names, initializer)
+            }
+            Stmt::Block(stmts) => visitor.visit_block_stmt(stmts),
+            Stmt::If(condition, then_branch, else_branch) => {
+                visitor.visit_if_stmt(condition, then_branch, else_branch)
+            }
+            Stmt::While(condition, body, increment, label) => {
+                visitor.visit_while_stmt(condition, body, increment, label)
+            }
+            Stmt::ForIn(name, keyword, iterable, body, label) => {
+                visitor.visit_for_in_stmt(name, keyword, iterable, body, label)
+            }
+            Stmt::Throw(keyword, value) => visitor.visit_throw_stmt(keyword, value),
+            Stmt::Try(body, catch, finally) => visitor.visit_try_stmt(body, catch, finally),
+            Stmt::Break(keyword, label) => visitor.visit_break_stmt(keyword, label),
+            Stmt::Continue(keyword, label) => visitor.visit_continue_stmt(keyword, label),
+        }
+    }
+}
@@ -1,4 +1,18 @@
+pub mod desugar;
+pub mod environment;
+pub mod error;
+#[cfg(test)]
+mod evaluation_order_tests;
 pub mod expr;
+pub mod fold;
+pub mod help;
+pub mod hoist;
 pub mod interpreter;
+pub mod ir;
+pub mod native;
+pub mod ordered_map;
 pub mod printer;
+pub mod stmt;
 pub mod syntax_tree;
+pub mod transform;
+pub mod value;
@@ -0,0 +1,1294 @@
+use anyhow::Error;
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::fs;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::error::{ArgumentError, NativeError, RuntimeError};
+use super::help::help_text;
+use super::interpreter::Interpreter;
+use super::ordered_map::OrderedMap;
+use super::value::{float_to_integer, Callable, List, Map, MapKey, NativeFunction, Tuple, Value};
+use crate::config::LogLevel;
+use crate::lexer::number::{scan_number, NumberLiteral};
+
+/// Creates a native from a Rust function with typed parameters, which are converted from the Lox
+/// arguments with `FromArg`. The result of the function is converted back with `IntoValue`, so
+/// that the function does not have to deal with `Value`s:
+///
+/// ```ignore
+/// fn repeat(text: &str, count: i64) -> Result<String, Error> { ... }
+///
+/// lox_native!("repeat", repeat, (&str, i64))
+/// ```
+///
+/// The native checks the number of arguments, and reports the arguments of the wrong type with
+/// their position. Functions that need the interpreter, such as to write output, declare it as
+/// their first parameter: `lox_native!("debug", debug, (&Interpreter, &Value))`.
+macro_rules! lox_native {
+    ($name:literal, $func:path, (&Interpreter $(, $arg:ty)*)) => {
+        lox_native!(@native $name, $func, interpreter, [interpreter] ($($arg),*))
+    };
+    ($name:literal, $func:path, ($($arg:ty),*)) => {
+        lox_native!(@native $name, $func, interpreter, [] ($($arg),*))
+    };
+    // The name of the interpreter parameter is passed down, so that it is the same identifier
+    // as the one passed to the function
+    (@native $name:literal, $func:path, $param:ident, [$($interpreter:ident)?] ($($arg:ty),*)) => {{
+        #[allow(unused_assignments, unused_mut, unused_variables)]
+        fn native(
+            $param: &$crate::ast::interpreter::Interpreter,
+            args: &[$crate::ast::value::Value],
+        ) -> Result<$crate::ast::value::Value, anyhow::Error> {
+            let mut position = 0;
+            $func($($interpreter,)? $({
+                let arg = $crate::ast::native::arg::<$arg>(args, position)?;
+                position += 1;
+                arg
+            }),*)
+            .map($crate::ast::native::IntoValue::into_value)
+        }
+
+        const ARITY: usize = <[&str]>::len(&[$(stringify!($arg)),*]);
+        $crate::ast::value::NativeFunction::new($name, Some(ARITY), native)
+    }};
+}
+
+/// Converts an argument of a native into the type of the parameter it is passed to.
+pub trait FromArg<'a>: Sized {
+    /// The values that can be converted, as described in the error messages ("a number").
+    const EXPECTED: &'static str;
+
+    fn from_arg(value: &'a Value) -> Option<Self>;
+}
+
+impl<'a> FromArg<'a> for &'a Value {
+    const EXPECTED: &'static str = "any value";
+
+    fn from_arg(value: &'a Value) -> Option<Self> {
+        Some(value)
+    }
+}
+
+impl FromArg<'_> for f64 {
+    const EXPECTED: &'static str = "a number";
+
+    fn from_arg(value: &Value) -> Option<Self> {
+        value.as_number()
+    }
+}
+
+/// A number argument that keeps whether it is an integer or a float, for the natives whose
+/// result is an integer when their arguments are.
+pub struct Numeric<'a>(pub &'a Value);
+
+impl<'a> FromArg<'a> for Numeric<'a> {
+    const EXPECTED: &'static str = "a number";
+
+    fn from_arg(value: &'a Value) -> Option<Self> {
+        value.as_number().map(|_| Numeric(value))
+    }
+}
+
+/// Integral floats are accepted as integers, like for indexing.
+impl FromArg<'_> for i64 {
+    const EXPECTED: &'static str = "an integer";
+
+    fn from_arg(value: &Value) -> Option<Self> {
+        match value {
+            Value::Integer(integer) => Some(*integer),
+            Value::Number(num) => float_to_integer(*num),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> FromArg<'a> for &'a str {
+    const EXPECTED: &'static str = "a string";
+
+    fn from_arg(value: &'a Value) -> Option<Self> {
+        match value {
+            Value::String(str) => Some(str),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg<'_> for bool {
+    const EXPECTED: &'static str = "a boolean";
+
+    fn from_arg(value: &Value) -> Option<Self> {
+        match value {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg<'_> for List {
+    const EXPECTED: &'static str = "a list";
+
+    fn from_arg(value: &Value) -> Option<Self> {
+        match value {
+            Value::List(list) => Some(list.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg<'_> for Map {
+    const EXPECTED: &'static str = "a map";
+
+    fn from_arg(value: &Value) -> Option<Self> {
+        match value {
+            Value::Map(map) => Some(map.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg<'_> for Tuple {
+    const EXPECTED: &'static str = "a tuple";
+
+    fn from_arg(value: &Value) -> Option<Self> {
+        match value {
+            Value::Tuple(tuple) => Some(tuple.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Map keys are checked like for indexing, so that natives can not be passed keys that no map
+/// can hold.
+impl FromArg<'_> for MapKey {
+    const EXPECTED: &'static str = "a number, string, boolean, nil or tuple key";
+
+    fn from_arg(value: &Value) -> Option<Self> {
+        MapKey::from_value(value)
+    }
+}
+
+/// A function argument, for the natives that call back into the program, such as `map`.
+pub struct Function<'a>(pub &'a dyn Callable);
+
+impl<'a> FromArg<'a> for Function<'a> {
+    const EXPECTED: &'static str = "a function";
+
+    fn from_arg(value: &'a Value) -> Option<Self> {
+        value.as_callable().map(Function)
+    }
+}
+
+/// Converts the argument at the position into the type of its parameter, or returns an
+/// `ArgumentError` that the interpreter reports at the call. Used by `lox_native!`.
+pub fn arg<'a, T: FromArg<'a>>(args: &'a [Value], position: usize) -> Result<T, Error> {
+    convert(&args[position], position + 1)
+}
+
+/// Converts the argument at the position, starting from 1, for the natives that accept arguments
+/// of different types and convert them once they know which one was passed.
+fn convert<'a, T: FromArg<'a>>(value: &'a Value, position: usize) -> Result<T, Error> {
+    T::from_arg(value).ok_or_else(|| {
+        ArgumentError {
+            position,
+            expected: T::EXPECTED,
+            found: value.debug_string(),
+        }
+        .into()
+    })
+}
+
+/// Converts the result of a typed native into a Lox value.
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> Value {
+        Value::Nil
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self) -> Value {
+        Value::Number(self)
+    }
+}
+
+impl IntoValue for i64 {
+    fn into_value(self) -> Value {
+        Value::Integer(self)
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::String(self)
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::Boolean(self)
+    }
+}
+
+/// `None` is converted into `nil`.
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        self.map_or(Value::Nil, IntoValue::into_value)
+    }
+}
+
+/// Returns the natives that are enabled by the configuration of the interpreter.
+pub fn natives(interpreter: &Interpreter) -> Vec<NativeFunction> {
+    let mut natives = vec![
+        lox_native!("clock", clock, ()),
+        lox_native!("debug", debug, (&Interpreter, &Value)),
+        lox_native!("exit", exit, (i64)),
+        lox_native!("help", help, (&Interpreter, &Value)),
+        NativeFunction::new("printf", None, printf),
+        lox_native!("source", source, (&Value)),
+        lox_native!("readLine", read_line, (&Interpreter)),
+        lox_native!("stacktrace", stacktrace, (&Interpreter)),
+        // Math
+        lox_native!("abs", abs, (Numeric)),
+        lox_native!("ceil", ceil, (f64)),
+        lox_native!("floor", floor, (f64)),
+        lox_native!("max", max, (Numeric, Numeric)),
+        lox_native!("min", min, (Numeric, Numeric)),
+        lox_native!("pow", pow, (f64, f64)),
+        lox_native!("sqrt", sqrt, (f64)),
+        // Strings
+        lox_native!("contains", contains, (&str, &str)),
+        NativeFunction::new("format", None, format),
+        lox_native!("replace", replace, (&str, &str, &str)),
+        lox_native!("split", split, (&str, &str)),
+        lox_native!("substring", substring, (&str, i64, i64)),
+        lox_native!("toLower", to_lower, (&str)),
+        lox_native!("toUpper", to_upper, (&str)),
+        lox_native!("trim", trim, (&str)),
+        // Lists
+        lox_native!("filter", filter, (&Interpreter, List, Function)),
+        lox_native!("insert", insert, (List, i64, &Value)),
+        lox_native!("len", len, (&Value)),
+        lox_native!("map", map, (&Interpreter, List, Function)),
+        lox_native!("pop", pop, (List)),
+        lox_native!("push", push, (List, &Value)),
+        lox_native!("reduce", reduce, (&Interpreter, List, Function, &Value)),
+        lox_native!("remove", remove, (&Value, &Value)),
+        // Maps
+        lox_native!("has", has, (Map, MapKey)),
+        lox_native!("keys", keys, (Map)),
+        lox_native!("values", values, (Map)),
+        // Time
+        lox_native!("formatTime", format_time, (i64, &str)),
+        lox_native!("now", now, ()),
+        // Types
+        lox_native!("num", num, (&str)),
+        lox_native!("str", str, (&Value)),
+        lox_native!("type", type_of, (&Value)),
+    ];
+
+    if interpreter.config().print_function {
+        natives.push(NativeFunction::new("print", None, print));
+    }
+
+    if !interpreter.config().sandbox {
+        natives.push(lox_native!("readFile", read_file, (&str)));
+        natives.push(lox_native!("writeFile", write_file, (&str, &str)));
+        natives.push(lox_native!("getEnv", get_env, (&str)));
+    }
+
+    natives
+}
+
+/// Returns the `log` map, whose natives log their argument with the level of their name
+/// (`log.warn("disk almost full")`). Values other than strings are logged as they are printed.
+pub fn log_natives() -> Value {
+    let natives = [
+        lox_native!("log.debug", log_debug, (&Interpreter, &Value)),
+        lox_native!("log.info", log_info, (&Interpreter, &Value)),
+        lox_native!("log.warn", log_warn, (&Interpreter, &Value)),
+        lox_native!("log.error", log_error, (&Interpreter, &Value)),
+    ];
+
+    let mut map = OrderedMap::new();
+    for native in natives {
+        let level = native.name.trim_start_matches("log.");
+        map.insert(
+            MapKey::String(level.to_string()),
+            Value::NativeFunction(native),
+        );
+    }
+    Value::Map(Rc::new(RefCell::new(map)))
+}
+
+fn log_debug(interpreter: &Interpreter, message: &Value) -> Result<(), Error> {
+    interpreter.log(LogLevel::Debug, &message.to_string());
+    Ok(())
+}
+
+fn log_info(interpreter: &Interpreter, message: &Value) -> Result<(), Error> {
+    interpreter.log(LogLevel::Info, &message.to_string());
+    Ok(())
+}
+
+fn log_warn(interpreter: &Interpreter, message: &Value) -> Result<(), Error> {
+    interpreter.log(LogLevel::Warn, &message.to_string());
+    Ok(())
+}
+
+fn log_error(interpreter: &Interpreter, message: &Value) -> Result<(), Error> {
+    interpreter.log(LogLevel::Error, &message.to_string());
+    Ok(())
+}
+
+/// Returns the number of seconds since the Unix epoch, with a fractional part, so that programs
+/// can measure how long their code takes by subtracting two calls.
+fn clock() -> Result<f64, Error> {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    Ok(elapsed.as_secs_f64())
+}
+
+/// Returns the number of milliseconds since the Unix epoch, as an integer, for the timestamps
+/// passed to `formatTime`.
+fn now() -> Result<i64, Error> {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    Ok(i64::try_from(elapsed.as_millis())?)
+}
+
+const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+/// Formats the time, in milliseconds since the Unix epoch, in UTC. The format is copied as is,
+/// except for the specifiers `%Y` (year), `%m` (month), `%d` (day), `%H` (hours), `%M` (minutes),
+/// `%S` (seconds) and `%L` (milliseconds), which are padded with zeros, and `%%`.
+fn format_time(millis: i64, format: &str) -> Result<String, Error> {
+    let (year, month, day) = civil_from_days(millis.div_euclid(MILLIS_PER_DAY));
+    let millis_of_day = millis.rem_euclid(MILLIS_PER_DAY);
+    let seconds_of_day = millis_of_day / 1000;
+
+    let mut text = String::new();
+    let mut chars = format.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            text.push(ch);
+            continue;
+        }
+
+        match chars.next() {
+            Some('Y') => write!(text, "{:04}", year)?,
+            Some('m') => write!(text, "{:02}", month)?,
+            Some('d') => write!(text, "{:02}", day)?,
+            Some('H') => write!(text, "{:02}", seconds_of_day / 3600)?,
+            Some('M') => write!(text, "{:02}", seconds_of_day / 60 % 60)?,
+            Some('S') => write!(text, "{:02}", seconds_of_day % 60)?,
+            Some('L') => write!(text, "{:03}", millis_of_day % 1000)?,
+            Some('%') => text.push('%'),
+            Some(other) => {
+                let message = format!("Unknown time format specifier '%{}'.", other);
+                return Err(NativeError(message).into());
+            }
+            None => {
+                let message = "Time format can not end with a single '%'.".to_string();
+                return Err(NativeError(message).into());
+            }
+        }
+    }
+    Ok(text)
+}
+
+/// Returns the year, month and day of the date that is the number of days after the Unix epoch,
+/// in the proleptic Gregorian calendar. Counts the years in eras of 400 years starting in March,
+/// so that leap days are at the end of the year, as described in
+/// <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = match shifted_month < 10 {
+        true => shifted_month + 3,
+        false => shifted_month - 9,
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Returns the absolute value of the number, which is an integer if the number is one and its
+/// absolute value fits in 64 bits.
+fn abs(Numeric(value): Numeric) -> Result<Value, Error> {
+    Ok(match value {
+        Value::Integer(integer) => match integer.checked_abs() {
+            Some(abs) => Value::Integer(abs),
+            None => Value::Number((*integer as f64).abs()),
+        },
+        value => Value::Number(value.as_number().unwrap_or_default().abs()),
+    })
+}
+
+/// Rounds the number up, to an integer if the result fits in 64 bits.
+fn ceil(num: f64) -> Result<Value, Error> {
+    Ok(rounded(num.ceil()))
+}
+
+/// Rounds the number down, to an integer if the result fits in 64 bits.
+fn floor(num: f64) -> Result<Value, Error> {
+    Ok(rounded(num.floor()))
+}
+
+/// Returns the integer with the value of the integral float, or the float if it does not fit.
+fn rounded(num: f64) -> Value {
+    match float_to_integer(num) {
+        Some(integer) => Value::Integer(integer),
+        None => Value::Number(num),
+    }
+}
+
+/// Returns the largest of the two numbers, or the first one if they are equal.
+fn max(Numeric(left): Numeric, Numeric(right): Numeric) -> Result<Value, Error> {
+    match is_less(left, right) {
+        true => Ok(right.clone()),
+        false => Ok(left.clone()),
+    }
+}
+
+/// Returns the smallest of the two numbers, or the first one if they are equal.
+fn min(Numeric(left): Numeric, Numeric(right): Numeric) -> Result<Value, Error> {
+    match is_less(right, left) {
+        true => Ok(right.clone()),
+        false => Ok(left.clone()),
+    }
+}
+
+/// Returns true if the left number is less than the right one, comparing integers exactly like
+/// the `<` operator.
+fn is_less(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Integer(left), Value::Integer(right)) => left < right,
+        (left, right) => left.as_number() < right.as_number(),
+    }
+}
+
+/// Raises the base to the exponent. The result is always a float, like for division.
+fn pow(base: f64, exponent: f64) -> Result<f64, Error> {
+    Ok(base.powf(exponent))
+}
+
+/// Returns the square root of the number, which is `NaN` for negative numbers.
+fn sqrt(num: f64) -> Result<f64, Error> {
+    Ok(num.sqrt())
+}
+
+/// Returns true if the part appears in the text.
+fn contains(text: &str, part: &str) -> Result<bool, Error> {
+    Ok(text.contains(part))
+}
+
+/// Replaces every occurrence of the pattern in the text, which can not be empty.
+fn replace(text: &str, pattern: &str, replacement: &str) -> Result<String, Error> {
+    if pattern.is_empty() {
+        return Err(ArgumentError {
+            position: 2,
+            expected: "a non empty string",
+            found: Value::String(String::new()).debug_string(),
+        }
+        .into());
+    }
+    Ok(text.replace(pattern, replacement))
+}
+
+/// Splits the text around the occurrences of the separator, or into its characters if the
+/// separator is empty.
+fn split(text: &str, separator: &str) -> Result<Value, Error> {
+    let parts: Vec<Value> = match separator {
+        "" => text
+            .chars()
+            .map(|ch| Value::String(ch.to_string()))
+            .collect(),
+        separator => text
+            .split(separator)
+            .map(|part| Value::String(part.to_string()))
+            .collect(),
+    };
+    Ok(Value::List(Rc::new(RefCell::new(parts))))
+}
+
+/// Returns the characters of the text from the start index up to the end index, excluded.
+fn substring(text: &str, start: i64, end: i64) -> Result<String, Error> {
+    let length = text.chars().count() as i64;
+    let out_of_range = |position, index, expected| ArgumentError {
+        position,
+        expected,
+        found: Value::Integer(index).debug_string(),
+    };
+
+    if !(0..=length).contains(&start) {
+        return Err(out_of_range(2, start, "an index within the string").into());
+    }
+    if !(start..=length).contains(&end) {
+        return Err(out_of_range(3, end, "an index between the start and the length").into());
+    }
+    Ok(text
+        .chars()
+        .skip(start as usize)
+        .take((end - start) as usize)
+        .collect())
+}
+
+fn to_lower(text: &str) -> Result<String, Error> {
+    Ok(text.to_lowercase())
+}
+
+fn to_upper(text: &str) -> Result<String, Error> {
+    Ok(text.to_uppercase())
+}
+
+/// Removes the whitespace at the start and the end of the text.
+fn trim(text: &str) -> Result<String, Error> {
+    Ok(text.trim().to_string())
+}
+
+/// Returns the number of characters of a string, of elements of a list or a tuple, or of entries
+/// of a map.
+fn len(value: &Value) -> Result<i64, Error> {
+    let length = match value {
+        Value::String(text) => text.chars().count(),
+        Value::List(list) => list.borrow().len(),
+        Value::Tuple(tuple) => tuple.len(),
+        Value::Map(map) => map.borrow().len(),
+        value => {
+            return Err(ArgumentError {
+                position: 1,
+                expected: "a string, list, tuple or map",
+                found: value.debug_string(),
+            }
+            .into())
+        }
+    };
+    Ok(length as i64)
+}
+
+/// Appends the value to the end of the list.
+fn push(list: List, value: &Value) -> Result<(), Error> {
+    list.borrow_mut().push(value.clone());
+    Ok(())
+}
+
+/// Removes the last element of the list and returns it, or returns `nil` if the list is empty.
+fn pop(list: List) -> Result<Option<Value>, Error> {
+    Ok(list.borrow_mut().pop())
+}
+
+/// Inserts the value at the index of the list, shifting the elements after it. The index can be
+/// the length of the list, to append the value.
+fn insert(list: List, index: i64, value: &Value) -> Result<(), Error> {
+    let length = list.borrow().len();
+    let index = list_index(index, length + 1)?;
+    list.borrow_mut().insert(index, value.clone());
+    Ok(())
+}
+
+/// Removes the element at the index of a list and returns it, shifting the elements after it, or
+/// removes the key of a map and returns its value, which is `nil` if the map does not have it.
+fn remove(collection: &Value, key: &Value) -> Result<Value, Error> {
+    match collection {
+        Value::List(list) => {
+            let length = list.borrow().len();
+            let index = list_index(convert(key, 2)?, length)?;
+            Ok(list.borrow_mut().remove(index))
+        }
+        Value::Map(map) => {
+            let key: MapKey = convert(key, 2)?;
+            Ok(map.borrow_mut().remove(&key).unwrap_or(Value::Nil))
+        }
+        collection => Err(ArgumentError {
+            position: 1,
+            expected: "a list or map",
+            found: collection.debug_string(),
+        }
+        .into()),
+    }
+}
+
+/// Checks that the index, passed as the second argument, is less than the bound.
+fn list_index(index: i64, bound: usize) -> Result<usize, Error> {
+    match usize::try_from(index) {
+        Ok(index) if index < bound => Ok(index),
+        _ => Err(ArgumentError {
+            position: 2,
+            expected: "an index within the list",
+            found: Value::Integer(index).debug_string(),
+        }
+        .into()),
+    }
+}
+
+/// Returns a new list with the results of calling the function with each element of the list.
+/// The elements are copied first, so that the function can modify the list.
+fn map(interpreter: &Interpreter, list: List, Function(func): Function) -> Result<Value, Error> {
+    let elements = list.borrow().clone();
+    let results = elements
+        .into_iter()
+        .map(|element| interpreter.call_back(func, &[element]))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Value::List(Rc::new(RefCell::new(results))))
+}
+
+/// Returns a new list with the elements of the list for which the function returns a truthy value.
+fn filter(interpreter: &Interpreter, list: List, Function(func): Function) -> Result<Value, Error> {
+    let elements = list.borrow().clone();
+    let mut kept = Vec::new();
+    for element in elements {
+        if interpreter
+            .call_back(func, std::slice::from_ref(&element))?
+            .is_truthy()
+        {
+            kept.push(element);
+        }
+    }
+    Ok(Value::List(Rc::new(RefCell::new(kept))))
+}
+
+/// Combines the elements of the list from the first one, by calling the function with the result
+/// so far and the element, starting from the initial value.
+fn reduce(
+    interpreter: &Interpreter,
+    list: List,
+    Function(func): Function,
+    initial: &Value,
+) -> Result<Value, Error> {
+    let elements = list.borrow().clone();
+    elements
+        .into_iter()
+        .try_fold(initial.clone(), |result, element| {
+            interpreter.call_back(func, &[result, element])
+        })
+}
+
+/// Returns true if the map has the key, even if its value is `nil`.
+fn has(map: Map, key: MapKey) -> Result<bool, Error> {
+    Ok(map.borrow().get(&key).is_some())
+}
+
+/// Returns a new list with the keys of the map, in the order they were inserted.
+fn keys(map: Map) -> Result<Value, Error> {
+    let keys = map.borrow().keys().map(MapKey::to_value).collect();
+    Ok(Value::List(Rc::new(RefCell::new(keys))))
+}
+
+/// Returns a new list with the values of the map, in the order of their keys.
+fn values(map: Map) -> Result<Value, Error> {
+    let values = map.borrow().values().cloned().collect();
+    Ok(Value::List(Rc::new(RefCell::new(values))))
+}
+
+/// Parses the text as a number literal with an optional sign, ignoring the whitespace around it,
+/// or returns `nil` if it is not one. Integers stay integers, as for literals in the source code.
+fn num(text: &str) -> Result<Option<Value>, Error> {
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+
+    let chars = digits.chars().collect::<Vec<_>>();
+    if !chars.first().is_some_and(char::is_ascii_digit) {
+        return Ok(None);
+    }
+    Ok(match scan_number(&chars, false) {
+        Ok((literal, length)) if length == chars.len() => match (literal, negative) {
+            (NumberLiteral::Integer(integer), false) => Some(Value::Integer(integer)),
+            (NumberLiteral::Integer(integer), true) => Some(Value::Integer(-integer)),
+            (NumberLiteral::Float(num), false) => Some(Value::Number(num)),
+            (NumberLiteral::Float(num), true) => Some(Value::Number(-num)),
+        },
+        _ => None,
+    })
+}
+
+/// Returns the value as it is printed.
+fn str(value: &Value) -> Result<String, Error> {
+    Ok(value.to_string())
+}
+
+/// Returns the name of the kind of the value, as tested by the `is` operator (`"Number"`).
+fn type_of(value: &Value) -> Result<String, Error> {
+    Ok(value.kind().to_string())
+}
+
+/// Ends the program with the exit status, which must be from 0 to 255 to mean the same on every
+/// system.
+fn exit(code: i64) -> Result<(), Error> {
+    match u8::try_from(code) {
+        Ok(code) => Err(RuntimeError::Exit { code: code.into() }.into()),
+        Err(_) => Err(ArgumentError {
+            position: 1,
+            expected: "an exit status from 0 to 255",
+            found: Value::Integer(code).debug_string(),
+        }
+        .into()),
+    }
+}
+
+/// Writes the developer oriented representation of the argument, followed by a newline.
+fn debug(interpreter: &Interpreter, value: &Value) -> Result<(), Error> {
+    interpreter.write_line(&value.debug_string())
+}
+
+/// Writes the reference entry of the native, keyword or operator with the provided name.
+/// Natives can also be passed directly, as in `help(debug)`.
+fn help(interpreter: &Interpreter, value: &Value) -> Result<(), Error> {
+    let name = match value {
+        Value::String(name) => name.clone(),
+        Value::NativeFunction(native) => native.name.to_string(),
+        value => value.to_string(),
+    };
+
+    interpreter.write_line(&help_text(&name))
+}
+
+/// Returns the source text of the declaration of the function or class passed as argument.
+/// Natives are not declared in Lox, and only functions and classes have a declaration, so
+/// the result is `nil` for every value until functions and classes can be declared.
+fn source(_value: &Value) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Returns the frames of the call stack at the call, from the innermost one, as a list of
+/// `(function, line)` tuples. Only natives can be called for now, so the list holds the single
+/// frame of the top level of the program.
+fn stacktrace(interpreter: &Interpreter) -> Result<Value, Error> {
+    let frames = interpreter
+        .stack_frames()
+        .into_iter()
+        .map(|(name, line)| {
+            let frame = vec![Value::String(name.to_string()), Value::Integer(line as i64)];
+            Value::Tuple(Rc::new(frame))
+        })
+        .collect();
+
+    Ok(Value::List(Rc::new(RefCell::new(frames))))
+}
+
+/// Returns the next line of the input of the program without its line ending, or `nil` at the
+/// end of the input.
+fn read_line(interpreter: &Interpreter) -> Result<Option<String>, Error> {
+    interpreter.read_line()
+}
+
+/// Returns the contents of the file at the path, which must be text.
+fn read_file(path: &str) -> Result<String, Error> {
+    fs::read_to_string(path)
+        .map_err(|err| NativeError(format!("Failed to read file '{}': {}.", path, err)).into())
+}
+
+/// Writes the contents to the file at the path, replacing the file if it exists.
+fn write_file(path: &str, contents: &str) -> Result<(), Error> {
+    fs::write(path, contents)
+        .map_err(|err| NativeError(format!("Failed to write file '{}': {}.", path, err)).into())
+}
+
+/// Returns the value of the environment variable, or `nil` if it is not set. Values that are not
+/// valid unicode have their invalid parts replaced.
+fn get_env(name: &str) -> Result<Option<String>, Error> {
+    Ok(std::env::var_os(name).map(|value| value.to_string_lossy().into_owned()))
+}
+
+/// Writes all the arguments separated by the configured separator, followed by a newline.
+/// Natives that take any number of arguments receive them as they are.
+fn print(interpreter: &Interpreter, args: &[Value]) -> Result<Value, Error> {
+    interpreter.write_values(args)?;
+    Ok(Value::Nil)
+}
+
+/// Replaces each `{}` placeholder of the format, passed as the first argument, with the next of
+/// the other arguments, as it is printed. `{{` and `}}` stand for literal braces.
+fn format(_interpreter: &Interpreter, args: &[Value]) -> Result<Value, Error> {
+    Ok(Value::String(format_args(args)?))
+}
+
+/// Writes the arguments formatted like by `format`, followed by a newline.
+fn printf(interpreter: &Interpreter, args: &[Value]) -> Result<Value, Error> {
+    interpreter.write_line(&format_args(args)?)?;
+    Ok(Value::Nil)
+}
+
+/// A part of a format string.
+enum Piece {
+    Text(String),
+    Placeholder,
+}
+
+/// Formats the values after the format string that is the first argument, checking that there is
+/// a value for each placeholder.
+fn format_args(args: &[Value]) -> Result<String, Error> {
+    let Some((format, values)) = args.split_first() else {
+        return Err(
+            NativeError("Expected a format string but got no arguments.".to_string()).into(),
+        );
+    };
+    let pieces = parse_format(convert(format, 1)?)?;
+
+    let placeholders = pieces
+        .iter()
+        .filter(|piece| matches!(piece, Piece::Placeholder))
+        .count();
+    if placeholders != values.len() {
+        let message = format!(
+            "Expected {} values for the format but got {}.",
+            placeholders,
+            values.len()
+        );
+        return Err(NativeError(message).into());
+    }
+
+    let mut values = values.iter();
+    Ok(pieces
+        .into_iter()
+        .map(|piece| match piece {
+            Piece::Text(text) => text,
+            Piece::Placeholder => values.next().map(Value::to_string).unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Splits the format into its text and its placeholders, unescaping the doubled braces.
+fn parse_format(format: &str) -> Result<Vec<Piece>, Error> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = format.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match (ch, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                text.push(ch);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                pieces.push(Piece::Text(std::mem::take(&mut text)));
+                pieces.push(Piece::Placeholder);
+            }
+            ('{' | '}', _) => {
+                let message = format!(
+                    "Unmatched '{}' in the format, use '{}{}' for a literal brace.",
+                    ch, ch, ch
+                );
+                return Err(NativeError(message).into());
+            }
+            (ch, _) => text.push(ch),
+        }
+    }
+
+    pieces.push(Piece::Text(text));
+    Ok(pieces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::interpreter::{check, check_with, run_program_with, SharedOutput};
+    use crate::config::Config;
+
+    fn repeat(text: &str, count: i64) -> Result<String, Error> {
+        Ok(text.repeat(count as usize))
+    }
+
+    fn first(list: List) -> Result<Option<Value>, Error> {
+        Ok(list.borrow().first().cloned())
+    }
+
+    fn fail(interpreter: &Interpreter) -> Result<(), Error> {
+        interpreter.write_line("failing")?;
+        Err(Error::msg("Failed."))
+    }
+
+    #[test]
+    fn test_lox_native() {
+        let test_cases = vec![
+            ("print repeat(\"ab\", 2), repeat(\"c\", 1.0);", Ok("abab c\n")),
+            ("print first([]), first([3, 4]);", Ok("nil 3\n")),
+            ("fail();", Err("Failed.")),
+            (
+                "repeat(1, 2);",
+                Err("[line 1] Invalid argument 1 for 'repeat': expected a string, got integer(1)."),
+            ),
+            (
+                "\nrepeat(\"a\", 1.5);",
+                Err("[line 2] Invalid argument 2 for 'repeat': expected an integer, got number(1.5)."),
+            ),
+            (
+                "first({});",
+                Err("[line 1] Invalid argument 1 for 'first': expected a list, got map{}."),
+            ),
+            (
+                "repeat(\"a\");",
+                Err("[line 1] Expected 2 arguments but got 1."),
+            ),
+        ];
+
+        check_with(test_cases, Config::default(), |interpreter| {
+            interpreter.define_native(lox_native!("repeat", repeat, (&str, i64)));
+            interpreter.define_native(lox_native!("first", first, (List)));
+            interpreter.define_native(lox_native!("fail", fail, (&Interpreter)));
+        });
+    }
+
+    #[test]
+    fn test_clock() {
+        let source = "var start = clock(); var end = clock();\n\
+                      print typeof start, start > 1700000000, end >= start, end - start < 60;";
+        let (output, result) = run_program_with(source, Config::default(), |_| {});
+        assert!(result.is_ok());
+        assert_eq!(output, "number true true true\n");
+    }
+
+    #[test]
+    fn test_math() {
+        let test_cases = vec![
+            (
+                "debug(abs(-2)); debug(abs(2.5)); debug(abs(-0.5));",
+                Ok("integer(2)\nnumber(2.5)\nnumber(0.5)\n"),
+            ),
+            (
+                "debug(floor(2.7)); debug(ceil(2.2)); debug(floor(-2.5)); debug(ceil(3));",
+                Ok("integer(2)\ninteger(3)\ninteger(-3)\ninteger(3)\n"),
+            ),
+            (
+                "print floor(1e300) == 1e300, sqrt(16), sqrt(2), sqrt(-1);",
+                Ok("true 4 1.4142135623730951 NaN\n"),
+            ),
+            (
+                "print pow(2, 10), pow(4, 0.5), pow(2, -1);",
+                Ok("1024 2 0.5\n"),
+            ),
+            (
+                "debug(min(1, 2.5)); debug(max(1, 2.5)); debug(max(2, 2.0));",
+                Ok("integer(1)\nnumber(2.5)\ninteger(2)\n"),
+            ),
+            (
+                "print min(9007199254740993, 9007199254740992);",
+                Ok("9007199254740992\n"),
+            ),
+            (
+                "\nsqrt(\"4\");",
+                Err(
+                    "[line 2] Invalid argument 1 for 'sqrt': expected a number, got string(\"4\").",
+                ),
+            ),
+            (
+                "max(1, nil);",
+                Err("[line 1] Invalid argument 2 for 'max': expected a number, got nil."),
+            ),
+            ("pow(2);", Err("[line 1] Expected 2 arguments but got 1.")),
+        ];
+
+        check(test_cases);
+    }
+
+    #[test]
+    fn test_strings() {
+        let test_cases = vec![
+            ("print len(\"\"), len(\"abc\"), len(\"\u{e9}t\u{e9}\");", Ok("0 3 3\n")),
+            ("print substring(\"hello\", 1, 3), substring(\"hello\", 0, 5), substring(\"h\u{e9}llo\", 1, 2);", Ok("el hello \u{e9}\n")),
+            ("print substring(\"abc\", 3, 3) == \"\";", Ok("true\n")),
+            ("print toUpper(\"aBc\"), toLower(\"aBc\"), trim(\"  a b \n\");", Ok("ABC abc a b\n")),
+            ("print split(\"a,b,,c\", \",\"), split(\"ab\", \"\"), split(\"\", \",\");", Ok("[\"a\", \"b\", \"\", \"c\"] [\"a\", \"b\"] [\"\"]\n")),
+            ("print replace(\"a-b-c\", \"-\", \"+\"), contains(\"abc\", \"bc\"), contains(\"abc\", \"d\");", Ok("a+b+c true false\n")),
+            (
+                "substring(\"abc\", 1, 4);",
+                Err("[line 1] Invalid argument 3 for 'substring': expected an index between the start and the length, got integer(4)."),
+            ),
+            (
+                "substring(\"abc\", -1, 2);",
+                Err("[line 1] Invalid argument 2 for 'substring': expected an index within the string, got integer(-1)."),
+            ),
+            (
+                "\nreplace(\"abc\", \"\", \"x\");",
+                Err("[line 2] Invalid argument 2 for 'replace': expected a non empty string, got string(\"\")."),
+            ),
+            (
+                "len(1);",
+                Err("[line 1] Invalid argument 1 for 'len': expected a string, list, tuple or map, got integer(1)."),
+            ),
+        ];
+
+        check(test_cases);
+    }
+
+    #[test]
+    fn test_lists() {
+        let test_cases = vec![
+            ("var l = [1]; push(l, 2); insert(l, 0, 0); insert(l, 3, 3); print l, len(l);", Ok("[0, 1, 2, 3] 4\n")),
+            ("var l = [1, 2, 3]; print pop(l), remove(l, 0), l, pop([]);", Ok("3 1 [2] nil\n")),
+            ("print len([]), len((1, 2));", Ok("0 2\n")),
+            ("print map([1, -2], abs), map([], abs), filter([\"1\", \"x\", \"2\"], num);", Ok("[1, 2] [] [\"1\", \"2\"]\n")),
+            ("print reduce([3, 1, 4, 1], max, 0), reduce([], max, nil);", Ok("4 nil\n")),
+            (
+                "insert([1], 2, 0);",
+                Err("[line 1] Invalid argument 2 for 'insert': expected an index within the list, got integer(2)."),
+            ),
+            (
+                "remove([], 0);",
+                Err("[line 1] Invalid argument 2 for 'remove': expected an index within the list, got integer(0)."),
+            ),
+            (
+                "map([1], 1);",
+                Err("[line 1] Invalid argument 2 for 'map': expected a function, got integer(1)."),
+            ),
+            (
+                "\nmap([\"a\"], abs);",
+                Err("[line 2] Invalid argument 1 for 'abs': expected a number, got string(\"a\")."),
+            ),
+            (
+                "reduce([1], abs, 0);",
+                Err("[line 1] Expected 1 arguments but got 2."),
+            ),
+        ];
+
+        check(test_cases);
+    }
+
+    #[test]
+    fn test_maps() {
+        let test_cases = vec![
+            ("var m = {\"a\": 1, \"b\": nil, 2: 3}; print keys(m), values(m), len(m), len({});", Ok("[\"a\", \"b\", 2] [1, nil, 3] 3 0\n")),
+            ("var m = {\"a\": 1}; print has(m, \"a\"), has(m, \"b\"), has({nil: nil}, nil), has({1: 2}, 1.0);", Ok("true false true true\n")),
+            ("var m = {\"a\": 1, \"b\": 2, \"c\": 3}; print remove(m, \"b\"), remove(m, \"b\"), m;", Ok("2 nil {\"a\": 1, \"c\": 3}\n")),
+            ("var m = {\"a\": 1, \"b\": 2}; remove(m, \"a\"); m[\"a\"] = 3; print keys(m);", Ok("[\"b\", \"a\"]\n")),
+            (
+                "has({}, []);",
+                Err("[line 1] Invalid argument 2 for 'has': expected a number, string, boolean, nil or tuple key, got list[]."),
+            ),
+            (
+                "remove((1, 2), 0);",
+                Err("[line 1] Invalid argument 1 for 'remove': expected a list or map, got tuple(integer(1), integer(2))."),
+            ),
+            (
+                "remove([1], \"a\");",
+                Err("[line 1] Invalid argument 2 for 'remove': expected an integer, got string(\"a\")."),
+            ),
+            (
+                "keys([]);",
+                Err("[line 1] Invalid argument 1 for 'keys': expected a map, got list[]."),
+            ),
+        ];
+
+        check(test_cases);
+    }
+
+    #[test]
+    fn test_time() {
+        let test_cases = vec![
+            ("print type(now()), now() > 1600000000000;", Ok("Number true\n")),
+            ("print formatTime(0, \"%Y-%m-%d %H:%M:%S.%L\");", Ok("1970-01-01 00:00:00.000\n")),
+            ("print formatTime(951782400123, \"%d/%m/%Y %L\");", Ok("29/02/2000 123\n")),
+            ("print formatTime(1700000000000, \"%H:%M:%S\");", Ok("22:13:20\n")),
+            ("print formatTime(-1, \"%Y-%m-%d %H:%M:%S.%L\");", Ok("1969-12-31 23:59:59.999\n")),
+            ("print formatTime(0, \"100%% at %Y\");", Ok("100% at 1970\n")),
+            (
+                "formatTime(0, \"%Q\");",
+                Err("[line 1] Unknown time format specifier '%Q'."),
+            ),
+            (
+                "formatTime(0, \"%\");",
+                Err("[line 1] Time format can not end with a single '%'."),
+            ),
+            (
+                "formatTime(0.5, \"\");",
+                Err("[line 1] Invalid argument 1 for 'formatTime': expected an integer, got number(0.5)."),
+            ),
+        ];
+
+        check(test_cases);
+    }
+
+    #[test]
+    fn test_format() {
+        let test_cases = vec![
+            ("print format(\"x={} y={}\", 1, \"a\");", Ok("x=1 y=a\n")),
+            (
+                "print format(\"{}{}\", [1, \"a\"], nil), format(\"none\");",
+                Ok("[1, \"a\"]nil none\n"),
+            ),
+            (
+                "print format(\"{{}} {{{}}} }}{{\", 2.5);",
+                Ok("{} {2.5} }{\n"),
+            ),
+            (
+                "printf(\"{} + {} = {}\", 1, 2, 1 + 2); printf(\"done\");",
+                Ok("1 + 2 = 3\ndone\n"),
+            ),
+            (
+                "format(\"{} {}\", 1);",
+                Err("[line 1] Expected 2 values for the format but got 1."),
+            ),
+            (
+                "printf(\"{}\", 1, 2);",
+                Err("[line 1] Expected 1 values for the format but got 2."),
+            ),
+            (
+                "format(\"{x}\", 1);",
+                Err("[line 1] Unmatched '{' in the format, use '{{' for a literal brace."),
+            ),
+            (
+                "format(\"a}\");",
+                Err("[line 1] Unmatched '}' in the format, use '}}' for a literal brace."),
+            ),
+            (
+                "format(1);",
+                Err("[line 1] Invalid argument 1 for 'format': expected a string, got integer(1)."),
+            ),
+            (
+                "format();",
+                Err("[line 1] Expected a format string but got no arguments."),
+            ),
+        ];
+
+        check(test_cases);
+    }
+
+    #[test]
+    fn test_types() {
+        let test_cases = vec![
+            ("print type(1), type(1.5), type(\"a\"), type(nil), type(type);", Ok("Number Number String Nil Function\n")),
+            ("print type([]) == \"List\", [] is List;", Ok("true true\n")),
+            ("debug(num(\"42\")); debug(num(\" -1.5e2 \")); debug(num(\"+0x1F\"));", Ok("integer(42)\nnumber(-150)\ninteger(31)\n")),
+            ("print num(\"\"), num(\"abc\"), num(\"1a\"), num(\"-\"), num(\"1_000\"), num(\".5\");", Ok("nil nil nil nil nil nil\n")),
+            ("print num(str(12)) + 1, str(1.5) + \"!\", str([1, \"a\"]), str(\"a\") == \"a\";", Ok("13 1.5! [1, \"a\"] true\n")),
+            (
+                "num(1);",
+                Err("[line 1] Invalid argument 1 for 'num': expected a string, got integer(1)."),
+            ),
+        ];
+
+        check(test_cases);
+    }
+
+    #[test]
+    fn test_read_line() {
+        let run = |source: &str, input: crate::ast::interpreter::Input| {
+            let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+            let stmts = crate::ast::syntax_tree::SyntaxTree::new(tokens)
+                .parse()
+                .unwrap();
+            let output = SharedOutput::default();
+            let interpreter = Interpreter::with_io(
+                Config::default(),
+                input,
+                Box::new(output.clone()),
+                Box::new(|_, _| {}),
+            );
+            let result = interpreter.interpret(&crate::ast::desugar::lower(stmts));
+            (output.contents(), result.map_err(|err| err.to_string()))
+        };
+
+        let mut lines = vec!["Lox".to_string()].into_iter();
+        let source = "var name = readLine(); print \"Hello, \" + name + \"!\";\n\
+                      print readLine(), readLine();";
+        assert_eq!(
+            run(source, Box::new(move || Ok(lines.next()))),
+            ("Hello, Lox!\nnil nil\n".to_string(), Ok(()))
+        );
+
+        // Errors of the input are runtime errors of the program
+        assert_eq!(
+            run(
+                "readLine();",
+                Box::new(|| Err(std::io::Error::other("input closed")))
+            ),
+            (String::new(), Err("input closed".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_files() {
+        let path = std::env::temp_dir().join(format!("lox-test-files-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap().replace('\\', "/");
+        let config = Config::from_flags(&["--no-sandbox".to_string()]).unwrap();
+
+        let source = format!(
+            "writeFile(\"{0}\", \"one\ntwo\"); print split(readFile(\"{0}\"), \"\n\");",
+            path
+        );
+        let (output, result) = run_program_with(&source, config.clone(), |_| {});
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(output, "[\"one\", \"two\"]\n");
+
+        // Errors of the operating system are reported at the call
+        let source = format!("\nreadFile(\"{}\");", path);
+        let (_, result) = run_program_with(&source, config, |_| {});
+        let message = result.unwrap_err().to_string();
+        assert!(
+            message.starts_with(&format!("[line 2] Failed to read file '{}': ", path)),
+            "{}",
+            message
+        );
+
+        // The natives are not defined in the sandbox
+        let (_, result) = run_program_with("readFile(\"a\");", Config::default(), |_| {});
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "[line 1] Undefined variable 'readFile'."
+        );
+    }
+
+    #[test]
+    fn test_get_env() {
+        std::env::set_var("LOX_TEST_GET_ENV", "value");
+        let config = Config::from_flags(&["--no-sandbox".to_string()]).unwrap();
+        let source = "print getEnv(\"LOX_TEST_GET_ENV\"), getEnv(\"LOX_TEST_MISSING_ENV\");";
+        let (output, result) = run_program_with(source, config, |_| {});
+        assert!(result.is_ok());
+        assert_eq!(output, "value nil\n");
+
+        // The environment is not reachable from the sandbox
+        let (_, result) = run_program_with(source, Config::default(), |_| {});
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "[line 1] Undefined variable 'getEnv'."
+        );
+    }
+
+    #[test]
+    fn test_exit() {
+        let exit_code = |source: &str| {
+            let (output, result) = run_program_with(source, Config::default(), |_| {});
+            let code = match result.unwrap_err().downcast_ref::<RuntimeError>() {
+                Some(RuntimeError::Exit { code }) => *code,
+                err => panic!("Expected an exit, got {:?}", err),
+            };
+            (output, code)
+        };
+
+        assert_eq!(
+            exit_code("print 1; exit(3); print 2;"),
+            ("1\n".to_string(), 3)
+        );
+        assert_eq!(exit_code("{ var a = { exit(0); }; }"), (String::new(), 0));
+        // Exiting is not caught by try statements, but their finally blocks run
+        assert_eq!(
+            exit_code("try { exit(1); } catch (e) { print e; } finally { print \"done\"; }"),
+            ("done\n".to_string(), 1)
+        );
+
+        let (_, result) = run_program_with("exit(256);", Config::default(), |_| {});
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "[line 1] Invalid argument 1 for 'exit': expected an exit status from 0 to 255, got integer(256)."
+        );
+    }
+}
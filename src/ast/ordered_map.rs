@@ -0,0 +1,131 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A hash map that remembers the order in which its keys were first inserted, and iterates over
+/// its entries in that order. Replacing the value of a key keeps its position.
+///
+/// Maps of Lox programs and the scopes of environments are stored in this map, so that printing
+/// them, iterating over them and listing their names gives the same result on every run.
+#[derive(Debug, Clone)]
+pub struct OrderedMap<K, V> {
+    entries: Vec<(K, V)>,
+    /// The position of each key in `entries`.
+    indices: HashMap<K, usize>,
+}
+
+impl<K, V> Default for OrderedMap<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> OrderedMap<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.indices.get(key).map(|&index| &self.entries[index].1)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.indices
+            .get(key)
+            .map(|&index| &mut self.entries[index].1)
+    }
+
+    /// Binds the key to the value, returning the previous value of the key if it had one.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.indices.get(&key) {
+            Some(&index) => Some(std::mem::replace(&mut self.entries[index].1, value)),
+            None => {
+                self.indices.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Unbinds the key, returning its value if it had one. The keys after it keep their order.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let index = self.indices.remove(key)?;
+        let (_, value) = self.entries.remove(index);
+        for (moved, _) in &self.entries[index..] {
+            if let Some(position) = self.indices.get_mut::<K>(moved) {
+                *position -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insertion_order() {
+        let mut map = OrderedMap::new();
+        for key in ["c", "a", "b"] {
+            map.insert(key, key.len());
+        }
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), vec!["c", "a", "b"]);
+
+        // Replacing a value keeps the position of its key
+        assert_eq!(map.insert("c", 0), Some(1));
+        assert_eq!(map.iter().next(), Some((&"c", &0)));
+        assert_eq!(map.get("b"), Some(&1));
+        assert_eq!(map.get("missing"), None);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut map = OrderedMap::new();
+        for key in ["a", "b", "c", "d"] {
+            map.insert(key, key.len());
+        }
+
+        assert_eq!(map.remove("b"), Some(1));
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), vec!["a", "c", "d"]);
+
+        // The keys after the removed one can still be found and replaced
+        assert_eq!(map.insert("d", 0), Some(1));
+        map.insert("b", 2);
+        assert_eq!(map.get("d"), Some(&0));
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), vec![1, 1, 0, 2]);
+    }
+}
@@ -1,18 +1,20 @@
-use super::expr::{Expr, Visitor};
+use super::desugar::lower;
+use super::expr::{BlockBody, Expr, Operator, Visitor};
+use super::ir::{Stmt, Visitor as StmtVisitor};
 use crate::lexer::token::Token;
 use crate::lexer::types::TokenType;
 
 pub struct AstPrinter;
 
 impl Visitor<String> for AstPrinter {
-    fn visit_unary_expr(&self, op: &Token, expr: &Box<Expr>) -> String {
-        format!("({} {})", op.lexeme, expr.accept(self))
+    fn visit_unary_expr(&self, op: &Operator, expr: &Box<Expr>) -> String {
+        format!("({} {})", op.lexeme(), expr.accept(self))
     }
 
-    fn visit_binary_expr(&self, expr1: &Box<Expr>, op: &Token, expr2: &Box<Expr>) -> String {
+    fn visit_binary_expr(&self, expr1: &Box<Expr>, op: &Operator, expr2: &Box<Expr>) -> String {
         format!(
             "({} {} {})",
-            op.lexeme,
+            op.lexeme(),
             expr1.accept(self),
             expr2.accept(self)
         )
@@ -20,7 +22,7 @@ impl Visitor<String> for AstPrinter {
 
     fn visit_literal_expr(&self, token: &Token) -> String {
         match &token.token_type {
-            TokenType::String(_) | TokenType::Number(_) => {
+            TokenType::String(_) | TokenType::Number(_) | TokenType::Integer(_) => {
                 token.get_value()
             }
             TokenType::TRUE => "true".to_string(),
@@ -33,12 +35,279 @@ impl Visitor<String> for AstPrinter {
     fn visit_grouping_expr(&self, expr: &Box<Expr>) -> String {
         format!("(group {})", expr.accept(self))
     }
+
+    fn visit_logical_expr(&self, left: &Box<Expr>, op: &Operator, right: &Box<Expr>) -> String {
+        format!(
+            "({} {} {})",
+            op.lexeme(),
+            left.accept(self),
+            right.accept(self)
+        )
+    }
+
+    fn visit_variable_expr(&self, name: &Token) -> String {
+        name.lexeme.clone()
+    }
+
+    fn visit_assign_expr(&self, name: &Token, value: &Box<Expr>) -> String {
+        format!("(= {} {})", name.lexeme, value.accept(self))
+    }
+
+    fn visit_call_expr(&self, callee: &Box<Expr>, _paren: &Token, args: &Vec<Expr>) -> String {
+        let mut parts = vec![callee.accept(self)];
+        parts.extend(args.iter().map(|arg| arg.accept(self)));
+        format!("(call {})", parts.join(" "))
+    }
+
+    fn visit_list_expr(&self, _bracket: &Token, elements: &Vec<Expr>) -> String {
+        let mut parts = vec!["list".to_string()];
+        parts.extend(elements.iter().map(|element| element.accept(self)));
+        format!("({})", parts.join(" "))
+    }
+
+    fn visit_index_expr(&self, object: &Box<Expr>, _bracket: &Token, index: &Box<Expr>) -> String {
+        format!("(index {} {})", object.accept(self), index.accept(self))
+    }
+
+    fn visit_index_set_expr(
+        &self,
+        object: &Box<Expr>,
+        _bracket: &Token,
+        index: &Box<Expr>,
+        value: &Box<Expr>,
+    ) -> String {
+        format!(
+            "(= (index {} {}) {})",
+            object.accept(self),
+            index.accept(self),
+            value.accept(self)
+        )
+    }
+
+    fn visit_map_expr(&self, _brace: &Token, entries: &Vec<(Expr, Expr)>) -> String {
+        let mut parts = vec!["map".to_string()];
+        parts.extend(
+            entries
+                .iter()
+                .map(|(key, value)| format!("({} {})", key.accept(self), value.accept(self))),
+        );
+        format!("({})", parts.join(" "))
+    }
+
+    fn visit_tuple_expr(&self, _paren: &Token, elements: &Vec<Expr>) -> String {
+        let mut parts = vec!["tuple".to_string()];
+        parts.extend(elements.iter().map(|element| element.accept(self)));
+        format!("({})", parts.join(" "))
+    }
+
+    fn visit_get_expr(&self, object: &Box<Expr>, name: &Token) -> String {
+        format!("(. {} {})", object.accept(self), name.name())
+    }
+
+    fn visit_spread_expr(&self, _ellipsis: &Token, expr: &Box<Expr>) -> String {
+        format!("(... {})", expr.accept(self))
+    }
+
+    fn visit_block_expr(
+        &self,
+        _brace: &Token,
+        body: &BlockBody,
+        value: &Option<Box<Expr>>,
+    ) -> String {
+        // The statements are printed as they run, even when they have not been lowered yet
+        let lowered;
+        let stmts = match body {
+            BlockBody::Surface(stmts) => {
+                lowered = lower(stmts.clone());
+                &lowered
+            }
+            BlockBody::Lowered(stmts) => stmts,
+        };
+
+        let mut parts = vec!["block-expr".to_string()];
+        parts.extend(stmts.iter().map(|stmt| stmt.accept(self)));
+        parts.extend(value.iter().map(|value| value.accept(self)));
+        format!("({})", parts.join(" "))
+    }
+}
+
+impl StmtVisitor<String> for AstPrinter {
+    fn visit_expression_stmt(&self, expr: &Expr) -> String {
+        expr.accept(self)
+    }
+
+    fn visit_print_stmt(&self, exprs: &Vec<Expr>) -> String {
+        let exprs = exprs
+            .iter()
+            .map(|expr| expr.accept(self))
+            .collect::<Vec<_>>();
+        format!("(print {})", exprs.join(" "))
+    }
+
+    fn visit_assert_stmt(
+        &self,
+        _keyword: &Token,
+        condition: &Expr,
+        _text: &String,
+        message: &Option<Expr>,
+    ) -> String {
+        match message {
+            Some(message) => format!(
+                "(assert {} {})",
+                condition.accept(self),
+                message.accept(self)
+            ),
+            None => format!("(assert {})", condition.accept(self)),
+        }
+    }
+
+    fn visit_var_stmt(&self, name: &Token, initializer: &Option<Expr>) -> String {
+        match initializer {
+            Some(expr) => format!("(var {} {})", name.lexeme, expr.accept(self)),
+            None => format!("(var {})", name.lexeme),
+        }
+    }
+
+    fn visit_const_stmt(&self, name: &Token, initializer: &Expr) -> String {
+        format!("(const {} {})", name.lexeme, initializer.accept(self))
+    }
+
+    fn visit_var_tuple_stmt(
+        &self,
+        _paren: &Token,
+        names: &Vec<Token>,
+        initializer: &Expr,
+    ) -> String {
+        let names = names
+            .iter()
+            .map(|name| name.lexeme.as_str())
+            .collect::<Vec<_>>();
+        format!("(var ({}) {})", names.join(" "), initializer.accept(self))
+    }
+
+    fn visit_block_stmt(&self, stmts: &Vec<Stmt>) -> String {
+        let mut parts = vec!["block".to_string()];
+        parts.extend(stmts.iter().map(|stmt| stmt.accept(self)));
+        format!("({})", parts.join(" "))
+    }
+
+    fn visit_if_stmt(
+        &self,
+        condition: &Expr,
+        then_branch: &Box<Stmt>,
+        else_branch: &Option<Box<Stmt>>,
+    ) -> String {
+        match else_branch {
+            Some(else_branch) => format!(
+                "(if {} {} {})",
+                condition.accept(self),
+                then_branch.accept(self),
+                else_branch.accept(self)
+            ),
+            None => format!(
+                "(if {} {})",
+                condition.accept(self),
+                then_branch.accept(self)
+            ),
+        }
+    }
+
+    fn visit_while_stmt(
+        &self,
+        condition: &Expr,
+        body: &Box<Stmt>,
+        increment: &Option<Expr>,
+        label: &Option<Token>,
+    ) -> String {
+        let stmt = match increment {
+            Some(increment) => format!(
+                "(while {} {} {})",
+                condition.accept(self),
+                body.accept(self),
+                increment.accept(self)
+            ),
+            None => format!("(while {} {})", condition.accept(self), body.accept(self)),
+        };
+
+        match label {
+            Some(label) => format!("(label {} {})", label.name(), stmt),
+            None => stmt,
+        }
+    }
+
+    fn visit_for_in_stmt(
+        &self,
+        name: &Token,
+        _keyword: &Token,
+        iterable: &Expr,
+        body: &Box<Stmt>,
+        label: &Option<Token>,
+    ) -> String {
+        let stmt = format!(
+            "(for-in {} {} {})",
+            name.lexeme,
+            iterable.accept(self),
+            body.accept(self)
+        );
+
+        match label {
+            Some(label) => format!("(label {} {})", label.name(), stmt),
+            None => stmt,
+        }
+    }
+
+    fn visit_throw_stmt(&self, _keyword: &Token, value: &Expr) -> String {
+        format!("(throw {})", value.accept(self))
+    }
+
+    fn visit_try_stmt(
+        &self,
+        body: &Vec<Stmt>,
+        catch: &Option<(Token, Vec<Stmt>)>,
+        finally: &Option<Vec<Stmt>>,
+    ) -> String {
+        let mut parts = vec!["try".to_string(), self.visit_block_stmt(body)];
+        if let Some((name, handler)) = catch {
+            parts.push(format!(
+                "(catch {} {})",
+                name.lexeme,
+                self.visit_block_stmt(handler)
+            ));
+        }
+        if let Some(finally) = finally {
+            parts.push(format!("(finally {})", self.visit_block_stmt(finally)));
+        }
+        format!("({})", parts.join(" "))
+    }
+
+    fn visit_break_stmt(&self, _keyword: &Token, label: &Option<Token>) -> String {
+        match label {
+            Some(label) => format!("(break {})", label.name()),
+            None => "(break)".to_string(),
+        }
+    }
+
+    fn visit_continue_stmt(&self, _keyword: &Token, label: &Option<Token>) -> String {
+        match label {
+            Some(label) => format!("(continue {})", label.name()),
+            None => "(continue)".to_string(),
+        }
+    }
 }
 
 impl AstPrinter {
-    pub fn print(expr: Expr) -> String {
+    pub fn print(expr: &Expr) -> String {
         expr.accept(&Self)
     }
+
+    /// Prints each statement of a lowered program on its own line.
+    pub fn print_stmts(stmts: &[Stmt]) -> String {
+        stmts
+            .iter()
+            .map(|stmt| stmt.accept(&Self))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 #[test]
@@ -55,7 +324,7 @@ fn test_print() {
                 Token::new_default(TokenType::Plus, "+"),
                 Expr::new_number_literal(34.0),
             ),
-            expected: "(+ 12 34)",
+            expected: "(+ 12.0 34.0)",
         },
         TestCase {
             input: Expr::new_binary_expr(
@@ -63,7 +332,7 @@ fn test_print() {
                 Token::new_default(TokenType::Plus, "+"),
                 Expr::new_grouping_expr(Expr::new_number_literal(34.0)),
             ),
-            expected: "(+ 12 (group 34))",
+            expected: "(+ 12.0 (group 34.0))",
         },
         TestCase {
             input: Expr::new_binary_expr(
@@ -74,12 +343,86 @@ fn test_print() {
                 Token::new_default(TokenType::Star, "*"),
                 Expr::new_grouping_expr(Expr::new_number_literal(45.67)),
             ),
-            expected: "(* (- 123) (group 45.67))",
+            expected: "(* (- 123.0) (group 45.67))",
+        },
+        TestCase {
+            input: Expr::new_binary_expr(
+                Expr::new_string_literal("foo"),
+                Token::new_default(TokenType::Plus, "+"),
+                Expr::new_string_literal("bar"),
+            ),
+            expected: "(+ foo bar)",
         },
     ];
 
     for t in testcases {
         println!("Testing: {:?}", t.input);
-        assert_eq!(AstPrinter::print(t.input), t.expected);
+        assert_eq!(AstPrinter::print(&t.input), t.expected);
+    }
+}
+
+#[test]
+fn test_print_lowered() {
+    struct TestCase<'a> {
+        input: &'a str,
+        expected: &'a str,
+    }
+
+    let testcases = vec![
+        TestCase {
+            input: "var a = 1; print a, 2;",
+            expected: "(var a 1.0)\n(print a 2.0)",
+        },
+        TestCase {
+            input: "if (a) { print a; } else b = 1;",
+            expected: "(if a (block (print a)) (= b 1.0))",
+        },
+        TestCase {
+            input: "for (var i = 0; i < 2; i = i + 1) print i;",
+            expected: "(block (var i 0.0) (while (< i 2.0) (print i) (= i (+ i 1.0))))",
+        },
+        TestCase {
+            input: "for (;;) f();",
+            expected: "(while true (call f))",
+        },
+        TestCase {
+            input: "xs[0] = [1, [], f(2)[3],];",
+            expected: "(= (index xs 0.0) (list 1.0 (list) (index (call f 2.0) 3.0)))",
+        },
+        TestCase {
+            input: "{ var m = {\"a\": 1, 2: {},}; }",
+            expected: "(block (var m (map (a 1.0) (2.0 (map)))))",
+        },
+        TestCase {
+            input: "switch (x) { case 1, -2: print 1; default: print 2; }",
+            expected: "(block (var $subject x) (if (or (== $subject 1.0) (== $subject (- 2.0))) (print 1.0) (print 2.0)))",
+        },
+        TestCase {
+            input: "do print 1; while (x);",
+            expected: "(block (var $first true) (while (or $first x) (print 1.0) (= $first false)))",
+        },
+        TestCase {
+            input: "outer: for (;;) while (true) { break outer; continue; }",
+            expected: "(label outer (while true (while true (block (break outer) (continue)))))",
+        },
+        TestCase {
+            input: "log.info(m.a.b);",
+            expected: "(call (. log info) (. (. m a) b))",
+        },
+        TestCase {
+            input: "f(...[...xs, 1]);",
+            expected: "(call f (... (list (... xs) 1.0)))",
+        },
+        TestCase {
+            input: "var x = { for (;;) f(); x * 2 }; print {}, { f(); };",
+            expected: "(var x (block-expr (while true (call f)) (* x 2.0)))\n(print (map) (block-expr (call f)))",
+        },
+    ];
+
+    for t in testcases {
+        let tokens = crate::lexer::lexer::Lexer::new(t.input).get_tokens();
+        let stmts = super::syntax_tree::SyntaxTree::new(tokens).parse().unwrap();
+        let stmts = super::desugar::lower(stmts);
+        assert_eq!(AstPrinter::print_stmts(&stmts), t.expected, "{}", t.input);
     }
 }
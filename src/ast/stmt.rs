@@ -0,0 +1,35 @@
+use super::expr::Expr;
+use crate::lexer::token::Token;
+
+/// The statements of the surface language, as written by the user. They are not evaluated
+/// directly, but lowered into the core statements of the IR by the desugaring pass.
+///
+/// The increment of a `While` is only set when it was desugared from a `for` or `do` loop. Loops
+/// carry the label they were given (`outer: while ...`), which `break` and `continue` can refer to.
+#[derive(Debug, Clone)]
+pub enum Stmt {
+    Expression(Expr),
+    Print(Vec<Expr>),
+    Assert(Token, Expr, String, Option<Expr>),
+    Var(Token, Option<Expr>),
+    Const(Token, Expr),
+    VarTuple(Token, Vec<Token>, Expr),
+    Block(Vec<Stmt>),
+    If(Expr, Box<Stmt>, Option<Box<Stmt>>),
+    While(Expr, Box<Stmt>, Option<Expr>, Option<Token>),
+    For(
+        Token,
+        Option<Box<Stmt>>,
+        Option<Expr>,
+        Option<Expr>,
+        Box<Stmt>,
+        Option<Token>,
+    ),
+    DoWhile(Token, Box<Stmt>, Expr, Option<Token>),
+    ForIn(Token, Token, Expr, Box<Stmt>, Option<Token>),
+    Switch(Token, Expr, Vec<(Vec<Expr>, Stmt)>, Option<Box<Stmt>>),
+    Throw(Token, Expr),
+    Try(Vec<Stmt>, Option<(Token, Vec<Stmt>)>, Option<Vec<Stmt>>),
+    Break(Token, Option<Token>),
+    Continue(Token, Option<Token>),
+}
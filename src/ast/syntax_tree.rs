@@ -1,11 +1,23 @@
 use crate::lexer::{token::Token, types::TokenType};
 
-use super::{expr::Expr, printer::AstPrinter};
+use super::{
+    expr::{BlockBody, Expr},
+    stmt::Stmt,
+    value::KINDS,
+};
+
+#[cfg(test)]
+use super::printer::AstPrinter;
 
-#[derive(Debug)]
 pub struct SyntaxTree {
     tokens: Vec<Token>,
+    /// The tokens that have not been read into `tokens` yet, when the program is parsed while it
+    /// is being lexed.
+    pending: Option<Box<dyn Iterator<Item = Token>>>,
     current: usize,
+    /// The labels of the loops enclosing the statement being parsed, from the outermost to the
+    /// innermost. Unlabeled loops are recorded as `None`.
+    loops: Vec<Option<String>>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -27,18 +39,55 @@ impl ParserError {
 
 impl SyntaxTree {
     pub fn new(tokens: Vec<Token>) -> Self {
-        SyntaxTree { tokens, current: 0 }
+        SyntaxTree {
+            tokens,
+            pending: None,
+            current: 0,
+            loops: Vec::new(),
+        }
+    }
+
+    /// Creates a parser that reads the tokens as they are needed, so that the declarations can be
+    /// parsed one at a time with `next_declaration` while the program is being lexed.
+    pub fn streaming(tokens: impl Iterator<Item = Token> + 'static) -> Self {
+        let mut parser = SyntaxTree {
+            tokens: Vec::new(),
+            pending: Some(Box::new(tokens)),
+            current: 0,
+            loops: Vec::new(),
+        };
+        parser.buffer();
+        parser
+    }
+
+    /// Reads pending tokens until the second token after the next one is available, as the parser
+    /// never looks further ahead.
+    fn buffer(&mut self) {
+        if let Some(pending) = &mut self.pending {
+            while self.tokens.len() < self.current + 3 {
+                match pending.next() {
+                    Some(token) => self.tokens.push(token),
+                    None => break,
+                }
+            }
+        }
     }
 
     /// Returns the next token in the source code without consuming it.
     fn peek(&self) -> Option<Token> {
-        self.tokens.get(self.current).map(|t| t.clone())
+        self.tokens.get(self.current).cloned()
+    }
+
+    /// Returns the token after the next one without consuming anything.
+    fn peek_next(&self) -> Option<&Token> {
+        self.tokens.get(self.current + 1)
     }
 
     /// Consumes the next token in the source code and returns it.
     fn consume(&mut self) -> Option<Token> {
         let tok = self.peek();
         self.current += 1;
+        self.buffer();
         tok
     }
 
@@ -50,6 +99,7 @@ impl SyntaxTree {
                 None,
                 "Expected a ) to close the expression, but arrived at EOF",
             )),
+            Some(token) if token.is_error() => Err(lexical_error(&token)),
             Some(token) => match expected_types.contains(&token.token_type) {
                 true => Ok(()),
                 false => Err(ParserError::new(
@@ -80,88 +130,975 @@ impl SyntaxTree {
         }
     }
 
-    /// Parses an expression.
-    pub fn expression(&mut self) -> Result<Expr, ParserError> {
-        self.equality()
+    /// Consumes the next token if it is an identifier and returns it, otherwise returns
+    /// an error with the provided message. Keywords used in place of the identifier are
+    /// reported with a dedicated message, as they are a common mistake.
+    fn expect_identifier(&mut self, message: &str) -> Result<Token, ParserError> {
+        match self.peek() {
+            Some(token) if matches!(token.token_type, TokenType::Identifier(_)) => {
+                self.consume();
+                Ok(token)
+            }
+            Some(token) if token.token_type.is_keyword() => {
+                Err(ParserError::new(
+                    Some(&token),
+                    format!(
+                        "Expected identifier, found keyword '{}'. Note: '{}' is a reserved word and cannot be used as a name.",
+                        token.lexeme, token.lexeme
+                    )
+                    .as_str(),
+                ))
+            }
+            token => Err(ParserError::new(token.as_ref(), message)),
+        }
+    }
+
+    /// Returns true if the next token is of the provided type, without consuming it.
+    fn check(&self, expected: &TokenType) -> bool {
+        self.peek()
+            .is_some_and(|token| &token.token_type == expected)
     }
 
-    /// Parses an equality expression.
-    fn equality(&mut self) -> Result<Expr, ParserError> {
-        let mut expr = self.comparision()?;
+    /// Returns true if all the tokens except the EOF token have been consumed.
+    fn is_at_end(&self) -> bool {
+        self.peek()
+            .is_none_or(|token| token.token_type == TokenType::EOF)
+    }
+
+    /// Parses a complete program, which is a list of declarations.
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, ParserError> {
+        let mut stmts = Vec::new();
 
-        while let Some(tok) = self.matches(&[TokenType::EqualEqual, TokenType::BangEqual]) {
-            let right = self.comparision()?;
-            expr = Expr::new_binary_expr(expr, tok, right);
+        while !self.is_at_end() {
+            stmts.extend(self.declaration()?);
         }
 
-        Ok(expr)
+        Ok(stmts)
     }
 
-    /// Parses a comparision expression.
-    fn comparision(&mut self) -> Result<Expr, ParserError> {
-        let mut expr = self.term()?;
+    /// Parses the next top-level declaration of the program, or returns `None` once all of them
+    /// have been parsed. The tokens of the previous declarations are discarded, so that a streaming
+    /// parser only keeps the tokens of the declaration being parsed.
+    pub fn next_declaration(&mut self) -> Option<Result<Vec<Stmt>, ParserError>> {
+        if self.is_at_end() {
+            return None;
+        }
+
+        self.tokens.drain(..self.current);
+        self.current = 0;
+        Some(self.declaration())
+    }
 
-        while let Some(tok) = self.matches(&[
-            TokenType::Greater,
-            TokenType::GreaterEqual,
-            TokenType::Less,
-            TokenType::LessEqual,
-        ]) {
-            let right = self.term()?;
-            expr = Expr::new_binary_expr(expr, tok, right);
+    /// Parses a complete program like `parse`, but recovers from syntax errors by skipping
+    /// to the start of the next statement, so that all the errors of the program are reported.
+    pub fn parse_all(&mut self) -> Result<Vec<Stmt>, Vec<ParserError>> {
+        let mut stmts = Vec::new();
+        let mut errors = Vec::new();
+
+        while !self.is_at_end() {
+            match self.declaration() {
+                Ok(declared) => stmts.extend(declared),
+                Err(err) => {
+                    errors.push(err);
+                    self.synchronize();
+                }
+            }
         }
 
-        Ok(expr)
+        match errors.is_empty() {
+            true => Ok(stmts),
+            false => Err(errors),
+        }
     }
 
-    /// Parses a term expression.
-    fn term(&mut self) -> Result<Expr, ParserError> {
-        let mut expr = self.factor()?;
+    /// Discards tokens until the end of the current statement, which is either after a
+    /// semicolon or before a keyword that starts a statement.
+    fn synchronize(&mut self) {
+        while !self.is_at_end() {
+            let token = self.consume();
+            if token.is_some_and(|token| token.token_type == TokenType::Semicolon) {
+                return;
+            }
 
-        while let Some(tok) = self.matches(&[TokenType::Plus, TokenType::Minus]) {
-            let right = self.factor()?;
-            expr = Expr::new_binary_expr(expr, tok, right);
+            if self
+                .peek()
+                .is_some_and(|token| starts_statement(&token.token_type))
+            {
+                return;
+            }
         }
+    }
 
-        Ok(expr)
+    /// Parses a declaration, which is either a variable or constant declaration, or a statement.
+    /// Declarations that destructure a list or a map expand into several statements.
+    fn declaration(&mut self) -> Result<Vec<Stmt>, ParserError> {
+        if self.matches(&[TokenType::CONST]).is_some() {
+            return Ok(vec![self.const_declaration()?]);
+        }
+
+        if self.matches(&[TokenType::VAR]).is_none() {
+            return Ok(vec![self.statement()?]);
+        }
+
+        match self.matches(&[TokenType::LeftBracket, TokenType::LeftBrace]) {
+            Some(open) => self.destructuring_declaration(open),
+            None => Ok(vec![self.var_declaration()?]),
+        }
+    }
+
+    /// Parses a variable declaration. Assumes that the `var` keyword has already been consumed.
+    fn var_declaration(&mut self) -> Result<Stmt, ParserError> {
+        if let Some(paren) = self.matches(&[TokenType::LeftParen]) {
+            return self.var_tuple_declaration(paren);
+        }
+
+        // Destructuring lists and maps declares several statements, which only fit in a block
+        if let Some(token) = self.peek().filter(|token| {
+            matches!(
+                token.token_type,
+                TokenType::LeftBracket | TokenType::LeftBrace
+            )
+        }) {
+            return Err(ParserError::new(
+                Some(&token),
+                "Lists and maps can only be destructured in a block or at the top level.",
+            ));
+        }
+
+        let name = self.expect_identifier("Expected variable name.")?;
+
+        let initializer = match self.matches(&[TokenType::Equal]) {
+            Some(_) => Some(self.expression()?),
+            None => None,
+        };
+
+        self.expect(&[TokenType::Semicolon])?;
+        Ok(Stmt::Var(name, initializer))
+    }
+
+    /// Parses the declaration of the variables that a tuple is unpacked into, as in
+    /// `var (x, y) = point;`. Assumes that the opening parenthesis has already been consumed.
+    fn var_tuple_declaration(&mut self, paren: Token) -> Result<Stmt, ParserError> {
+        let names = self.pattern_names(TokenType::RightParen)?;
+        let initializer = self.pattern_initializer(&paren)?;
+        Ok(Stmt::VarTuple(paren, names, initializer))
+    }
+
+    /// Parses a declaration that destructures a list or a map into variables, and desugars it
+    /// into a declaration of each variable from an element of the value, which is evaluated once
+    /// into a variable that can not be named by the user:
+    ///
+    /// ```text
+    /// var [a, b] = xs;    =>    var $destructured = xs; var a = $destructured[0]; var b = $destructured[1];
+    /// var {x, y} = p;     =>    var $destructured = p; var x = $destructured["x"]; var y = $destructured["y"];
+    /// ```
+    ///
+    /// Assumes that the opening bracket or brace has already been consumed.
+    fn destructuring_declaration(&mut self, open: Token) -> Result<Vec<Stmt>, ParserError> {
+        let is_list = open.token_type == TokenType::LeftBracket;
+        let names = match is_list {
+            true => self.pattern_names(TokenType::RightBracket)?,
+            false => self.pattern_names(TokenType::RightBrace)?,
+        };
+        let initializer = self.pattern_initializer(&open)?;
+
+        let value = open.synthesize(
+            TokenType::Identifier("$destructured".to_string()),
+            "$destructured",
+        );
+        let bracket = open.synthesize(TokenType::LeftBracket, "[");
+
+        let mut stmts = vec![Stmt::Var(value.clone(), Some(initializer))];
+        for (i, name) in names.into_iter().enumerate() {
+            let key = match is_list {
+                true => open.synthesize(TokenType::Integer(i as i64), &i.to_string()),
+                false => open.synthesize(
+                    TokenType::String(name.name().to_string()),
+                    &format!("\"{}\"", name.name()),
+                ),
+            };
+            let element = Expr::Index(
+                Box::new(Expr::Variable(value.clone())),
+                bracket.clone(),
+                Box::new(Expr::Literal(key)),
+            );
+            stmts.push(Stmt::Var(name, Some(element)));
+        }
+
+        Ok(stmts)
+    }
+
+    /// Parses the comma separated names of the variables declared by a pattern, up to the token
+    /// that closes the pattern.
+    fn pattern_names(&mut self, close: TokenType) -> Result<Vec<Token>, ParserError> {
+        let mut names: Vec<Token> = Vec::new();
+
+        while self.matches(std::slice::from_ref(&close)).is_none() {
+            let name = self.expect_identifier("Expected variable name.")?;
+            if names.iter().any(|other| other.name() == name.name()) {
+                return Err(ParserError::new(
+                    Some(&name),
+                    format!("Variable '{}' is declared twice.", name.name()).as_str(),
+                ));
+            }
+            names.push(name);
+
+            if self.matches(&[TokenType::Comma]).is_none() {
+                self.expect(&[close])?;
+                break;
+            }
+        }
+
+        Ok(names)
+    }
+
+    /// Parses the initializer of a pattern, up to the end of the declaration. The variables of a
+    /// pattern are only defined from the value they are destructured from, so there must be one.
+    fn pattern_initializer(&mut self, open: &Token) -> Result<Expr, ParserError> {
+        if self.matches(&[TokenType::Equal]).is_none() {
+            return Err(ParserError::new(
+                Some(open),
+                "A destructuring declaration must be initialized.",
+            ));
+        }
+        let initializer = self.expression()?;
+
+        self.expect(&[TokenType::Semicolon])?;
+        Ok(initializer)
+    }
+
+    /// Parses a constant declaration. Assumes that the `const` keyword has already been consumed.
+    fn const_declaration(&mut self) -> Result<Stmt, ParserError> {
+        let name = self.expect_identifier("Expected constant name.")?;
+
+        // A constant can never be assigned later, so it must be initialized
+        if self.matches(&[TokenType::Equal]).is_none() {
+            return Err(ParserError::new(
+                Some(&name),
+                format!("Constant '{}' must be initialized.", name.name()).as_str(),
+            ));
+        }
+        let initializer = self.expression()?;
+
+        self.expect(&[TokenType::Semicolon])?;
+        Ok(Stmt::Const(name, initializer))
+    }
+
+    /// Parses a statement.
+    fn statement(&mut self) -> Result<Stmt, ParserError> {
+        if self.matches(&[TokenType::PRINT]).is_some() {
+            return self.print_statement();
+        }
+        if let Some(keyword) = self.matches(&[TokenType::ASSERT]) {
+            return self.assert_statement(keyword);
+        }
+        if self.matches(&[TokenType::LeftBrace]).is_some() {
+            return Ok(Stmt::Block(self.block()?));
+        }
+        if self.matches(&[TokenType::IF]).is_some() {
+            return self.if_statement();
+        }
+        if self.matches(&[TokenType::WHILE]).is_some() {
+            return self.while_statement(None);
+        }
+        if let Some(keyword) = self.matches(&[TokenType::FOR]) {
+            return self.for_statement(keyword, None);
+        }
+        if let Some(keyword) = self.matches(&[TokenType::DO]) {
+            return self.do_statement(keyword, None);
+        }
+        if let Some(keyword) = self.matches(&[TokenType::SWITCH]) {
+            return self.switch_statement(keyword);
+        }
+        if let Some(keyword) = self.matches(&[TokenType::BREAK, TokenType::CONTINUE]) {
+            return self.jump_statement(keyword);
+        }
+        if self.matches(&[TokenType::TRY]).is_some() {
+            return self.try_statement();
+        }
+        if let Some(keyword) = self.matches(&[TokenType::THROW]) {
+            let value = self.expression()?;
+            self.expect(&[TokenType::Semicolon])?;
+            return Ok(Stmt::Throw(keyword, value));
+        }
+        if self.is_label() {
+            return self.labeled_statement();
+        }
+
+        self.expression_statement()
+    }
+
+    /// Returns true if the next tokens are a label (`name:`).
+    fn is_label(&self) -> bool {
+        self.peek()
+            .is_some_and(|token| matches!(token.token_type, TokenType::Identifier(_)))
+            && self
+                .peek_next()
+                .is_some_and(|token| token.token_type == TokenType::Colon)
+    }
+
+    /// Returns true if the next tokens are a label followed by a loop (`name: while`). Braces in
+    /// expression position use it instead of `is_label`, as `{name: value}` is a map.
+    fn is_labeled_loop(&self) -> bool {
+        self.is_label()
+            && self.tokens.get(self.current + 2).is_some_and(|token| {
+                matches!(
+                    token.token_type,
+                    TokenType::WHILE | TokenType::FOR | TokenType::DO
+                )
+            })
+    }
+
+    /// Parses a loop preceded by a label, which `break` and `continue` statements in its body
+    /// can refer to. Assumes that it has already been checked that the next tokens are a label.
+    fn labeled_statement(&mut self) -> Result<Stmt, ParserError> {
+        let label = self.consume().expect("The label should have been checked");
+        self.expect(&[TokenType::Colon])?;
+
+        if self.loops.contains(&Some(label.name().to_string())) {
+            return Err(ParserError::new(
+                Some(&label),
+                format!(
+                    "Label '{}' is already used by an enclosing loop.",
+                    label.name()
+                )
+                .as_str(),
+            ));
+        }
+
+        if self.matches(&[TokenType::WHILE]).is_some() {
+            return self.while_statement(Some(label));
+        }
+        if let Some(keyword) = self.matches(&[TokenType::FOR]) {
+            return self.for_statement(keyword, Some(label));
+        }
+        if let Some(keyword) = self.matches(&[TokenType::DO]) {
+            return self.do_statement(keyword, Some(label));
+        }
+
+        Err(ParserError::new(
+            self.peek().as_ref(),
+            format!("Expected a loop after the label '{}'.", label.name()).as_str(),
+        ))
+    }
+
+    /// Parses the declarations of a block. Assumes that the opening brace has already been consumed.
+    fn block(&mut self) -> Result<Vec<Stmt>, ParserError> {
+        let mut stmts = Vec::new();
+
+        while !self.is_at_end() && !self.check(&TokenType::RightBrace) {
+            stmts.extend(self.declaration()?);
+        }
+
+        self.expect(&[TokenType::RightBrace])?;
+        Ok(stmts)
+    }
+
+    /// Parses an if statement. Assumes that the `if` keyword has already been consumed.
+    fn if_statement(&mut self) -> Result<Stmt, ParserError> {
+        self.expect(&[TokenType::LeftParen])?;
+        let condition = self.expression()?;
+        self.expect(&[TokenType::RightParen])?;
+
+        let then_branch = Box::new(self.statement()?);
+        let else_branch = match self.matches(&[TokenType::ELSE]) {
+            Some(_) => Some(Box::new(self.statement()?)),
+            None => None,
+        };
+
+        Ok(Stmt::If(condition, then_branch, else_branch))
+    }
+
+    /// Parses a while statement with an optional label.
+    /// Assumes that the `while` keyword has already been consumed.
+    fn while_statement(&mut self, label: Option<Token>) -> Result<Stmt, ParserError> {
+        self.expect(&[TokenType::LeftParen])?;
+        let condition = self.expression()?;
+        self.expect(&[TokenType::RightParen])?;
+
+        let body = self.loop_body(label.as_ref())?;
+        Ok(Stmt::While(condition, Box::new(body), None, label))
+    }
+
+    /// Parses a do-while statement, whose body runs once before the condition is first checked.
+    /// Assumes that the `do` keyword has already been consumed.
+    fn do_statement(&mut self, keyword: Token, label: Option<Token>) -> Result<Stmt, ParserError> {
+        let body = self.loop_body(label.as_ref())?;
+
+        self.expect(&[TokenType::WHILE])?;
+        self.expect(&[TokenType::LeftParen])?;
+        let condition = self.expression()?;
+        self.expect(&[TokenType::RightParen])?;
+        self.expect(&[TokenType::Semicolon])?;
+
+        Ok(Stmt::DoWhile(keyword, Box::new(body), condition, label))
+    }
+
+    /// Parses the body of a loop, keeping track of the enclosing loops and their labels.
+    fn loop_body(&mut self, label: Option<&Token>) -> Result<Stmt, ParserError> {
+        self.loops.push(label.map(|label| label.name().to_string()));
+        let body = self.statement();
+        self.loops.pop();
+
+        body
+    }
+
+    /// Parses a break or continue statement, with an optional label of the loop it targets.
+    /// Assumes that the keyword has already been consumed.
+    fn jump_statement(&mut self, keyword: Token) -> Result<Stmt, ParserError> {
+        if self.loops.is_empty() {
+            return Err(ParserError::new(
+                Some(&keyword),
+                format!("Can't use '{}' outside of a loop.", keyword.lexeme).as_str(),
+            ));
+        }
+
+        let label = match self.peek() {
+            Some(token) if matches!(token.token_type, TokenType::Identifier(_)) => {
+                self.consume();
+                if !self.loops.contains(&Some(token.name().to_string())) {
+                    return Err(ParserError::new(
+                        Some(&token),
+                        format!("Undefined label '{}'.", token.name()).as_str(),
+                    ));
+                }
+                Some(token)
+            }
+            _ => None,
+        };
+
+        self.expect(&[TokenType::Semicolon])?;
+        match keyword.token_type {
+            TokenType::BREAK => Ok(Stmt::Break(keyword, label)),
+            _ => Ok(Stmt::Continue(keyword, label)),
+        }
+    }
+
+    /// Parses a try statement, which must have a catch clause, a finally clause or both.
+    /// Assumes that the `try` keyword has already been consumed.
+    fn try_statement(&mut self) -> Result<Stmt, ParserError> {
+        self.expect(&[TokenType::LeftBrace])?;
+        let body = self.block()?;
+
+        let catch = match self.matches(&[TokenType::CATCH]) {
+            Some(_) => {
+                self.expect(&[TokenType::LeftParen])?;
+                let name = self.expect_identifier("Expected variable name.")?;
+                self.expect(&[TokenType::RightParen])?;
+                self.expect(&[TokenType::LeftBrace])?;
+                Some((name, self.block()?))
+            }
+            None => None,
+        };
+
+        let finally = match self.matches(&[TokenType::FINALLY]) {
+            Some(_) => {
+                self.expect(&[TokenType::LeftBrace])?;
+                Some(self.block()?)
+            }
+            None => None,
+        };
+
+        if catch.is_none() && finally.is_none() {
+            return Err(ParserError::new(
+                self.peek().as_ref(),
+                "Expected 'catch' or 'finally' after the try block.",
+            ));
+        }
+
+        Ok(Stmt::Try(body, catch, finally))
+    }
+
+    /// Parses a for statement with an optional label, whose clauses are all optional.
+    /// Assumes that the `for` keyword has already been consumed.
+    fn for_statement(&mut self, keyword: Token, label: Option<Token>) -> Result<Stmt, ParserError> {
+        self.expect(&[TokenType::LeftParen])?;
+
+        let is_for_in = self
+            .peek()
+            .is_some_and(|token| matches!(token.token_type, TokenType::Identifier(_)))
+            && self
+                .peek_next()
+                .is_some_and(|token| token.token_type == TokenType::IN);
+        if is_for_in {
+            return self.for_in_statement(label);
+        }
+
+        let initializer = match self.matches(&[TokenType::Semicolon, TokenType::VAR]) {
+            Some(token) if token.token_type == TokenType::Semicolon => None,
+            Some(_) => Some(Box::new(self.var_declaration()?)),
+            None => Some(Box::new(self.expression_statement()?)),
+        };
+
+        let condition = match self.matches(&[TokenType::Semicolon]) {
+            Some(_) => None,
+            None => {
+                let condition = self.expression()?;
+                self.expect(&[TokenType::Semicolon])?;
+                Some(condition)
+            }
+        };
+
+        let increment = match self.matches(&[TokenType::RightParen]) {
+            Some(_) => None,
+            None => {
+                let increment = self.expression()?;
+                self.expect(&[TokenType::RightParen])?;
+                Some(increment)
+            }
+        };
+
+        let body = Box::new(self.loop_body(label.as_ref())?);
+        Ok(Stmt::For(
+            keyword,
+            initializer,
+            condition,
+            increment,
+            body,
+            label,
+        ))
+    }
+
+    /// Parses the rest of a `for (name in iterable) body` loop, after the opening parenthesis.
+    fn for_in_statement(&mut self, label: Option<Token>) -> Result<Stmt, ParserError> {
+        let name = self.expect_identifier("Expected variable name.")?;
+        let keyword = self
+            .consume()
+            .expect("The 'in' keyword should follow the name");
+        let iterable = self.expression()?;
+        self.expect(&[TokenType::RightParen])?;
+
+        let body = Box::new(self.loop_body(label.as_ref())?);
+        Ok(Stmt::ForIn(name, keyword, iterable, body, label))
+    }
+
+    /// Parses a switch statement, whose cases are tried in order and do not fall through.
+    /// Assumes that the `switch` keyword has already been consumed.
+    fn switch_statement(&mut self, keyword: Token) -> Result<Stmt, ParserError> {
+        self.expect(&[TokenType::LeftParen])?;
+        let subject = self.expression()?;
+        self.expect(&[TokenType::RightParen])?;
+        self.expect(&[TokenType::LeftBrace])?;
+
+        let mut cases = Vec::new();
+        let mut default = None;
+
+        while self.matches(&[TokenType::RightBrace]).is_none() {
+            let token = self.peek();
+
+            if self.matches(&[TokenType::CASE]).is_some() {
+                if default.is_some() {
+                    return Err(ParserError::new(
+                        token.as_ref(),
+                        "The default case must be the last case of a switch.",
+                    ));
+                }
+
+                let mut patterns = vec![self.pattern()?];
+                while self.matches(&[TokenType::Comma]).is_some() {
+                    patterns.push(self.pattern()?);
+                }
+                self.expect(&[TokenType::Colon])?;
+
+                cases.push((patterns, self.statement()?));
+            } else if self.matches(&[TokenType::DEFAULT]).is_some() {
+                if default.is_some() {
+                    return Err(ParserError::new(
+                        token.as_ref(),
+                        "A switch can only have one default case.",
+                    ));
+                }
+
+                self.expect(&[TokenType::Colon])?;
+                default = Some(Box::new(self.statement()?));
+            } else {
+                return Err(ParserError::new(
+                    token.as_ref(),
+                    "Expected 'case', 'default' or '}' in the switch.",
+                ));
+            }
+        }
+
+        Ok(Stmt::Switch(keyword, subject, cases, default))
+    }
+
+    /// Parses the literal pattern of a switch case, which can be a negative number.
+    fn pattern(&mut self) -> Result<Expr, ParserError> {
+        let token = self.consume();
+
+        match token.as_ref().map(|token| &token.token_type) {
+            Some(
+                TokenType::String(_)
+                | TokenType::Number(_)
+                | TokenType::Integer(_)
+                | TokenType::TRUE
+                | TokenType::FALSE
+                | TokenType::NIL,
+            ) => Ok(Expr::Literal(token.unwrap())),
+
+            Some(TokenType::Minus)
+                if self.peek().is_some_and(|next| {
+                    matches!(
+                        next.token_type,
+                        TokenType::Number(_) | TokenType::Integer(_)
+                    )
+                }) =>
+            {
+                let number = self.consume().unwrap();
+                Ok(Expr::new_unary_expr(token.unwrap(), Expr::Literal(number)))
+            }
+
+            _ => Err(ParserError::new(
+                token.as_ref(),
+                "Expected a literal pattern in the switch case.",
+            )),
+        }
+    }
+
+    /// Parses a print statement with one or more comma separated expressions.
+    /// Assumes that the `print` keyword has already been consumed.
+    fn print_statement(&mut self) -> Result<Stmt, ParserError> {
+        let mut exprs = vec![self.expression()?];
+        while self.matches(&[TokenType::Comma]).is_some() {
+            exprs.push(self.expression()?);
+        }
+
+        self.expect(&[TokenType::Semicolon])?;
+        Ok(Stmt::Print(exprs))
+    }
+
+    /// Parses an assert statement, whose optional message follows the condition after a comma.
+    /// The text of the condition is kept for the error message. Assumes that the `assert` keyword
+    /// has already been consumed.
+    fn assert_statement(&mut self, keyword: Token) -> Result<Stmt, ParserError> {
+        let start = self.current;
+        let condition = self.expression()?;
+        let text = source_text(&self.tokens[start..self.current]);
+
+        let message = match self.matches(&[TokenType::Comma]) {
+            Some(_) => Some(self.expression()?),
+            None => None,
+        };
+
+        self.expect(&[TokenType::Semicolon])?;
+        Ok(Stmt::Assert(keyword, condition, text, message))
+    }
+
+    /// Parses an expression statement.
+    fn expression_statement(&mut self) -> Result<Stmt, ParserError> {
+        let expr = self.expression()?;
+        self.expect(&[TokenType::Semicolon])?;
+        Ok(Stmt::Expression(expr))
+    }
+
+    /// Parses a single expression that spans all the tokens, as typed in the REPL.
+    pub fn parse_expression(&mut self) -> Result<Expr, ParserError> {
+        let expr = self.expression()?;
+
+        match self.is_at_end() {
+            true => Ok(expr),
+            false => Err(ParserError::new(
+                self.peek().as_ref(),
+                "Expected the end of the expression.",
+            )),
+        }
+    }
+
+    /// Parses an expression.
+    pub fn expression(&mut self) -> Result<Expr, ParserError> {
+        self.assignment()
     }
 
-    /// Parses a factor expression.
-    fn factor(&mut self) -> Result<Expr, ParserError> {
+    /// Parses an assignment expression, which is right associative.
+    fn assignment(&mut self) -> Result<Expr, ParserError> {
+        let expr = self.binary(1)?;
+
+        match self.matches(&[TokenType::Equal]) {
+            None => Ok(expr),
+            Some(equals) => {
+                let value = self.assignment()?;
+
+                match expr {
+                    Expr::Variable(name) => Ok(Expr::Assign(name, Box::new(value))),
+                    Expr::Index(object, bracket, index) => {
+                        Ok(Expr::IndexSet(object, bracket, index, Box::new(value)))
+                    }
+                    _ => Err(ParserError::new(
+                        Some(&equals),
+                        "Invalid assignment target.",
+                    )),
+                }
+            }
+        }
+    }
+
+    /// Parses the binary and logical operators whose binding power is at least the given one,
+    /// so that operators which bind tighter end up deeper in the tree.
+    fn binary(&mut self, min_power: u8) -> Result<Expr, ParserError> {
         let mut expr = self.unary()?;
 
-        while let Some(tok) = self.matches(&[TokenType::Star, TokenType::Slash]) {
-            let right = self.unary()?;
-            expr = Expr::new_binary_expr(expr, tok, right);
+        while let Some(power) = self
+            .peek()
+            .and_then(|token| token.token_type.binding_power())
+            .filter(|&power| power >= min_power)
+        {
+            let tok = self.consume().expect("The operator was just peeked");
+            let right = match tok.token_type {
+                TokenType::IS => self.kind()?,
+                _ => self.binary(power + 1)?,
+            };
+            expr = match tok.token_type {
+                TokenType::AND | TokenType::OR => Expr::new_logical_expr(expr, tok, right),
+                _ => Expr::new_binary_expr(expr, tok, right),
+            };
         }
 
         Ok(expr)
     }
 
+    /// Parses the name of a kind of values after `is`. There are no values for types, so the
+    /// name is stored as a string literal, which is what the interpreter compares kinds with.
+    fn kind(&mut self) -> Result<Expr, ParserError> {
+        let mut name = self.expect_identifier("Expected a type name after 'is'.")?;
+        if !KINDS.contains(&name.name()) {
+            return Err(ParserError::new(
+                Some(&name),
+                format!(
+                    "Unknown type '{}', expected one of {}.",
+                    name.name(),
+                    KINDS.join(", ")
+                )
+                .as_str(),
+            ));
+        }
+
+        name.token_type = TokenType::String(name.name().to_string());
+        Ok(Expr::Literal(name))
+    }
+
     /// Parses a unary expression.
     fn unary(&mut self) -> Result<Expr, ParserError> {
-        match self.matches(&[TokenType::Bang, TokenType::Minus]) {
-            None => self.primary(),
+        match self.matches(&[TokenType::Bang, TokenType::Minus, TokenType::TYPEOF]) {
+            None => self.call(),
             Some(tok) => Ok(Expr::new_unary_expr(tok, self.unary()?)),
         }
     }
 
+    /// Parses a call, an index or a property expression, which can be chained like `f(1)(2)`,
+    /// `xs[0][1]` or `log.info("started")`.
+    fn call(&mut self) -> Result<Expr, ParserError> {
+        let mut expr = self.primary()?;
+
+        while let Some(token) =
+            self.matches(&[TokenType::LeftParen, TokenType::LeftBracket, TokenType::Dot])
+        {
+            if token.token_type == TokenType::Dot {
+                let name = self.expect_identifier("Expected property name after '.'.")?;
+                expr = Expr::Get(Box::new(expr), name);
+                continue;
+            }
+
+            if token.token_type == TokenType::LeftBracket {
+                let index = self.expression()?;
+                self.expect(&[TokenType::RightBracket])?;
+                expr = Expr::Index(Box::new(expr), token, Box::new(index));
+                continue;
+            }
+
+            let mut args = Vec::new();
+
+            if self.matches(&[TokenType::RightParen]).is_none() {
+                loop {
+                    args.push(self.spreadable()?);
+                    if self.matches(&[TokenType::Comma]).is_none() {
+                        break;
+                    }
+                }
+                self.expect(&[TokenType::RightParen])?;
+            }
+
+            // The closing parenthesis is stored to report the location of errors in the call
+            let paren = self.tokens[self.current - 1].clone();
+            expr = Expr::Call(Box::new(expr), paren, args);
+        }
+
+        Ok(expr)
+    }
+
+    /// Parses the elements of a list literal, which can have a trailing comma.
+    /// Assumes that the opening bracket has already been consumed.
+    fn list(&mut self, bracket: Token) -> Result<Expr, ParserError> {
+        let mut elements = Vec::new();
+
+        while self.matches(&[TokenType::RightBracket]).is_none() {
+            elements.push(self.spreadable()?);
+
+            if self.matches(&[TokenType::Comma]).is_none() {
+                self.expect(&[TokenType::RightBracket])?;
+                break;
+            }
+        }
+
+        Ok(Expr::List(bracket, elements))
+    }
+
+    /// Parses an element of a list or an argument of a call, which can be a list spread into its
+    /// elements (`...xs`).
+    fn spreadable(&mut self) -> Result<Expr, ParserError> {
+        match self.matches(&[TokenType::DotDotDot]) {
+            Some(ellipsis) => Ok(Expr::Spread(ellipsis, Box::new(self.expression()?))),
+            None => self.expression(),
+        }
+    }
+
+    /// Parses a parenthesized expression, which is a tuple if it is empty or contains a comma,
+    /// as in `()`, `(a,)` and `(a, b)`. Assumes that the opening parenthesis has already been
+    /// consumed.
+    fn grouping_or_tuple(&mut self, paren: Token) -> Result<Expr, ParserError> {
+        if self.matches(&[TokenType::RightParen]).is_some() {
+            return Ok(Expr::Tuple(paren, Vec::new()));
+        }
+
+        let expr = self.expression()?;
+        if self.matches(&[TokenType::Comma]).is_none() {
+            self.expect(&[TokenType::RightParen])?;
+            return Ok(Expr::new_grouping_expr(expr));
+        }
+
+        let mut elements = vec![expr];
+        while self.matches(&[TokenType::RightParen]).is_none() {
+            elements.push(self.expression()?);
+
+            if self.matches(&[TokenType::Comma]).is_none() {
+                self.expect(&[TokenType::RightParen])?;
+                break;
+            }
+        }
+
+        Ok(Expr::Tuple(paren, elements))
+    }
+
+    /// Parses a brace in expression position, which opens a map if it is empty or its first
+    /// expression is followed by a colon, and a block expression otherwise. Assumes that the
+    /// opening brace has already been consumed.
+    fn map_or_block(&mut self, brace: Token) -> Result<Expr, ParserError> {
+        if self.check(&TokenType::RightBrace) {
+            return self.map(brace, None);
+        }
+        if self.starts_block_item() {
+            return self.block_expression(brace, None);
+        }
+
+        let first = self.expression()?;
+        match self.check(&TokenType::Colon) {
+            true => self.map(brace, Some(first)),
+            false => self.block_expression(brace, Some(first)),
+        }
+    }
+
+    /// Returns true if the next token starts a statement of a block rather than an expression.
+    fn starts_block_item(&self) -> bool {
+        self.is_labeled_loop()
+            || self.peek().is_some_and(|token| {
+                token.token_type == TokenType::LeftBrace || starts_statement(&token.token_type)
+            })
+    }
+
+    /// Parses the `key: value` entries of a map literal, which can have a trailing comma. The
+    /// first key is passed when it has already been parsed. Assumes that the opening brace has
+    /// already been consumed.
+    fn map(&mut self, brace: Token, mut first: Option<Expr>) -> Result<Expr, ParserError> {
+        let mut entries = Vec::new();
+
+        while first.is_some() || self.matches(&[TokenType::RightBrace]).is_none() {
+            let key = match first.take() {
+                Some(key) => key,
+                None => self.expression()?,
+            };
+            self.expect(&[TokenType::Colon])?;
+            entries.push((key, self.expression()?));
+
+            if self.matches(&[TokenType::Comma]).is_none() {
+                self.expect(&[TokenType::RightBrace])?;
+                break;
+            }
+        }
+
+        Ok(Expr::Map(brace, entries))
+    }
+
+    /// Parses the statements of a block expression, whose value is the expression that ends it
+    /// without a semicolon, or `nil` if there is none. The first expression is passed when it has
+    /// already been parsed. Loops outside of the block can not be exited from inside it.
+    fn block_expression(&mut self, brace: Token, first: Option<Expr>) -> Result<Expr, ParserError> {
+        let loops = std::mem::take(&mut self.loops);
+        let result = self.block_expression_items(first);
+        self.loops = loops;
+
+        let (stmts, value) = result?;
+        Ok(Expr::Block(
+            brace,
+            BlockBody::Surface(stmts),
+            value.map(Box::new),
+        ))
+    }
+
+    fn block_expression_items(
+        &mut self,
+        mut first: Option<Expr>,
+    ) -> Result<(Vec<Stmt>, Option<Expr>), ParserError> {
+        let mut stmts = Vec::new();
+
+        loop {
+            let expr = match first.take() {
+                Some(expr) => expr,
+                None if self.matches(&[TokenType::RightBrace]).is_some() => {
+                    return Ok((stmts, None))
+                }
+                None if self.is_at_end() || self.starts_block_item() => {
+                    stmts.extend(self.declaration()?);
+                    continue;
+                }
+                None => self.expression()?,
+            };
+
+            if self.matches(&[TokenType::Semicolon]).is_some() {
+                stmts.push(Stmt::Expression(expr));
+                continue;
+            }
+            if self.matches(&[TokenType::RightBrace]).is_none() {
+                return Err(ParserError::new(
+                    self.peek().as_ref(),
+                    "Expected ';' or '}' after expression.",
+                ));
+            }
+            return Ok((stmts, Some(expr)));
+        }
+    }
+
     /// Parses a primary expression.
     fn primary(&mut self) -> Result<Expr, ParserError> {
         match self.consume() {
             None => Err(ParserError::new(None, "Unexpected end of file")),
             Some(token) => match token.token_type {
-                TokenType::String(_)
-                | TokenType::Number(_)
-                | TokenType::TRUE
-                | TokenType::FALSE
-                | TokenType::NIL => Ok(Expr::Literal(token)),
+                ref token_type if token_type.is_literal() => Ok(Expr::Literal(token)),
 
-                TokenType::LeftParen => {
-                    let expr = self.expression()?;
-                    let _ = self.expect(&[TokenType::RightParen])?;
-                    Ok(Expr::new_grouping_expr(expr))
-                }
+                TokenType::Identifier(_) => Ok(Expr::Variable(token)),
+
+                TokenType::LeftParen => self.grouping_or_tuple(token),
+
+                TokenType::LeftBracket => self.list(token),
+
+                // Braces are only parsed as a map or a block expression in expression position,
+                // at the start of a statement they always open a block
+                TokenType::LeftBrace => self.map_or_block(token),
+
+                _ if token.is_error() => Err(lexical_error(&token)),
+
+                ref token_type if token_type.is_operator() => Err(ParserError::new(
+                    Some(&token),
+                    format!("Expected an operand before '{}'.", token.lexeme).as_str(),
+                )),
 
                 _ => Err(ParserError::new(
                     Some(&token),
@@ -177,9 +1114,10 @@ impl SyntaxTree {
 
     /// Prints the syntax tree generated from the source code.
     /// Makes use of the AstPrinter to generate the string representation of the syntax tree.
+    #[cfg(test)]
     fn print(&mut self) -> String {
         match self.expression() {
-            Ok(expr) => AstPrinter::print(expr),
+            Ok(expr) => AstPrinter::print(&expr),
             Err(err) => format!(
                 "Error at line {}:{}: {}",
                 err.line, err.character, err.message
@@ -188,6 +1126,99 @@ impl SyntaxTree {
     }
 }
 
+/// Returns true for the keywords that start a statement.
+fn starts_statement(token_type: &TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::ASSERT
+            | TokenType::BREAK
+            | TokenType::CLASS
+            | TokenType::CONST
+            | TokenType::CONTINUE
+            | TokenType::DO
+            | TokenType::FOR
+            | TokenType::FUN
+            | TokenType::IF
+            | TokenType::PRINT
+            | TokenType::RETURN
+            | TokenType::SWITCH
+            | TokenType::THROW
+            | TokenType::TRY
+            | TokenType::VAR
+            | TokenType::WHILE
+    )
+}
+
+/// Writes the tokens back as source code. The spacing of the original source is not kept, so the
+/// tokens are separated by single spaces, except around brackets, commas and unary operators.
+fn source_text(tokens: &[Token]) -> String {
+    let mut text = String::new();
+
+    for (position, token) in tokens.iter().enumerate() {
+        let attached = match position.checked_sub(1).map(|before| &tokens[before]) {
+            None => true,
+            Some(previous) => {
+                let after_opening = matches!(
+                    previous.token_type,
+                    TokenType::LeftParen
+                        | TokenType::LeftBracket
+                        | TokenType::LeftBrace
+                        | TokenType::Bang
+                        | TokenType::Dot
+                        | TokenType::DotDotDot
+                );
+                let closing = matches!(
+                    token.token_type,
+                    TokenType::RightParen
+                        | TokenType::RightBracket
+                        | TokenType::RightBrace
+                        | TokenType::Comma
+                        | TokenType::Colon
+                        | TokenType::Dot
+                );
+                // Calls and indexing are attached to the expression they apply to
+                let applied = matches!(
+                    token.token_type,
+                    TokenType::LeftParen | TokenType::LeftBracket
+                ) && is_operand_end(previous);
+                // A minus negates the operand after it when it does not follow an operand
+                let negation = previous.token_type == TokenType::Minus
+                    && (position < 2 || !is_operand_end(&tokens[position - 2]));
+                after_opening || closing || applied || negation
+            }
+        };
+
+        if !attached {
+            text.push(' ');
+        }
+        text.push_str(&token.lexeme);
+    }
+
+    text
+}
+
+/// Returns whether the token can be the last token of an operand.
+fn is_operand_end(token: &Token) -> bool {
+    token.token_type.is_literal()
+        || matches!(
+            token.token_type,
+            TokenType::Identifier(_)
+                | TokenType::RightParen
+                | TokenType::RightBracket
+                | TokenType::RightBrace
+        )
+}
+
+/// Reports the error token wherever it is found, with the message of the tokenize command.
+fn lexical_error(token: &Token) -> ParserError {
+    ParserError::new(
+        Some(token),
+        &token
+            .error_message()
+            .expect("Error tokens should have a message"),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,6 +1242,302 @@ mod tests {
         assert!(syntax_tree.expect(&[TokenType::AND]).is_ok());
     }
 
+    #[test]
+    fn test_keyword_as_identifier() {
+        let tokens = crate::lexer::lexer::Lexer::new("var class = 3;").get_tokens();
+        let err = SyntaxTree::new(tokens).parse().unwrap_err();
+        assert_eq!(
+            err.message,
+            "Expected identifier, found keyword 'class'. Note: 'class' is a reserved word and cannot be used as a name."
+        );
+
+        let tokens = crate::lexer::lexer::Lexer::new("var 3 = 3;").get_tokens();
+        let err = SyntaxTree::new(tokens).parse().unwrap_err();
+        assert_eq!(err.message, "Expected variable name.");
+    }
+
+    #[test]
+    fn test_missing_operand() {
+        let tokens = crate::lexer::lexer::Lexer::new("print 1 + * 2;").get_tokens();
+        let err = SyntaxTree::new(tokens).parse().unwrap_err();
+        assert_eq!(err.message, "Expected an operand before '*'.");
+    }
+
+    #[test]
+    fn test_lexical_errors() {
+        let config = crate::config::Config {
+            number_separators: true,
+            ..Default::default()
+        };
+        let cases = [
+            (
+                "print 1__0;",
+                "Error: Consecutive underscores are not allowed in number literals.",
+            ),
+            ("print $;", "Error: Unexpected character: $"),
+            ("print \"abc", "Error: Unterminated string."),
+            // Also when a token of another type is expected in their place
+            ("print 1 \"abc", "Error: Unterminated string."),
+            (
+                "var a = 1 1__0;",
+                "Error: Consecutive underscores are not allowed in number literals.",
+            ),
+            ("print (1 $);", "Error: Unexpected character: $"),
+        ];
+
+        // Error tokens are reported with the message of the tokenize command
+        for (source, message) in cases {
+            let tokens =
+                crate::lexer::lexer::Lexer::with_config(source, config.clone()).get_tokens();
+            let err = SyntaxTree::new(tokens).parse().unwrap_err();
+            assert_eq!(err.message, message, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_const_declaration() {
+        let parse = |source| {
+            let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+            SyntaxTree::new(tokens).parse()
+        };
+
+        assert!(parse("const a = 1;").is_ok());
+        assert_eq!(
+            parse("const a;").unwrap_err().message,
+            "Constant 'a' must be initialized."
+        );
+        assert_eq!(
+            parse("const = 1;").unwrap_err().message,
+            "Expected constant name."
+        );
+    }
+
+    #[test]
+    fn test_tuples() {
+        let parse_expr = |source| {
+            let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+            AstPrinter::print(&SyntaxTree::new(tokens).expression().unwrap())
+        };
+        assert_eq!(parse_expr("(1, 2)"), "(tuple 1.0 2.0)");
+        assert_eq!(parse_expr("(1, 2,)"), "(tuple 1.0 2.0)");
+        assert_eq!(parse_expr("(1,)"), "(tuple 1.0)");
+        assert_eq!(parse_expr("()"), "(tuple)");
+        assert_eq!(parse_expr("(1)"), "(group 1.0)");
+
+        let parse = |source| {
+            let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+            SyntaxTree::new(tokens).parse()
+        };
+        assert!(parse("var (a, b) = t;").is_ok());
+        assert_eq!(
+            parse("var (a, b);").unwrap_err().message,
+            "A destructuring declaration must be initialized."
+        );
+        assert_eq!(
+            parse("var (a, a) = t;").unwrap_err().message,
+            "Variable 'a' is declared twice."
+        );
+    }
+
+    #[test]
+    fn test_destructuring() {
+        let parse = |source| {
+            let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+            SyntaxTree::new(tokens)
+                .parse()
+                .map(|stmts| AstPrinter::print_stmts(&crate::ast::desugar::lower(stmts)))
+        };
+
+        assert_eq!(
+            parse("var [a, b] = xs;").unwrap(),
+            "(var $destructured xs)\n(var a (index $destructured 0.0))\n(var b (index $destructured 1.0))"
+        );
+        assert_eq!(
+            parse("{ var {x, y,} = p; }").unwrap(),
+            "(block (var $destructured p) (var x (index $destructured x)) (var y (index $destructured y)))"
+        );
+        assert_eq!(
+            parse("var [a] ;").unwrap_err().message,
+            "A destructuring declaration must be initialized."
+        );
+        assert_eq!(
+            parse("var {x, x} = p;").unwrap_err().message,
+            "Variable 'x' is declared twice."
+        );
+        assert_eq!(
+            parse("for (var [i] = xs; i < 1;) print i;")
+                .unwrap_err()
+                .message,
+            "Lists and maps can only be destructured in a block or at the top level."
+        );
+    }
+
+    #[test]
+    fn test_for_in() {
+        let parse = |source| {
+            let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+            SyntaxTree::new(tokens)
+                .parse()
+                .map(|stmts| AstPrinter::print_stmts(&crate::ast::desugar::lower(stmts)))
+                .map_err(|err| err.message)
+        };
+
+        assert_eq!(
+            parse("for (x in xs) print x;").unwrap(),
+            "(for-in x xs (print x))"
+        );
+        assert_eq!(
+            parse("a: for (x in xs) break a;").unwrap(),
+            "(label a (for-in x xs (break a)))"
+        );
+        // A name followed by anything else starts a regular for loop
+        assert_eq!(
+            parse("for (x; x;) print x;").unwrap(),
+            "(block x (while x (print x)))"
+        );
+        assert_eq!(
+            parse("for (x in xs print x;").unwrap_err(),
+            "Expected one of types [RightParen], but got PRINT"
+        );
+    }
+
+    #[test]
+    fn test_try() {
+        let parse = |source| {
+            let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+            SyntaxTree::new(tokens)
+                .parse()
+                .map(|stmts| AstPrinter::print_stmts(&crate::ast::desugar::lower(stmts)))
+                .map_err(|err| err.message)
+        };
+
+        assert_eq!(
+            parse("try { throw 1; } catch (e) { print e; } finally {}").unwrap(),
+            "(try (block (throw 1.0)) (catch e (block (print e))) (finally (block)))"
+        );
+        assert_eq!(
+            parse("try {} finally { print 1; }").unwrap(),
+            "(try (block) (finally (block (print 1.0))))"
+        );
+        assert_eq!(
+            parse("try {} print 1;").unwrap_err(),
+            "Expected 'catch' or 'finally' after the try block."
+        );
+        assert_eq!(
+            parse("try {} catch {}").unwrap_err(),
+            "Expected one of types [LeftParen], but got LeftBrace"
+        );
+    }
+
+    #[test]
+    fn test_assert() {
+        let parse = |source| {
+            let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+            SyntaxTree::new(tokens).parse().map_err(|err| err.message)
+        };
+        let text = |source| match parse(source).unwrap().remove(0) {
+            Stmt::Assert(_, _, text, _) => text,
+            stmt => panic!("Expected an assert statement, got {:?}", stmt),
+        };
+
+        assert_eq!(text("assert x;"), "x");
+        assert_eq!(text("assert  a+b*2==-c ;"), "a + b * 2 == -c");
+        assert_eq!(
+            text("assert f(x, -(1))[0] != {\"k\": [1]}, \"message\";"),
+            "f(x, -(1))[0] != {\"k\": [1]}"
+        );
+        assert_eq!(text("assert !(a - -1);"), "!(a - -1)");
+        assert_eq!(
+            parse("assert x, ;").unwrap_err(),
+            "Unexpected type of token, expected a literal but got Semicolon"
+        );
+    }
+
+    #[test]
+    fn test_labels() {
+        let parse = |source| {
+            let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+            SyntaxTree::new(tokens).parse().map_err(|err| err.message)
+        };
+
+        assert!(parse("outer: while (true) { for (;;) { break outer; continue; } }").is_ok());
+        assert!(parse("a: while (true) {} b: while (true) break b;").is_ok());
+        assert_eq!(
+            parse("break;").unwrap_err(),
+            "Can't use 'break' outside of a loop."
+        );
+        assert_eq!(
+            parse("a: while (true) {} while (true) continue a;").unwrap_err(),
+            "Undefined label 'a'."
+        );
+        assert_eq!(
+            parse("a: while (true) a: while (true) {}").unwrap_err(),
+            "Label 'a' is already used by an enclosing loop."
+        );
+        assert_eq!(
+            parse("a: print 1;").unwrap_err(),
+            "Expected a loop after the label 'a'."
+        );
+    }
+
+    #[test]
+    fn test_streaming() {
+        let source = "var a = 1;\n{ print a; }\nprint (a;\nprint 2;";
+        let lexer = crate::lexer::lexer::Lexer::new(source);
+        let mut parser = SyntaxTree::streaming(lexer.into_tokens());
+
+        let mut next = || {
+            parser.next_declaration().map(|declared| {
+                declared
+                    .map(|stmts| AstPrinter::print_stmts(&crate::ast::desugar::lower(stmts)))
+                    .map_err(|err| (err.line, err.message))
+            })
+        };
+        assert_eq!(next(), Some(Ok("(var a 1.0)".to_string())));
+        assert_eq!(next(), Some(Ok("(block (print a))".to_string())));
+        // Errors keep the position of the token in the whole program
+        assert_eq!(
+            next(),
+            Some(Err((
+                3,
+                "Expected one of types [RightParen], but got Semicolon".to_string()
+            )))
+        );
+
+        let lexer = crate::lexer::lexer::Lexer::new("print 1; print 2;");
+        let mut parser = SyntaxTree::streaming(lexer.into_tokens());
+        assert!(parser
+            .next_declaration()
+            .is_some_and(|declared| declared.is_ok()));
+        assert!(parser
+            .next_declaration()
+            .is_some_and(|declared| declared.is_ok()));
+        assert!(parser.next_declaration().is_none());
+    }
+
+    #[test]
+    fn test_parse_all() {
+        let parse_all = |source| {
+            let tokens = crate::lexer::lexer::Lexer::new(source).get_tokens();
+            SyntaxTree::new(tokens).parse_all().map_err(|errors| {
+                errors
+                    .into_iter()
+                    .map(|err| (err.line, err.message))
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(parse_all("print 1;\nvar a = 2;").unwrap().len(), 2);
+
+        // Parsing resumes at the next statement after an error
+        let errors = parse_all("var = 1;\nprint 2;\nbreak;\nprint 3 print 4;\nvar b;").unwrap_err();
+        assert_eq!(
+            errors.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+            vec![1, 3, 4]
+        );
+        assert_eq!(errors[1].1, "Can't use 'break' outside of a loop.");
+    }
+
     #[test]
     fn test_print_valid_expr() {
         struct TestCase<'a> {
@@ -223,87 +1550,92 @@ mod tests {
             TestCase {
                 name: "Simple addition",
                 input: "12 + 34",
-                expected: "(+ 12 34)",
+                expected: "(+ 12.0 34.0)",
             },
             TestCase {
                 name: "Simple subtraction",
                 input: "12 - 34",
-                expected: "(- 12 34)",
+                expected: "(- 12.0 34.0)",
             },
             TestCase {
                 name: "Simple multiplication",
                 input: "12 * 34",
-                expected: "(* 12 34)",
+                expected: "(* 12.0 34.0)",
             },
             TestCase {
                 name: "Simple division",
                 input: "12 / 34",
-                expected: "(/ 12 34)",
+                expected: "(/ 12.0 34.0)",
             },
             TestCase {
                 name: "Simple grouping",
                 input: "(12 + 34)",
-                expected: "(group (+ 12 34))",
+                expected: "(group (+ 12.0 34.0))",
             },
             TestCase {
                 name: "Simple grouping with multiple operators",
                 input: "(12 + 34) * 56",
-                expected: "(* (group (+ 12 34)) 56)",
+                expected: "(* (group (+ 12.0 34.0)) 56.0)",
             },
             TestCase {
                 name: "Precedence: Grouping",
                 input: "12 + (34 * 56)",
-                expected: "(+ 12 (group (* 34 56)))",
+                expected: "(+ 12.0 (group (* 34.0 56.0)))",
             },
             TestCase {
                 name: "Precedence: Primary > Unary",
                 input: "-12 + 34",
-                expected: "(+ (- 12) 34)",
+                expected: "(+ (- 12.0) 34.0)",
             },
             TestCase {
                 name: "Precedence: Unary > Factor",
                 input: "-12 * 34",
-                expected: "(* (- 12) 34)",
+                expected: "(* (- 12.0) 34.0)",
             },
             TestCase {
                 name: "Precedence: Factor > Term",
                 input: "-12 * 34 / 56",
-                expected: "(/ (* (- 12) 34) 56)",
+                expected: "(/ (* (- 12.0) 34.0) 56.0)",
             },
             TestCase {
                 name: "Precedence: Term > Comparison",
                 input: "-12 * 34 / 56 > 78",
-                expected: "(> (/ (* (- 12) 34) 56) 78)",
+                expected: "(> (/ (* (- 12.0) 34.0) 56.0) 78.0)",
             },
             TestCase {
                 name: "Precedence: Comparison > Equality",
                 input: "-12 * 34 / 56 > 78 == 90",
-                expected: "(== (> (/ (* (- 12) 34) 56) 78) 90)",
+                expected: "(== (> (/ (* (- 12.0) 34.0) 56.0) 78.0) 90.0)",
             },
             TestCase {
                 name: "Left Associativity: Equality",
                 input: "12 == 34 == 56",
-                expected: "(== (== 12 34) 56)",
+                expected: "(== (== 12.0 34.0) 56.0)",
             },
             TestCase {
                 name: "Left Associativity: Comparison",
                 input: "12 > 34 > 56",
-                expected: "(> (> 12 34) 56)",
+                expected: "(> (> 12.0 34.0) 56.0)",
             },
             TestCase {
                 name: "Left Associativity: Term",
                 input: "12 * 34 * 56",
-                expected: "(* (* 12 34) 56)",
+                expected: "(* (* 12.0 34.0) 56.0)",
             },
             TestCase {
                 name: "Left Associativity: Factor",
                 input: "12 / 34 / 56",
-                expected: "(/ (/ 12 34) 56)",
+                expected: "(/ (/ 12.0 34.0) 56.0)",
             },
             TestCase {
                 name: "Right Associativity: Unary",
                 input: "!-12",
-                expected: "(! (- 12))",
+                expected: "(! (- 12.0))",
+            },
+            TestCase {
+                name: "Precedence: Equality > And > Or",
+                input: "a or b and c == d or e",
+                expected: "(or (or a (and b (== c d))) e)",
             },
         ];
 
@@ -0,0 +1,169 @@
+use super::expr::{BlockBody, Expr};
+use super::stmt::Stmt;
+
+/// Rewrites a syntax tree into a new one. Every method defaults to rebuilding the node from its
+/// transformed children, so a transform only needs to override the nodes it rewrites and can
+/// call `walk_stmt` or `walk_expr` to recurse into the nodes it leaves as they are.
+///
+/// Nodes created by a transform should use `Token::synthesize` on the token they are derived
+/// from, so that any error caused by them is reported at the location the user wrote.
+pub trait Transformer {
+    fn transform_stmts(&mut self, stmts: Vec<Stmt>) -> Vec<Stmt> {
+        stmts
+            .into_iter()
+            .map(|stmt| self.transform_stmt(stmt))
+            .collect()
+    }
+
+    fn transform_stmt(&mut self, stmt: Stmt) -> Stmt {
+        walk_stmt(self, stmt)
+    }
+
+    fn transform_expr(&mut self, expr: Expr) -> Expr {
+        walk_expr(self, expr)
+    }
+}
+
+/// Rebuilds the statement from its children transformed with the provided transformer.
+pub fn walk_stmt<T: Transformer + ?Sized>(transformer: &mut T, stmt: Stmt) -> Stmt {
+    let mut expr = |expr| transformer.transform_expr(expr);
+
+    match stmt {
+        Stmt::Expression(e) => Stmt::Expression(expr(e)),
+        Stmt::Print(exprs) => Stmt::Print(exprs.into_iter().map(expr).collect()),
+        Stmt::Assert(keyword, condition, text, message) => {
+            Stmt::Assert(keyword, expr(condition), text, message.map(expr))
+        }
+        Stmt::Var(name, initializer) => Stmt::Var(name, initializer.map(expr)),
+        Stmt::Const(name, initializer) => Stmt::Const(name, expr(initializer)),
+        Stmt::VarTuple(paren, names, initializer) => {
+            Stmt::VarTuple(paren, names, expr(initializer))
+        }
+        Stmt::Block(stmts) => Stmt::Block(transformer.transform_stmts(stmts)),
+        Stmt::If(condition, then_branch, else_branch) => Stmt::If(
+            transformer.transform_expr(condition),
+            transform_boxed_stmt(transformer, then_branch),
+            else_branch.map(|stmt| transform_boxed_stmt(transformer, stmt)),
+        ),
+        Stmt::While(condition, body, increment, label) => Stmt::While(
+            transformer.transform_expr(condition),
+            transform_boxed_stmt(transformer, body),
+            increment.map(|expr| transformer.transform_expr(expr)),
+            label,
+        ),
+        Stmt::For(keyword, initializer, condition, increment, body, label) => Stmt::For(
+            keyword,
+            initializer.map(|stmt| transform_boxed_stmt(transformer, stmt)),
+            condition.map(|expr| transformer.transform_expr(expr)),
+            increment.map(|expr| transformer.transform_expr(expr)),
+            transform_boxed_stmt(transformer, body),
+            label,
+        ),
+        Stmt::DoWhile(keyword, body, condition, label) => Stmt::DoWhile(
+            keyword,
+            transform_boxed_stmt(transformer, body),
+            transformer.transform_expr(condition),
+            label,
+        ),
+        Stmt::ForIn(name, keyword, iterable, body, label) => Stmt::ForIn(
+            name,
+            keyword,
+            transformer.transform_expr(iterable),
+            transform_boxed_stmt(transformer, body),
+            label,
+        ),
+        Stmt::Switch(keyword, subject, cases, default) => Stmt::Switch(
+            keyword,
+            transformer.transform_expr(subject),
+            cases
+                .into_iter()
+                .map(|(patterns, body)| {
+                    let patterns = patterns
+                        .into_iter()
+                        .map(|pattern| transformer.transform_expr(pattern))
+                        .collect();
+                    (patterns, transformer.transform_stmt(body))
+                })
+                .collect(),
+            default.map(|stmt| transform_boxed_stmt(transformer, stmt)),
+        ),
+        Stmt::Throw(keyword, value) => Stmt::Throw(keyword, expr(value)),
+        Stmt::Try(body, catch, finally) => Stmt::Try(
+            transformer.transform_stmts(body),
+            catch.map(|(name, handler)| (name, transformer.transform_stmts(handler))),
+            finally.map(|finally| transformer.transform_stmts(finally)),
+        ),
+        Stmt::Break(keyword, label) => Stmt::Break(keyword, label),
+        Stmt::Continue(keyword, label) => Stmt::Continue(keyword, label),
+    }
+}
+
+/// Rebuilds the expression from its children transformed with the provided transformer.
+pub fn walk_expr<T: Transformer + ?Sized>(transformer: &mut T, expr: Expr) -> Expr {
+    let mut boxed = |expr: Box<Expr>| Box::new(transformer.transform_expr(*expr));
+
+    match expr {
+        Expr::Unary(op, e) => Expr::Unary(op, boxed(e)),
+        Expr::Binary(left, op, right) => Expr::Binary(boxed(left), op, boxed(right)),
+        Expr::Grouping(e) => Expr::Grouping(boxed(e)),
+        Expr::Literal(value) => Expr::Literal(value),
+        Expr::Variable(name) => Expr::Variable(name),
+        Expr::Assign(name, value) => Expr::Assign(name, boxed(value)),
+        Expr::Call(callee, paren, args) => {
+            let callee = boxed(callee);
+            let args = args
+                .into_iter()
+                .map(|arg| transformer.transform_expr(arg))
+                .collect();
+            Expr::Call(callee, paren, args)
+        }
+        Expr::Logical(left, op, right) => Expr::Logical(boxed(left), op, boxed(right)),
+        Expr::List(bracket, elements) => Expr::List(
+            bracket,
+            elements
+                .into_iter()
+                .map(|element| transformer.transform_expr(element))
+                .collect(),
+        ),
+        Expr::Index(object, bracket, index) => Expr::Index(boxed(object), bracket, boxed(index)),
+        Expr::IndexSet(object, bracket, index, value) => {
+            let object = boxed(object);
+            let index = boxed(index);
+            Expr::IndexSet(object, bracket, index, boxed(value))
+        }
+        Expr::Map(brace, entries) => Expr::Map(
+            brace,
+            entries
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = transformer.transform_expr(key);
+                    (key, transformer.transform_expr(value))
+                })
+                .collect(),
+        ),
+        Expr::Tuple(paren, elements) => Expr::Tuple(
+            paren,
+            elements
+                .into_iter()
+                .map(|element| transformer.transform_expr(element))
+                .collect(),
+        ),
+        Expr::Get(object, name) => Expr::Get(boxed(object), name),
+        Expr::Spread(ellipsis, e) => Expr::Spread(ellipsis, boxed(e)),
+        // Lowered statements are no longer part of the surface syntax tree, so only their value is
+        // transformed
+        Expr::Block(brace, BlockBody::Surface(stmts), value) => {
+            let stmts = transformer.transform_stmts(stmts);
+            let value = value.map(|value| Box::new(transformer.transform_expr(*value)));
+            Expr::Block(brace, BlockBody::Surface(stmts), value)
+        }
+        Expr::Block(brace, body, value) => Expr::Block(brace, body, value.map(boxed)),
+    }
+}
+
+fn transform_boxed_stmt<T: Transformer + ?Sized>(
+    transformer: &mut T,
+    stmt: Box<Stmt>,
+) -> Box<Stmt> {
+    Box::new(transformer.transform_stmt(*stmt))
+}
@@ -0,0 +1,681 @@
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use anyhow::Error;
+
+use super::error::{Located, RuntimeError, ValueError};
+use super::interpreter::Interpreter;
+use super::ordered_map::OrderedMap;
+
+/// A mutable list of values, shared by all the values that refer to it.
+pub type List = Rc<RefCell<Vec<Value>>>;
+
+/// A mutable map from keys to values, shared by all the values that refer to it. The entries are
+/// kept in the order their keys were first inserted.
+pub type Map = Rc<RefCell<OrderedMap<MapKey, Value>>>;
+
+/// A fixed sequence of values. Tuples can not be changed once created, so unlike lists they are
+/// compared by their elements, and can be used as keys of maps when all their elements can.
+pub type Tuple = Rc<Vec<Value>>;
+
+/// Numbers are either integers or floats. Integers are produced by integer literals, and stay
+/// integers through the arithmetic operators (except `/`) as long as the result fits. The rest of
+/// the operations work on both kinds alike, so that an integer equals the float of the same value.
+#[derive(Clone)]
+pub enum Value {
+    Number(f64),
+    Integer(i64),
+    String(String),
+    Boolean(bool),
+    Nil,
+    NativeFunction(NativeFunction),
+    List(List),
+    Map(Map),
+    Tuple(Tuple),
+}
+
+/// The names of the kinds of values, which are the types that can follow `is`.
+pub const KINDS: &[&str] = &[
+    "Boolean", "Function", "List", "Map", "Nil", "Number", "String", "Tuple",
+];
+
+/// The values that can be used as the keys of a map. Integral floats are stored as integers, so
+/// that `1`, `1.0` and `-0` and `0` refer to the same entry, and other floats by their bits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Integer(i64),
+    Number(u64),
+    String(String),
+    Boolean(bool),
+    Nil,
+    Tuple(Vec<MapKey>),
+}
+
+impl MapKey {
+    /// Returns the key for the value, or `None` if the value can not be used as a key.
+    /// Containers and functions are not valid keys, and neither is `NaN` as it is not equal
+    /// to itself.
+    pub fn from_value(value: &Value) -> Option<MapKey> {
+        match value {
+            Value::Number(num) if num.is_nan() => None,
+            Value::Number(num) => match float_to_integer(*num) {
+                Some(integer) => Some(MapKey::Integer(integer)),
+                None => Some(MapKey::Number(num.to_bits())),
+            },
+            Value::Integer(integer) => Some(MapKey::Integer(*integer)),
+            Value::String(str) => Some(MapKey::String(str.clone())),
+            Value::Boolean(b) => Some(MapKey::Boolean(*b)),
+            Value::Nil => Some(MapKey::Nil),
+            Value::Tuple(tuple) => tuple
+                .iter()
+                .map(MapKey::from_value)
+                .collect::<Option<_>>()
+                .map(MapKey::Tuple),
+            Value::NativeFunction(_) | Value::List(_) | Value::Map(_) => None,
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            MapKey::Integer(integer) => Value::Integer(*integer),
+            MapKey::Number(bits) => Value::Number(f64::from_bits(*bits)),
+            MapKey::String(str) => Value::String(str.clone()),
+            MapKey::Boolean(b) => Value::Boolean(*b),
+            MapKey::Nil => Value::Nil,
+            MapKey::Tuple(keys) => {
+                Value::Tuple(Rc::new(keys.iter().map(MapKey::to_value).collect()))
+            }
+        }
+    }
+}
+
+/// The number of nested containers that are formatted before the rest is elided with `...`.
+const MAX_FORMAT_DEPTH: usize = 16;
+
+/// The representations a value can be formatted in.
+#[derive(Clone, Copy)]
+enum Style {
+    /// The user facing representation used by `print`.
+    Display,
+    /// The developer oriented representation used by `debug` and the error messages.
+    Debug,
+}
+
+impl Value {
+    /// Returns the number held by the value as a float, or `None` if it is not a number.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(num) => Some(*num),
+            Value::Integer(integer) => Some(*integer as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the number held by the value, or a type mismatch error for the operator.
+    pub fn expect_number(&self, operator: &impl Located) -> Result<f64, Error> {
+        self.as_number()
+            .ok_or_else(|| RuntimeError::type_mismatch("a number", vec![self.clone()], operator))
+    }
+
+    /// Converts the value into an index or a count, which must be a non negative integer that fits
+    /// in a `usize`. This is the only place where numbers should be converted into sizes, so that
+    /// the invalid values are always rejected.
+    pub fn as_index(&self) -> Result<usize, ValueError> {
+        let num = self.as_number().ok_or(ValueError::NotANumber)?;
+
+        let problem = if let Value::Integer(integer) = self {
+            match usize::try_from(*integer) {
+                Ok(index) => return Ok(index),
+                Err(_) => "is negative",
+            }
+        } else if num.is_nan() {
+            "is not a number"
+        } else if num.is_infinite() {
+            "is infinite"
+        } else if num < 0.0 {
+            "is negative"
+        } else if num.fract() != 0.0 {
+            "is not an integer"
+        } else if num >= usize::MAX as f64 {
+            "is too large"
+        } else {
+            return Ok(num as usize);
+        };
+
+        Err(ValueError::InvalidIndex(problem))
+    }
+
+    /// Converts the value into an index or a count like `as_index`, with the error reported at
+    /// the provided token. The role describes what the value is used as in the error messages,
+    /// such as "count".
+    pub fn expect_index(&self, token: &impl Located, role: &str) -> Result<usize, Error> {
+        self.as_index().map_err(|err| err.at(self, token, role))
+    }
+
+    /// Returns the value as something that can be called, or `None` if it can not be.
+    pub fn as_callable(&self) -> Option<&dyn Callable> {
+        match self {
+            Value::NativeFunction(native) => Some(native),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the kind of the value, as tested by the `is` operator and returned in
+    /// lowercase by `typeof`. Integers and floats are both numbers.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Number(_) | Value::Integer(_) => "Number",
+            Value::String(_) => "String",
+            Value::Boolean(_) => "Boolean",
+            Value::Nil => "Nil",
+            Value::NativeFunction(_) => "Function",
+            Value::List(_) => "List",
+            Value::Map(_) => "Map",
+            Value::Tuple(_) => "Tuple",
+        }
+    }
+
+    /// Returns the truthiness of the value. Only `false` and `nil` are falsy.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Boolean(false) | Value::Nil)
+    }
+
+    /// Returns a developer oriented representation of the value, which unlike the user facing
+    /// representation used by `print`, tags the value with its type and quotes strings.
+    /// This is the representation shared by `debug` and the runtime error messages.
+    pub fn debug_string(&self) -> String {
+        self.format(Style::Debug, &mut Vec::new())
+    }
+
+    /// Formats the value in the provided style. The containers that enclose the value are
+    /// tracked, so that a container that contains itself is elided with `...` instead of
+    /// recursing forever. Deeply nested containers are elided in the same way.
+    fn format(&self, style: Style, enclosing: &mut Vec<*const ()>) -> String {
+        match (self, style) {
+            (Value::Number(num), Style::Display) => format_number(*num),
+            (Value::Number(num), Style::Debug) => format!("number({})", format_number(*num)),
+            (Value::Integer(integer), Style::Display) => integer.to_string(),
+            (Value::Integer(integer), Style::Debug) => format!("integer({})", integer),
+
+            // Strings are quoted inside of containers, so that their elements can be told apart
+            (Value::String(str), Style::Display) if enclosing.is_empty() => str.clone(),
+            (Value::String(str), Style::Display) => format!("{:?}", str),
+            (Value::String(str), Style::Debug) => format!("string({:?})", str),
+
+            (Value::Boolean(b), Style::Display) => b.to_string(),
+            (Value::Boolean(b), Style::Debug) => format!("boolean({})", b),
+
+            (Value::Nil, _) => "nil".to_string(),
+
+            (Value::NativeFunction(native), Style::Display) => {
+                format!("<native fn {}>", native.name)
+            }
+            (Value::NativeFunction(native), Style::Debug) => match native.arity {
+                Some(arity) => format!("native fn {}(arity {})", native.name, arity),
+                None => format!("native fn {}(variadic)", native.name),
+            },
+
+            (Value::List(list), _) => {
+                let prefix = match style {
+                    Style::Display => "",
+                    Style::Debug => "list",
+                };
+
+                format_container(Rc::as_ptr(list) as *const (), enclosing, |enclosing| {
+                    let elements = list
+                        .borrow()
+                        .iter()
+                        .map(|element| element.format(style, enclosing))
+                        .collect::<Vec<_>>();
+                    format!("{}[{}]", prefix, elements.join(", "))
+                })
+                .unwrap_or_else(|| format!("{}[...]", prefix))
+            }
+
+            (Value::Map(map), _) => {
+                let prefix = match style {
+                    Style::Display => "",
+                    Style::Debug => "map",
+                };
+
+                format_container(Rc::as_ptr(map) as *const (), enclosing, |enclosing| {
+                    let entries = map
+                        .borrow()
+                        .iter()
+                        .map(|(key, value)| {
+                            format!(
+                                "{}: {}",
+                                key.to_value().format(style, enclosing),
+                                value.format(style, enclosing)
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    format!("{}{{{}}}", prefix, entries.join(", "))
+                })
+                .unwrap_or_else(|| format!("{}{{...}}", prefix))
+            }
+
+            (Value::Tuple(tuple), _) => {
+                let prefix = match style {
+                    Style::Display => "",
+                    Style::Debug => "tuple",
+                };
+
+                format_container(Rc::as_ptr(tuple) as *const (), enclosing, |enclosing| {
+                    let elements = tuple
+                        .iter()
+                        .map(|element| element.format(style, enclosing))
+                        .collect::<Vec<_>>();
+
+                    // A tuple of one element keeps its trailing comma, as in its literal
+                    match elements.as_slice() {
+                        [element] => format!("{}({},)", prefix, element),
+                        elements => format!("{}({})", prefix, elements.join(", ")),
+                    }
+                })
+                .unwrap_or_else(|| format!("{}(...)", prefix))
+            }
+        }
+    }
+}
+
+/// Formats a container with the provided function, while it is tracked as enclosing the values
+/// formatted by the function. Returns `None` if the container should be elided, as it already
+/// encloses the value being formatted or the nesting is too deep.
+fn format_container(
+    ptr: *const (),
+    enclosing: &mut Vec<*const ()>,
+    format: impl FnOnce(&mut Vec<*const ()>) -> String,
+) -> Option<String> {
+    if enclosing.contains(&ptr) || enclosing.len() >= MAX_FORMAT_DEPTH {
+        return None;
+    }
+
+    enclosing.push(ptr);
+    let formatted = format(enclosing);
+    enclosing.pop();
+
+    Some(formatted)
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(Style::Display, &mut Vec::new()))
+    }
+}
+
+impl std::fmt::Debug for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.debug_string())
+    }
+}
+
+/// Lists and maps are compared by identity like the other objects in Lox, while all the other
+/// values are compared by their contents, including the elements of tuples. Values of different
+/// types are never equal, except for integers and floats with the same value, as both are Lox
+/// numbers.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Number(left), Value::Number(right)) => left == right,
+            (Value::Integer(left), Value::Integer(right)) => left == right,
+            (Value::Integer(integer), Value::Number(num))
+            | (Value::Number(num), Value::Integer(integer)) => {
+                float_to_integer(*num) == Some(*integer)
+            }
+            (Value::String(left), Value::String(right)) => left == right,
+            (Value::Boolean(left), Value::Boolean(right)) => left == right,
+            (Value::Nil, Value::Nil) => true,
+            (Value::NativeFunction(left), Value::NativeFunction(right)) => left == right,
+            (Value::List(left), Value::List(right)) => Rc::ptr_eq(left, right),
+            (Value::Map(left), Value::Map(right)) => Rc::ptr_eq(left, right),
+            (Value::Tuple(left), Value::Tuple(right)) => left == right,
+            // The types are listed rather than matched with a wildcard, so that a new type of
+            // value can not be added without deciding how it compares to itself
+            (
+                Value::Number(_)
+                | Value::Integer(_)
+                | Value::String(_)
+                | Value::Boolean(_)
+                | Value::Nil
+                | Value::NativeFunction(_)
+                | Value::List(_)
+                | Value::Map(_)
+                | Value::Tuple(_),
+                _,
+            ) => false,
+        }
+    }
+}
+
+/// Returns the integer with the exact value of the float, or `None` if the float is not integral
+/// or out of the range of integers. Both zeros are converted to `0`.
+pub fn float_to_integer(num: f64) -> Option<i64> {
+    // The bounds are powers of two, so they are exact as floats
+    match num.fract() == 0.0 && num >= i64::MIN as f64 && num < -(i64::MIN as f64) {
+        true => Some(num as i64),
+        false => None,
+    }
+}
+
+/// Formats a number the way the runtime prints it, which follows the Lox book: integral values
+/// are printed without a decimal part (`5`, not `5.0`), and the special values are spelled out.
+/// Number literals in the token stream are formatted differently, by `format_literal`.
+pub fn format_number(num: f64) -> String {
+    if num.is_nan() {
+        "NaN".to_string()
+    } else if num.is_infinite() {
+        match num.is_sign_positive() {
+            true => "Infinity".to_string(),
+            false => "-Infinity".to_string(),
+        }
+    } else {
+        format!("{}", num)
+    }
+}
+
+/// A value that can be called from Lox code. The interpreter checks the number of arguments
+/// against the arity and tracks the call in the call stack, so implementations only compute the
+/// result.
+pub trait Callable {
+    /// The name of the function, as shown in the call stack and in the errors of its arguments.
+    fn name(&self) -> &'static str;
+
+    /// The number of arguments the function expects, or `None` if it is variadic.
+    fn arity(&self) -> Option<usize>;
+
+    fn call(&self, interpreter: &Interpreter, args: &[Value]) -> Result<Value, Error>;
+}
+
+/// The signature of the Rust functions that back the natives exposed to Lox programs.
+pub type NativeFn = fn(&Interpreter, &[Value]) -> Result<Value, Error>;
+
+/// A function implemented in Rust that can be called from Lox code.
+#[derive(Clone)]
+pub struct NativeFunction {
+    pub name: &'static str,
+    /// The number of arguments the function expects, or `None` if it is variadic.
+    pub arity: Option<usize>,
+    pub func: NativeFn,
+}
+
+impl NativeFunction {
+    pub fn new(name: &'static str, arity: Option<usize>, func: NativeFn) -> Self {
+        NativeFunction { name, arity, func }
+    }
+}
+
+impl Callable for NativeFunction {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn arity(&self) -> Option<usize> {
+        self.arity
+    }
+
+    fn call(&self, interpreter: &Interpreter, args: &[Value]) -> Result<Value, Error> {
+        (self.func)(interpreter, args)
+    }
+}
+
+/// Natives are identified by their name, as the same function is never registered twice.
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl std::fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::token::Token;
+
+    #[test]
+    fn test_expect_index() {
+        let token = Token::new(crate::lexer::types::TokenType::Star, "*", 3, 1);
+        let test_cases = vec![
+            (Value::Number(0.0), Ok(0)),
+            (Value::Number(42.0), Ok(42)),
+            (Value::Number(-0.0), Ok(0)),
+            (Value::Integer(7), Ok(7)),
+            (
+                Value::Integer(-1),
+                Err("[line 3] Invalid count for '*': integer(-1) is negative."),
+            ),
+            (
+                Value::Number(-1.0),
+                Err("[line 3] Invalid count for '*': number(-1) is negative."),
+            ),
+            (
+                Value::Number(1.5),
+                Err("[line 3] Invalid count for '*': number(1.5) is not an integer."),
+            ),
+            (
+                Value::Number(f64::NAN),
+                Err("[line 3] Invalid count for '*': number(NaN) is not a number."),
+            ),
+            (
+                Value::Number(f64::INFINITY),
+                Err("[line 3] Invalid count for '*': number(Infinity) is infinite."),
+            ),
+            (
+                Value::Number(1e30),
+                Err("[line 3] Invalid count for '*': number(1000000000000000000000000000000) is too large."),
+            ),
+            (
+                Value::Nil,
+                Err("[line 3] Invalid operand for '*': expected a number, got nil."),
+            ),
+        ];
+
+        for (value, expected) in test_cases {
+            let result = value
+                .expect_index(&token, "count")
+                .map_err(|err| err.to_string());
+            assert_eq!(
+                result,
+                expected.map_err(|err| err.to_string()),
+                "{:?}",
+                value
+            );
+        }
+
+        // The errors of the conversion only describe the problem, which is rendered on reporting
+        assert_eq!(Value::Integer(2).as_index(), Ok(2));
+        assert_eq!(Value::Nil.as_index(), Err(ValueError::NotANumber));
+        assert_eq!(
+            Value::Number(-1.0).as_index(),
+            Err(ValueError::InvalidIndex("is negative"))
+        );
+        let err = Value::Integer(-1)
+            .expect_index(&token, "count")
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RuntimeError>(),
+            Some(RuntimeError::InvalidIndex {
+                problem: "is negative",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_equality() {
+        fn clock(_: &Interpreter, _: &[Value]) -> Result<Value, Error> {
+            Ok(Value::Nil)
+        }
+
+        let list = Rc::new(RefCell::new(vec![]));
+        let map = Rc::new(RefCell::new(OrderedMap::new()));
+        let values = vec![
+            Value::Nil,
+            Value::Boolean(false),
+            Value::Boolean(true),
+            Value::Integer(0),
+            Value::Integer(1),
+            Value::Number(1.5),
+            Value::String("".to_string()),
+            Value::String("1".to_string()),
+            Value::NativeFunction(NativeFunction::new("clock", Some(0), clock)),
+            Value::List(list.clone()),
+            Value::Map(map.clone()),
+        ];
+
+        // Every value is only equal to itself, whatever the pair of types
+        for (i, left) in values.iter().enumerate() {
+            for (j, right) in values.iter().enumerate() {
+                assert_eq!(left == right, i == j, "{:?} == {:?}", left, right);
+                assert_eq!(left != right, i != j, "{:?} != {:?}", left, right);
+            }
+        }
+
+        let test_cases = vec![
+            (Value::Integer(1), Value::Number(1.0), true),
+            (Value::Number(-0.0), Value::Integer(0), true),
+            (Value::Number(f64::NAN), Value::Number(f64::NAN), false),
+            (
+                Value::Integer(i64::MAX),
+                Value::Number(i64::MAX as f64),
+                false,
+            ),
+            (Value::Nil, Value::Boolean(false), false),
+            (Value::Integer(0), Value::Boolean(false), false),
+            (Value::String("nil".to_string()), Value::Nil, false),
+            (
+                Value::List(list.clone()),
+                Value::List(Rc::new(RefCell::new(vec![]))),
+                false,
+            ),
+            (Value::Map(map.clone()), Value::List(list.clone()), false),
+        ];
+
+        for (left, right, expected) in test_cases {
+            assert_eq!(left == right, expected, "{:?} == {:?}", left, right);
+            assert_eq!(right == left, expected, "{:?} == {:?}", right, left);
+        }
+    }
+
+    #[test]
+    fn test_format_list() {
+        let list = Rc::new(RefCell::new(vec![
+            Value::Number(1.0),
+            Value::String("a".to_string()),
+            Value::List(Rc::new(RefCell::new(vec![]))),
+        ]));
+        let value = Value::List(list.clone());
+        assert_eq!(value.to_string(), "[1, \"a\", []]");
+        assert_eq!(
+            value.debug_string(),
+            "list[number(1), string(\"a\"), list[]]"
+        );
+
+        // A list that contains itself
+        list.borrow_mut().push(value.clone());
+        assert_eq!(value.to_string(), "[1, \"a\", [], [...]]");
+        assert_eq!(
+            value.debug_string(),
+            "list[number(1), string(\"a\"), list[], list[...]]"
+        );
+
+        // Deeply nested lists
+        let mut nested = Value::Nil;
+        for _ in 0..100 {
+            nested = Value::List(Rc::new(RefCell::new(vec![nested])));
+        }
+        let expected = format!("{}[...]{}", "[".repeat(16), "]".repeat(16));
+        assert_eq!(nested.to_string(), expected);
+    }
+
+    #[test]
+    fn test_format_map() {
+        let map = Rc::new(RefCell::new(OrderedMap::new()));
+        let value = Value::Map(map.clone());
+        assert_eq!(value.to_string(), "{}");
+
+        map.borrow_mut()
+            .insert(MapKey::String("a".to_string()), Value::Number(1.0));
+        assert_eq!(value.to_string(), "{\"a\": 1}");
+        assert_eq!(value.debug_string(), "map{string(\"a\"): number(1)}");
+
+        // A map that contains itself
+        map.borrow_mut()
+            .insert(MapKey::String("a".to_string()), value.clone());
+        assert_eq!(value.to_string(), "{\"a\": {...}}");
+        assert_eq!(value.debug_string(), "map{string(\"a\"): map{...}}");
+    }
+
+    #[test]
+    fn test_map_keys() {
+        assert_eq!(
+            MapKey::from_value(&Value::Number(-0.0)),
+            MapKey::from_value(&Value::Number(0.0))
+        );
+        assert_eq!(MapKey::from_value(&Value::Number(f64::NAN)), None);
+        assert_eq!(
+            MapKey::from_value(&Value::List(Rc::new(RefCell::new(vec![])))),
+            None
+        );
+        assert_eq!(
+            MapKey::from_value(&Value::Number(1.5)).unwrap().to_value(),
+            Value::Number(1.5)
+        );
+
+        // Integers and integral floats are the same key
+        assert_eq!(
+            MapKey::from_value(&Value::Integer(3)),
+            MapKey::from_value(&Value::Number(3.0))
+        );
+        assert_eq!(
+            MapKey::from_value(&Value::Number(-0.0)),
+            Some(MapKey::Integer(0))
+        );
+        assert_eq!(
+            MapKey::from_value(&Value::Number(1e300)),
+            Some(MapKey::Number(1e300f64.to_bits()))
+        );
+    }
+
+    #[test]
+    fn test_integer_equality() {
+        assert_eq!(Value::Integer(1), Value::Number(1.0));
+        assert_eq!(Value::Number(-0.0), Value::Integer(0));
+        assert_ne!(Value::Integer(1), Value::Number(1.5));
+        assert_ne!(Value::Integer(1), Value::String("1".to_string()));
+
+        // 2^53 + 1 is rounded when converted to a float, but is not equal to the rounded value
+        assert_ne!(
+            Value::Integer(9007199254740993),
+            Value::Number(9007199254740992.0)
+        );
+        assert_ne!(Value::Integer(i64::MAX), Value::Number(i64::MAX as f64));
+    }
+
+    #[test]
+    fn test_format_number() {
+        let test_cases = vec![
+            (5.0, "5"),
+            (0.0, "0"),
+            (-0.0, "-0"),
+            (-3.0, "-3"),
+            (12.5, "12.5"),
+            (0.1, "0.1"),
+            (1e20, "100000000000000000000"),
+            (f64::NAN, "NaN"),
+            (f64::INFINITY, "Infinity"),
+            (f64::NEG_INFINITY, "-Infinity"),
+        ];
+
+        for (num, expected) in test_cases {
+            assert_eq!(format_number(num), expected, "{}", num);
+            assert_eq!(Value::Number(num).to_string(), expected, "{}", num);
+        }
+    }
+}
@@ -0,0 +1,545 @@
+use std::fs;
+use std::io::sink;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::ast::hoist::Hoister;
+use crate::ast::interpreter::Interpreter;
+use crate::ast::syntax_tree::SyntaxTree;
+use crate::config::Config;
+use crate::lexer::lexer::Lexer;
+use crate::optimize;
+
+/// The directory where the results of the benchmarks are saved, relative to the working directory.
+pub const BENCH_DIR: &str = ".lox/bench";
+
+/// The p-value under which a change between two runs is reported as significant.
+const SIGNIFICANCE: f64 = 0.05;
+
+/// The number of times each program is run when it is not provided.
+const DEFAULT_ITERATIONS: usize = 10;
+
+/// What the `bench` command was asked to do by its arguments.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Measures the programs, and saves the results under the name if there is one.
+    Measure {
+        programs: Vec<String>,
+        iterations: usize,
+        save: Option<String>,
+        config: Config,
+    },
+    /// Compares two saved runs, the second one against the first one.
+    Compare(String, String),
+}
+
+impl Command {
+    /// Parses the arguments of the command, which are either `--compare <a> <b>`, or the programs
+    /// to measure along with `--save <name>`, `--iterations <n>` and the language flags.
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut args = args.iter();
+        let mut programs = Vec::new();
+        let mut flags = Vec::new();
+        let mut iterations = DEFAULT_ITERATIONS;
+        let mut save = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--compare" => {
+                    return match (args.next(), args.next(), args.next()) {
+                        (Some(a), Some(b), None) if programs.is_empty() => {
+                            Ok(Command::Compare(a.clone(), b.clone()))
+                        }
+                        _ => Err("Usage: bench --compare <baseline> <name>".to_string()),
+                    }
+                }
+                "--save" => match args.next() {
+                    Some(name) => save = Some(name.clone()),
+                    None => return Err("Expected a name after --save".to_string()),
+                },
+                "--iterations" => match args.next().and_then(|count| count.parse().ok()) {
+                    Some(count) if count > 0 => iterations = count,
+                    _ => return Err("Expected a positive count after --iterations".to_string()),
+                },
+                flag if flag.starts_with("--") => flags.push(arg.clone()),
+                _ => programs.push(arg.clone()),
+            }
+        }
+
+        if programs.is_empty() {
+            return Err(
+                "Usage: bench <files...> [--save <name>] [--iterations <n>] [flags]".to_string(),
+            );
+        }
+
+        Ok(Command::Measure {
+            programs,
+            iterations,
+            save,
+            config: Config::from_flags(&flags)?,
+        })
+    }
+
+    /// Runs the command, and returns the report to print.
+    pub fn run(&self, dir: &Path) -> Result<String, String> {
+        match self {
+            Command::Measure {
+                programs,
+                iterations,
+                save: name,
+                config,
+            } => {
+                let mut measurements = Vec::new();
+                for program in programs {
+                    let source = fs::read_to_string(program)
+                        .map_err(|err| format!("Failed to read file {}: {}", program, err))?;
+                    measurements.extend(measure(program, &source, config, *iterations)?);
+                }
+
+                if let Some(name) = name {
+                    save(dir, name, &measurements)?;
+                }
+                Ok(format_measurements(&measurements))
+            }
+
+            Command::Compare(baseline, current) => Ok(format_comparisons(&compare(
+                &load(dir, baseline)?,
+                &load(dir, current)?,
+            ))),
+        }
+    }
+}
+
+/// The durations in milliseconds of every iteration of a phase of a program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub program: String,
+    pub phase: String,
+    pub samples: Vec<f64>,
+}
+
+/// The change of the mean duration of a phase of a program between two runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub program: String,
+    pub phase: String,
+    pub baseline: f64,
+    pub current: f64,
+    /// The probability of observing a difference at least as large if the means were equal, or
+    /// `NaN` if either run has too few samples to tell.
+    pub p_value: f64,
+}
+
+impl Comparison {
+    /// Returns the relative change of the mean duration, where `0.1` is 10% slower.
+    pub fn change(&self) -> f64 {
+        (self.current - self.baseline) / self.baseline
+    }
+
+    pub fn is_significant(&self) -> bool {
+        self.p_value < SIGNIFICANCE
+    }
+}
+
+/// Runs the program the provided number of times, and measures each of its phases: lexing,
+/// parsing, the phases of `optimize` that prepare it like for the `run` command, and running it
+/// with its output discarded. Programs with syntax or runtime errors can not be measured.
+pub fn measure(
+    program: &str,
+    source: &str,
+    config: &Config,
+    iterations: usize,
+) -> Result<Vec<Measurement>, String> {
+    let phases = ["lex", "parse", "fold", "lower", "hoist", "run"];
+    let mut measurements = phases.map(|phase| Measurement {
+        program: program.to_string(),
+        phase: phase.to_string(),
+        samples: Vec::with_capacity(iterations),
+    });
+
+    for _ in 0..iterations {
+        // The time at the start, and at the end of each phase
+        let mut times = vec![Instant::now()];
+        let tokens = Lexer::with_config(source, config.clone()).get_tokens();
+        times.push(Instant::now());
+        let stmts = SyntaxTree::new(tokens)
+            .parse()
+            .map_err(|err| format!("{}: [line {}] {}", program, err.line, err.message))?;
+        times.push(Instant::now());
+        let stmts = optimize(
+            stmts,
+            config,
+            &mut Hoister::new(),
+            false,
+            false,
+            &mut |_| times.push(Instant::now()),
+        );
+        Interpreter::with_output(config.clone(), Box::new(sink()))
+            .interpret(&stmts)
+            .map_err(|err| format!("{}: {}", program, err))?;
+        times.push(Instant::now());
+
+        for (measurement, bounds) in measurements.iter_mut().zip(times.windows(2)) {
+            let duration = bounds[1].duration_since(bounds[0]);
+            measurement.samples.push(duration.as_secs_f64() * 1000.0);
+        }
+    }
+
+    Ok(measurements.to_vec())
+}
+
+/// Saves the measurements under the name in the directory, with a line per measurement made of
+/// the program, the phase and the comma separated samples, separated by tabs.
+pub fn save(dir: &Path, name: &str, measurements: &[Measurement]) -> Result<(), String> {
+    let contents = measurements
+        .iter()
+        .map(|measurement| {
+            let samples = measurement
+                .samples
+                .iter()
+                .map(|sample| sample.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            format!(
+                "{}\t{}\t{}\n",
+                measurement.program, measurement.phase, samples
+            )
+        })
+        .collect::<String>();
+
+    fs::create_dir_all(dir)
+        .and_then(|_| fs::write(dir.join(name), contents))
+        .map_err(|err| format!("Failed to save the benchmark '{}': {}", name, err))
+}
+
+/// Loads the measurements saved under the name in the directory.
+pub fn load(dir: &Path, name: &str) -> Result<Vec<Measurement>, String> {
+    let contents = fs::read_to_string(dir.join(name))
+        .map_err(|err| format!("Failed to load the benchmark '{}': {}", name, err))?;
+
+    contents
+        .lines()
+        .map(|line| {
+            let malformed = || format!("Malformed line in the benchmark '{}': {}", name, line);
+            let mut fields = line.split('\t');
+            let (program, phase, samples) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(program), Some(phase), Some(samples)) => (program, phase, samples),
+                _ => return Err(malformed()),
+            };
+
+            Ok(Measurement {
+                program: program.to_string(),
+                phase: phase.to_string(),
+                samples: samples
+                    .split(',')
+                    .map(|sample| sample.parse::<f64>().map_err(|_| malformed()))
+                    .collect::<Result<_, _>>()?,
+            })
+        })
+        .collect()
+}
+
+/// Compares the phases of the programs measured in both runs, in the order of the baseline.
+pub fn compare(baseline: &[Measurement], current: &[Measurement]) -> Vec<Comparison> {
+    baseline
+        .iter()
+        .filter_map(|before| {
+            let after = current
+                .iter()
+                .find(|after| after.program == before.program && after.phase == before.phase)?;
+
+            Some(Comparison {
+                program: before.program.clone(),
+                phase: before.phase.clone(),
+                baseline: mean(&before.samples),
+                current: mean(&after.samples),
+                p_value: welch_t_test(&before.samples, &after.samples),
+            })
+        })
+        .collect()
+}
+
+/// Formats the mean and standard deviation of every measurement, one per line.
+pub fn format_measurements(measurements: &[Measurement]) -> String {
+    measurements
+        .iter()
+        .map(|measurement| {
+            format!(
+                "{} {}: {:.3} ms ± {:.3} ms ({} runs)\n",
+                measurement.program,
+                measurement.phase,
+                mean(&measurement.samples),
+                variance(&measurement.samples).sqrt(),
+                measurement.samples.len()
+            )
+        })
+        .collect()
+}
+
+/// Formats the comparisons one per line, flagging the changes that are significant.
+pub fn format_comparisons(comparisons: &[Comparison]) -> String {
+    comparisons
+        .iter()
+        .map(|comparison| {
+            let p_value = match comparison.p_value.is_nan() {
+                true => "n/a".to_string(),
+                false => format!("{:.3}", comparison.p_value),
+            };
+            format!(
+                "{} {}: {:.3} ms -> {:.3} ms ({:+.1}%, p = {}){}\n",
+                comparison.program,
+                comparison.phase,
+                comparison.baseline,
+                comparison.current,
+                comparison.change() * 100.0,
+                p_value,
+                match comparison.is_significant() {
+                    true => " *",
+                    false => "",
+                }
+            )
+        })
+        .collect()
+}
+
+fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+/// Returns the unbiased sample variance, which is zero for a single sample.
+fn variance(samples: &[f64]) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+
+    let mean = mean(samples);
+    samples
+        .iter()
+        .map(|sample| (sample - mean).powi(2))
+        .sum::<f64>()
+        / (samples.len() - 1) as f64
+}
+
+/// Returns the two sided p-value of Welch's t-test, which checks if two sets of samples with
+/// possibly different variances have the same mean. Returns `NaN` if either set has a single
+/// sample, as its variance and so the test are undefined.
+fn welch_t_test(a: &[f64], b: &[f64]) -> f64 {
+    if a.len() < 2 || b.len() < 2 {
+        return f64::NAN;
+    }
+    let (variance_a, variance_b) = (variance(a) / a.len() as f64, variance(b) / b.len() as f64);
+    let difference = mean(a) - mean(b);
+
+    // Without any variance, the means are either exactly equal or certainly different
+    let error = variance_a + variance_b;
+    if error == 0.0 {
+        return if difference == 0.0 { 1.0 } else { 0.0 };
+    }
+
+    let t = difference / error.sqrt();
+    let degrees = error.powi(2)
+        / (variance_a.powi(2) / (a.len() as f64 - 1.0).max(1.0)
+            + variance_b.powi(2) / (b.len() as f64 - 1.0).max(1.0));
+
+    // The tails of Student's t distribution, from the regularized incomplete beta function
+    incomplete_beta(degrees / (degrees + t * t), degrees / 2.0, 0.5)
+}
+
+/// Returns the regularized incomplete beta function `I_x(a, b)`, evaluated with its continued
+/// fraction as in Numerical Recipes.
+fn incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+
+    // The continued fraction converges quickly only on one side of the mean
+    match x < (a + 1.0) / (a + b + 2.0) {
+        true => front * beta_fraction(x, a, b) / a,
+        false => 1.0 - front * beta_fraction(1.0 - x, b, a) / b,
+    }
+}
+
+/// Evaluates the continued fraction of the incomplete beta function with Lentz's method.
+fn beta_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let clamp = |value: f64| if value.abs() < TINY { TINY } else { value };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / clamp(1.0 - (a + b) * x / (a + 1.0));
+    let mut result = d;
+
+    for m in 1..300 {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+
+        for numerator in [even, odd] {
+            d = 1.0 / clamp(1.0 + numerator * d);
+            c = clamp(1.0 + numerator / c);
+            result *= c * d;
+        }
+
+        if (c * d - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+
+    result
+}
+
+/// Returns the natural logarithm of the gamma function, with the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let series = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .fold(1.000000000190015, |series, (i, coefficient)| {
+            series + coefficient / (x + 1.0 + i as f64)
+        });
+
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement(program: &str, phase: &str, samples: &[f64]) -> Measurement {
+        Measurement {
+            program: program.to_string(),
+            phase: phase.to_string(),
+            samples: samples.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_command_from_args() {
+        let args = |args: &[&str]| {
+            Command::from_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+        };
+
+        assert_eq!(
+            args(&["a.lox", "--save", "main", "b.lox", "--iterations", "3"]),
+            Ok(Command::Measure {
+                programs: vec!["a.lox".to_string(), "b.lox".to_string()],
+                iterations: 3,
+                save: Some("main".to_string()),
+                config: Config::default(),
+            })
+        );
+        assert!(matches!(
+            args(&["a.lox", "--string-comparison"]),
+            Ok(Command::Measure { iterations: DEFAULT_ITERATIONS, save: None, config, .. })
+                if config.string_comparison
+        ));
+        assert_eq!(
+            args(&["--compare", "main", "branch"]),
+            Ok(Command::Compare("main".to_string(), "branch".to_string()))
+        );
+
+        assert!(args(&[]).is_err());
+        assert!(args(&["--compare", "main"]).is_err());
+        assert!(args(&["a.lox", "--iterations", "0"]).is_err());
+        assert!(args(&["a.lox", "--unknown"]).is_err());
+    }
+
+    #[test]
+    fn test_measure() {
+        let source = "var i = 0; while (i < 10) i = i + 1; print i;";
+        let measurements = measure("loop.lox", source, &Config::default(), 3).unwrap();
+
+        let phases = measurements
+            .iter()
+            .map(|measurement| measurement.phase.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            phases,
+            vec!["lex", "parse", "fold", "lower", "hoist", "run"]
+        );
+        assert!(measurements
+            .iter()
+            .all(|measurement| measurement.samples.len() == 3));
+
+        assert_eq!(
+            measure("error.lox", "print nil + 1;", &Config::default(), 1).unwrap_err(),
+            "error.lox: [line 1] Invalid operands for '+': expected two numbers or two strings, got nil and integer(1)."
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("lox-bench-{}", std::process::id()));
+        let measurements = vec![
+            measurement("a.lox", "lex", &[0.5, 0.25]),
+            measurement("a.lox", "run", &[12.0]),
+        ];
+
+        save(&dir, "baseline", &measurements).unwrap();
+        assert_eq!(load(&dir, "baseline").unwrap(), measurements);
+        assert!(load(&dir, "missing").is_err());
+
+        fs::write(dir.join("malformed"), "a.lox\tlex\n").unwrap();
+        assert!(load(&dir, "malformed").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_welch_t_test() {
+        // t = -2.036 with 15.5 degrees of freedom, whose p-value was computed by integrating the
+        // density of Student's t distribution numerically
+        let a = [27.5, 21.0, 19.0, 23.6, 17.0, 17.9, 16.9, 20.1, 21.9, 22.6];
+        let b = [27.1, 22.0, 20.8, 23.4, 23.4, 23.5, 25.8, 22.0, 24.8, 20.2];
+        assert!((welch_t_test(&a, &b) - 0.0593).abs() < 1e-3);
+
+        assert_eq!(welch_t_test(&[1.0, 1.0], &[1.0, 1.0]), 1.0);
+        assert_eq!(welch_t_test(&[1.0, 1.0], &[2.0, 2.0]), 0.0);
+        // A single sample has no variance to test against, even if the other set has one
+        assert!(welch_t_test(&[1.0], &[2.0]).is_nan());
+        assert!(welch_t_test(&[1.0], &[2.0, 2.1, 1.9]).is_nan());
+        assert!(welch_t_test(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]) > 0.99);
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = vec![
+            measurement("a.lox", "run", &[10.0, 10.1, 9.9, 10.0]),
+            measurement("a.lox", "lex", &[1.0, 1.2, 0.8, 1.0]),
+            measurement("b.lox", "run", &[5.0]),
+            measurement("b.lox", "lex", &[1.0]),
+        ];
+        let current = vec![
+            measurement("a.lox", "lex", &[1.1, 0.9, 1.0, 1.0]),
+            measurement("a.lox", "run", &[12.0, 12.1, 11.9, 12.0]),
+            measurement("b.lox", "lex", &[2.0]),
+        ];
+
+        let comparisons = compare(&baseline, &current);
+        assert_eq!(comparisons.len(), 3);
+        assert_eq!(comparisons[0].phase, "run");
+        assert!((comparisons[0].change() - 0.2).abs() < 1e-9);
+        assert!(comparisons[0].is_significant());
+        assert!(!comparisons[1].is_significant());
+
+        let report = format_comparisons(&comparisons);
+        assert!(report.starts_with("a.lox run: 10.000 ms -> 12.000 ms (+20.0%, p = 0.000) *\n"));
+        // Runs with a single sample can not be tested, and are never flagged
+        assert!(!comparisons[2].is_significant());
+        assert!(report.ends_with("b.lox lex: 1.000 ms -> 2.000 ms (+100.0%, p = n/a)\n"));
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt;
+
+/// The severity of a message logged by a program with the `log` natives, from the least severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// Returns the level with the provided name, as passed to `--log-level`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "debug" => Some(LogLevel::Debug),
+            "info" => Some(LogLevel::Info),
+            "warn" => Some(LogLevel::Warn),
+            "error" => Some(LogLevel::Error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        };
+        f.write_str(name)
+    }
+}
+
+/// How the operators treat operands of different types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conversions {
+    /// Operands of different types are a runtime error, like in the original Lox.
+    Strict,
+    /// `+` converts a number to a string when the other operand is a string (`"a" + 1`).
+    Lenient,
+}
+
+impl Conversions {
+    /// Returns the mode with the provided name, as passed to `--conversions`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "strict" => Some(Conversions::Strict),
+            "lenient" => Some(Conversions::Lenient),
+            _ => None,
+        }
+    }
+}
+
+/// Options that control the language extensions enabled for a run of the interpreter.
+/// The default configuration is the strict, book-compatible dialect of Lox.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// When set, `print` is an ordinary variadic native function (`print(a, b)`) instead
+    /// of a keyword statement, so it can be passed around and shadowed like any other value.
+    pub print_function: bool,
+
+    /// The string placed between the values printed by a single `print`.
+    pub print_separator: String,
+
+    /// When set, number literals can contain `_` separators between their digits (`1_000`).
+    pub number_separators: bool,
+
+    /// When set, strings can contain unicode escapes (`"caf\u{e9}"`). Otherwise backslashes are
+    /// kept as they are written, like in the original Lox.
+    pub unicode_escapes: bool,
+
+    /// When set, identifiers can be wrapped in backticks (`` `class` ``), so that names that
+    /// collide with keywords can be declared and accessed.
+    pub raw_identifiers: bool,
+
+    /// When set, the relational operators (`<`, `<=`, `>`, `>=`) compare strings
+    /// lexicographically, instead of failing with a runtime error like in strict Lox.
+    pub string_comparison: bool,
+
+    /// When set, a string can be multiplied by a non negative integer to repeat it (`"ab" * 3`).
+    pub string_repetition: bool,
+
+    /// When set, reading a key that is missing from a map is a runtime error, instead of
+    /// evaluating to `nil`.
+    pub missing_key_error: bool,
+
+    /// The least severe level of the messages logged with the `log` natives that are reported,
+    /// the others are discarded.
+    pub log_level: LogLevel,
+
+    /// Whether the operators convert operands of different types to a common type.
+    pub conversions: Conversions,
+
+    /// When set, the natives that reach outside of the program, such as to read and write files,
+    /// are not defined, so that untrusted programs can be run safely.
+    pub sandbox: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            print_function: false,
+            print_separator: " ".to_string(),
+            number_separators: false,
+            unicode_escapes: false,
+            raw_identifiers: false,
+            string_comparison: false,
+            string_repetition: false,
+            missing_key_error: false,
+            log_level: LogLevel::Info,
+            conversions: Conversions::Strict,
+            sandbox: true,
+        }
+    }
+}
+
+impl Config {
+    /// Builds a configuration from the command line flags passed to the interpreter.
+    pub fn from_flags(flags: &[String]) -> Result<Self, String> {
+        let mut config = Config::default();
+
+        for flag in flags {
+            // Flags that take a value are passed as `--name=value`
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (flag.as_str(), None),
+            };
+
+            match (name, value) {
+                ("--print-function", None) => config.print_function = true,
+                ("--number-separators", None) => config.number_separators = true,
+                ("--unicode-escapes", None) => config.unicode_escapes = true,
+                ("--raw-identifiers", None) => config.raw_identifiers = true,
+                ("--string-comparison", None) => config.string_comparison = true,
+                ("--string-repetition", None) => config.string_repetition = true,
+                ("--missing-key-error", None) => config.missing_key_error = true,
+                ("--no-sandbox", None) => config.sandbox = false,
+                ("--print-separator", Some(separator)) => {
+                    config.print_separator = separator.to_string()
+                }
+                ("--log-level", Some(level)) => {
+                    config.log_level = LogLevel::from_name(level)
+                        .ok_or_else(|| format!("Unknown log level: {}", level))?
+                }
+                ("--conversions", Some(mode)) => {
+                    config.conversions = Conversions::from_name(mode)
+                        .ok_or_else(|| format!("Unknown conversions mode: {}", mode))?
+                }
+                _ => return Err(format!("Unknown flag: {}", flag)),
+            }
+        }
+
+        Ok(config)
+    }
+}
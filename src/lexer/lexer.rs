@@ -1,4 +1,6 @@
+use super::number::{scan_number, NumberLiteral};
 use super::{token::Token, types::TokenType};
+use crate::config::Config;
 
 use std::string::String;
 use TokenType::*;
@@ -10,16 +12,25 @@ pub struct Lexer {
     current: usize,
     line: usize,
     character: usize,
+
+    config: Config,
 }
 
 impl Lexer {
-    /// Creates a new lexer with the given source code.
+    /// Creates a new lexer with the given source code and the default configuration.
+    #[cfg(test)]
     pub fn new(source: &str) -> Self {
+        Self::with_config(source, Config::default())
+    }
+
+    /// Creates a new lexer with the given source code and language configuration.
+    pub fn with_config(source: &str, config: Config) -> Self {
         Self {
             characters: source.chars().collect(),
             current: 0,
             line: 1,
             character: 1,
+            config,
         }
     }
 
@@ -28,10 +39,6 @@ impl Lexer {
         self.characters.get(self.current).copied()
     }
 
-    fn peek_next(&self) -> Option<char> {
-        self.characters.get(self.current + 1).copied()
-    }
-
     /// Consumes the next character in the source code and returns it.
     fn consume(&mut self) -> Option<char> {
         let next = self.peek();
@@ -57,18 +64,24 @@ impl Lexer {
     }
 
     /// Creates a new token with the given token type and lexeme.
-    fn new_token(&self, token_type: TokenType, lexeme: &str) -> Token {
+    fn new_token(&self, token_type: TokenType, lexeme: impl Into<String>) -> Token {
         Token::new(token_type, lexeme, self.line, self.character)
     }
 
-    /// Skips any whitespace characters in the source code.
+    /// Skips the trivia before the next token, which is whitespace and comments.
     /// Whitespace characters include spaces, tabs, carriage returns, and newlines.
-    fn skip_whitespace(&mut self) {
+    fn skip_trivia(&mut self) {
         while let Some(ch) = self.peek() {
             match ch {
                 ' ' | '\t' | '\r' | '\n' => {
                     self.consume();
                 }
+                '/' if self.characters.get(self.current + 1) == Some(&'/') => {
+                    // The following characters are a comment
+                    while self.peek() != Some('\n') && self.peek().is_some() {
+                        self.consume();
+                    }
+                }
                 _ => break,
             }
         }
@@ -76,17 +89,17 @@ impl Lexer {
 
     /// Returns if the provided digit is a 0-9 digit
     fn is_digit(c: char) -> bool {
-        return c >= '0' && c <= '9';
+        c.is_ascii_digit()
     }
 
     /// Returns if the provided character is an identifier character
     fn is_identifier(c: char) -> bool {
-        return (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || c == '_';
+        c.is_ascii_alphabetic() || c == '_'
     }
 
     /// Returns if the provided character is an alphanumeric character
     fn is_aplhanumeric(c: char) -> bool {
-        return Self::is_digit(c) || Self::is_identifier(c);
+        Self::is_digit(c) || Self::is_identifier(c)
     }
 
     /// Parses an identifier from the input. It assumes that it has already been
@@ -106,57 +119,98 @@ impl Lexer {
         identifier
     }
 
-    /// Parses an integer from the input. Returns the parsed integer
-    /// and the number of characters consumed
-    fn parse_integer(&mut self) -> (usize, usize) {
-        let mut res = 0;
-        let mut consumed = 0;
-
-        while let Some(ch) = self.peek() {
-            if !Self::is_digit(ch) {
-                break;
+    /// Parses a raw identifier wrapped in backticks, which can be any name including keywords.
+    /// Assumes that the opening backtick has already been consumed.
+    fn parse_raw_identifier_token(&mut self) -> Token {
+        let identifier = self.parse_identifier();
+
+        if self.peek() != Some('`') {
+            // Consume the rest of the malformed identifier to avoid cascading errors
+            let mut lexeme = format!("`{}", identifier);
+            while let Some(ch) = self.peek() {
+                if ch == '`' || ch.is_whitespace() {
+                    break;
+                }
+                self.consume();
+                lexeme.push(ch);
             }
 
-            self.consume();
-            consumed += 1;
-            let digit = ch as usize - ('0' as usize);
-            res = res * 10 + digit;
+            let message = match self.match_next('`') {
+                true => {
+                    lexeme.push('`');
+                    "Raw identifiers can only contain letters, digits and underscores."
+                }
+                false => "Unterminated raw identifier.",
+            };
+            return self.new_token(InvalidRawIdentifier(message.to_string()), &lexeme);
         }
 
-        (res, consumed)
-    }
+        self.consume();
+        let lexeme = format!("`{}`", identifier);
+        if identifier.is_empty() || Self::is_digit(identifier.chars().next().unwrap()) {
+            return self.new_token(
+                InvalidRawIdentifier(
+                    "Raw identifiers must start with a letter or an underscore.".to_string(),
+                ),
+                &lexeme,
+            );
+        }
 
-    /// Parses a floating-point or integer number from the source code.
-    fn parse_number(&mut self) -> f32 {
-        let mut num = self.parse_integer().0 as f32;
+        self.new_token(Identifier(identifier), &lexeme)
+    }
 
-        if self.peek() == Some('.') && self.peek_next().map_or(false, |ch| Self::is_digit(ch)) {
-            self.consume(); // Consume the dot
+    /// Parses a number token from the source code. It assumes that it has already been
+    /// checked that the first character is a digit.
+    fn parse_number_token(&mut self) -> Token {
+        let start_pos = self.current;
+        let result = scan_number(&self.characters[start_pos..], self.config.number_separators);
 
-            let (fr, len) = self.parse_integer();
-            num += fr as f32 / 10_f32.powi(len as i32);
+        let length = match &result {
+            Ok((_, length)) | Err((_, length)) => *length,
+        };
+        for _ in 0..length {
+            self.consume();
         }
 
-        num
+        let lexeme = self.characters[start_pos..self.current]
+            .iter()
+            .collect::<String>();
+        match result {
+            Ok((NumberLiteral::Integer(num), _)) => self.new_token(Integer(num), &lexeme),
+            Ok((NumberLiteral::Float(num), _)) => self.new_token(Number(num), &lexeme),
+            Err((message, _)) => self.new_token(InvalidNumber(message), &lexeme),
+        }
     }
 
-    /// Parses a string token from the source code.
+    /// Parses a string token from the source code. Strings can span multiple lines, in which case
+    /// the token is reported on the line where the string ends, as in the reference implementation.
+    /// With unicode escapes enabled, a malformed escape is reported once the whole string has been
+    /// read, so that lexing resumes after it.
     fn parse_string_token(&mut self) -> Token {
         let mut literal = String::new();
         let mut lexeme = String::from('"');
+        let mut invalid_escape = None;
 
         while let Some(ch) = self.peek() {
             lexeme.push(ch);
-
-            // Reached the end of the line before the string was terminated
-            if ch == '\n' {
-                return self.new_token(UnterminatedString(literal), lexeme.as_str());
-            }
-
             self.consume();
 
             if ch == '"' {
-                return self.new_token(String(literal), lexeme.as_str());
+                return match invalid_escape {
+                    Some(message) => self.new_token(InvalidEscape(message), lexeme.as_str()),
+                    None => self.new_token(String(literal), lexeme.as_str()),
+                };
+            }
+
+            // Other backslashes are kept as they are written
+            if ch == '\\' && self.peek() == Some('u') && self.config.unicode_escapes {
+                match self.scan_unicode_escape(&mut lexeme) {
+                    Ok(ch) => literal.push(ch),
+                    Err(message) => {
+                        invalid_escape.get_or_insert(message);
+                    }
+                }
+                continue;
             }
 
             literal.push(ch);
@@ -166,11 +220,62 @@ impl Lexer {
         self.new_token(UnterminatedString(literal), lexeme.as_str())
     }
 
+    /// Scans the `u{1F600}` after the backslash of a unicode escape, and returns the character
+    /// with the code point written in hexadecimal between the braces. The scanned characters are
+    /// added to the lexeme.
+    fn scan_unicode_escape(&mut self, lexeme: &mut String) -> Result<char, String> {
+        let malformed =
+            || "Malformed unicode escape, expected \\u{...} with 1 to 6 hex digits.".to_string();
+
+        lexeme.push('u');
+        self.consume();
+        if self.peek() != Some('{') {
+            return Err(malformed());
+        }
+        lexeme.push('{');
+        self.consume();
+
+        let mut digits = String::new();
+        while let Some(ch) = self.peek().filter(|ch| ch.is_ascii_hexdigit()) {
+            digits.push(ch);
+            lexeme.push(ch);
+            self.consume();
+        }
+        if self.peek() != Some('}') || digits.is_empty() || digits.len() > 6 {
+            return Err(malformed());
+        }
+        lexeme.push('}');
+        self.consume();
+
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| {
+                format!(
+                    "Invalid unicode escape, \\u{{{}}} is not a character.",
+                    digits
+                )
+            })
+    }
+
     /// Returns the next token in the source code. It consumes the source code
     /// character by character and returns a token for each character.
     pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
+        self.skip_trivia();
+        self.scan_token()
+    }
 
+    /// Returns the next token along with the trivia that was skipped before it, so that
+    /// concatenating the trivia and the lexemes of all the tokens gives back the source code.
+    pub fn next_token_with_trivia(&mut self) -> (String, Token) {
+        let start = self.current;
+        self.skip_trivia();
+        let trivia = self.characters[start..self.current].iter().collect();
+        (trivia, self.scan_token())
+    }
+
+    /// Scans the token that starts at the current character, which is not trivia.
+    fn scan_token(&mut self) -> Token {
         match self.consume() {
             None => self.new_token(EOF, ""),
             Some(ch) => match ch {
@@ -179,25 +284,25 @@ impl Lexer {
                 ')' => self.new_token(RightParen, ")"),
                 '{' => self.new_token(LeftBrace, "{"),
                 '}' => self.new_token(RightBrace, "}"),
+                '[' => self.new_token(LeftBracket, "["),
+                ']' => self.new_token(RightBracket, "]"),
 
                 // Operators
                 '*' => self.new_token(Star, "*"),
+                '.' if self.peek() == Some('.')
+                    && self.characters.get(self.current + 1) == Some(&'.') =>
+                {
+                    self.consume();
+                    self.consume();
+                    self.new_token(DotDotDot, "...")
+                }
                 '.' => self.new_token(Dot, "."),
                 ',' => self.new_token(Comma, ","),
+                ':' => self.new_token(Colon, ":"),
                 ';' => self.new_token(Semicolon, ";"),
                 '+' => self.new_token(Plus, "+"),
                 '-' => self.new_token(Minus, "-"),
-                '/' => {
-                    if self.peek() == Some('/') {
-                        // The following characters are a comment
-                        while self.peek() != Some('\n') && self.peek() != None {
-                            self.consume();
-                        }
-                        self.next_token()
-                    } else {
-                        self.new_token(Slash, "/")
-                    }
-                }
+                '/' => self.new_token(Slash, "/"),
 
                 // Equality and Negation
                 '=' => match self.match_next('=') {
@@ -221,29 +326,26 @@ impl Lexer {
 
                 // Literals
                 '"' => self.parse_string_token(),
+                '`' if self.config.raw_identifiers => self.parse_raw_identifier_token(),
 
                 _ => {
                     if Self::is_digit(ch) {
-                        self.current -= 1; // As we will consume the character again in parse_number
-
-                        let start_pos = self.current;
-                        let num = self.parse_number();
-                        let lexeme = &self.characters[start_pos..self.current]
-                            .iter()
-                            .collect::<String>();
-
-                        return self.new_token(Number(num), lexeme);
+                        self.current -= 1; // As we will consume the character again as a part of the number
+                        return self.parse_number_token();
                     }
 
                     if Self::is_identifier(ch) {
                         self.current -= 1; // As we will consume the character again in parse_identifier
                         let identifier = self.parse_identifier();
 
-                        if let Some(keyword) = TokenType::check_keyword(identifier.as_str()) {
-                            return self.new_token(keyword, identifier.as_str());
+                        if let Some(keyword) = TokenType::check_keyword(&identifier) {
+                            // `print` is a plain identifier when it is provided as a native function
+                            if !(keyword == PRINT && self.config.print_function) {
+                                return self.new_token(keyword, identifier);
+                            }
                         }
 
-                        return self.new_token(Identifier(identifier.clone()), identifier.as_str());
+                        return self.new_token(Identifier(identifier.clone()), identifier);
                     }
 
                     self.new_token(Unknown, String::from(ch).as_str())
@@ -267,4 +369,244 @@ impl Lexer {
 
         tokens
     }
+
+    /// Returns the tokens of the source code up to and including the EOF token, each with the
+    /// trivia that precedes it. This is the lossless form of `get_tokens`, for tools that need
+    /// to write the source code back without changing what they did not intend to.
+    pub fn get_tokens_with_trivia(&mut self) -> Vec<(String, Token)> {
+        let mut tokens = Vec::new();
+
+        loop {
+            let (trivia, token) = self.next_token_with_trivia();
+            let is_eof = token.token_type == EOF;
+            tokens.push((trivia, token));
+            if is_eof {
+                return tokens;
+            }
+        }
+    }
+
+    /// Returns an iterator that lexes the tokens of the source code as they are requested, up to
+    /// and including the EOF token.
+    pub fn into_tokens(mut self) -> impl Iterator<Item = Token> {
+        let mut done = false;
+        std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+
+            let token = self.next_token();
+            done = token.token_type == EOF;
+            Some(token)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ellipsis() {
+        let tokens = Lexer::new("f(...xs) .. .").get_tokens();
+        let names = tokens
+            .iter()
+            .map(|token| token.token_type.name())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "IDENTIFIER",
+                "LEFT_PAREN",
+                "DOT_DOT_DOT",
+                "IDENTIFIER",
+                "RIGHT_PAREN",
+                "DOT",
+                "DOT",
+                "DOT",
+                "EOF"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fidelity() {
+        let corpus = [
+            "",
+            "   \n\t",
+            "// only a comment",
+            "var a = 1; // trailing comment\r\nprint a/2;\n",
+            "print \"multi\nline\" + \"unterminated",
+            "var `class` = 0x1F + 1_000; `bad-name` `",
+            "@ # print \"héllo\" // ünïcode\n\n",
+            "a//b\n/ /c",
+        ];
+
+        for source in corpus {
+            let tokens = Lexer::new(source).get_tokens_with_trivia();
+
+            let mut text = String::new();
+            for (trivia, token) in &tokens {
+                text.push_str(trivia);
+                text.push_str(&token.lexeme);
+            }
+            assert_eq!(text, source);
+
+            // The tokens are the same as without the trivia
+            let expected = Lexer::new(source).get_tokens();
+            let tokens = tokens
+                .into_iter()
+                .map(|(_, token)| token)
+                .collect::<Vec<_>>();
+            assert_eq!(tokens, expected, "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn test_raw_identifiers() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<&'a str>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Keyword as a raw identifier",
+                input: "`class`",
+                expected: vec!["IDENTIFIER `class` null"],
+            },
+            TestCase {
+                name: "Plain name as a raw identifier",
+                input: "`foo_1` + 1",
+                expected: vec!["IDENTIFIER `foo_1` null", "PLUS + null", "NUMBER 1 1.0"],
+            },
+            TestCase {
+                name: "Unterminated raw identifier",
+                input: "`class + 1",
+                expected: vec![
+                    "[line 1] Error: Unterminated raw identifier.",
+                    "PLUS + null",
+                    "NUMBER 1 1.0",
+                ],
+            },
+            TestCase {
+                name: "Invalid characters",
+                input: "`a-b`",
+                expected: vec![
+                    "[line 1] Error: Raw identifiers can only contain letters, digits and underscores.",
+                ],
+            },
+            TestCase {
+                name: "Empty raw identifier",
+                input: "``",
+                expected: vec![
+                    "[line 1] Error: Raw identifiers must start with a letter or an underscore.",
+                ],
+            },
+        ];
+
+        for test_case in test_cases {
+            let config = Config {
+                raw_identifiers: true,
+                ..Config::default()
+            };
+            let tokens = Lexer::with_config(test_case.input, config)
+                .get_tokens()
+                .iter()
+                .filter(|token| token.token_type != EOF)
+                .map(|token| token.tokenized_string())
+                .collect::<Vec<_>>();
+
+            assert_eq!(tokens, test_case.expected, "{}", test_case.name);
+        }
+
+        // Backticks are not allowed without the extension
+        let tokens = Lexer::new("`class`").get_tokens();
+        assert!(tokens[0].is_error());
+    }
+
+    #[test]
+    fn test_multi_line_strings() {
+        let tokens = Lexer::new("var a = \"one\ntwo\n\";\nprint a;").get_tokens();
+
+        assert_eq!(tokens[3].token_type, String("one\ntwo\n".to_string()));
+        assert_eq!(tokens[3].line, 3);
+        // The lines after the string are counted from its end
+        assert_eq!(tokens[4].line, 3);
+        assert_eq!(tokens[5].line, 4);
+
+        let tokens = Lexer::new("print \"one\ntwo").get_tokens();
+        assert_eq!(
+            tokens[1].tokenized_string(),
+            "[line 2] Error: Unterminated string."
+        );
+        assert_eq!(tokens[2].token_type, EOF);
+    }
+
+    #[test]
+    fn test_unicode_escapes() {
+        let lex = |source: &str| {
+            let config = Config {
+                unicode_escapes: true,
+                ..Config::default()
+            };
+            Lexer::with_config(source, config).get_tokens()
+        };
+
+        // Book Lox has no escapes, so backslashes are kept as they are written by default
+        let tokens = Lexer::new(r#""C:\users\me" "\u{41}" "\u{""#).get_tokens();
+        assert_eq!(tokens[0].token_type, String(r"C:\users\me".to_string()));
+        assert_eq!(tokens[1].token_type, String(r"\u{41}".to_string()));
+        assert_eq!(tokens[2].token_type, String(r"\u{".to_string()));
+
+        let tokens = lex(r#""\u{1F600} \u{e9}\u{41}" "a\nb""#);
+        assert_eq!(tokens[0].token_type, String("😀 éA".to_string()));
+        assert_eq!(tokens[0].lexeme, r#""\u{1F600} \u{e9}\u{41}""#);
+        // Other backslashes are not escapes
+        assert_eq!(tokens[1].token_type, String(r"a\nb".to_string()));
+
+        let cases = [
+            (
+                r#""\u41""#,
+                "Malformed unicode escape, expected \\u{...} with 1 to 6 hex digits.",
+            ),
+            (
+                r#""\u{}""#,
+                "Malformed unicode escape, expected \\u{...} with 1 to 6 hex digits.",
+            ),
+            (
+                r#""\u{12""#,
+                "Malformed unicode escape, expected \\u{...} with 1 to 6 hex digits.",
+            ),
+            (
+                r#""\u{1234567}""#,
+                "Malformed unicode escape, expected \\u{...} with 1 to 6 hex digits.",
+            ),
+            (
+                r#""\u{D800}""#,
+                "Invalid unicode escape, \\u{D800} is not a character.",
+            ),
+            (
+                r#""\u{110000} \u{x}""#,
+                "Invalid unicode escape, \\u{110000} is not a character.",
+            ),
+        ];
+        for (input, message) in cases {
+            let tokens = lex(&format!("{} 1", input));
+            assert_eq!(
+                tokens[0].tokenized_string(),
+                format!("[line 1] Error: {}", message),
+                "{}",
+                input
+            );
+            assert_eq!(tokens[0].lexeme, input);
+            // Lexing resumes after the string
+            assert_eq!(tokens[1].token_type, Integer(1), "{}", input);
+        }
+
+        // An unterminated string is reported as such, even with a malformed escape
+        let tokens = lex(r#""\u{"#);
+        assert!(matches!(tokens[0].token_type, UnterminatedString(_)));
+    }
 }
@@ -1,3 +1,4 @@
 pub mod lexer;
+pub mod number;
 pub mod token;
 pub mod types;
@@ -0,0 +1,420 @@
+/// Scans a number literal from the start of the provided characters, which must begin with a digit.
+/// Literals are decimal with an optional exponent (`1.5e3`, `2E-4`), or hexadecimal (`0x1F`) and
+/// binary (`0b1010`) integers with a prefix.
+/// Returns the value of the literal and the number of characters it spans, or an error message
+/// along with the number of characters that make up the malformed literal.
+///
+/// The value is computed with Rust's own float parsing on a normalized copy of the digits,
+/// so the result never depends on the locale of the system.
+pub fn scan_number(
+    chars: &[char],
+    allow_separators: bool,
+) -> Result<(NumberLiteral, usize), (String, usize)> {
+    let mut scanner = NumberScanner {
+        chars,
+        current: 0,
+        digits: String::new(),
+        radix: 10,
+        allow_separators,
+    };
+
+    scanner.scan()
+}
+
+/// The value of a number literal. Literals without a fractional part or an exponent are integers,
+/// unless they are too large for an `i64`, in which case they are approximated by a float.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NumberLiteral {
+    Integer(i64),
+    Float(f64),
+}
+
+/// Formats the value of a number literal for the token stream, where integral values keep a
+/// single decimal place (`5.0`) so that the literal is always shown as a decimal number.
+/// This is not the format used by the runtime, see `Value::fmt` for it.
+pub fn format_literal(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{:.1}", value)
+    } else {
+        format!("{}", value)
+    }
+}
+
+struct NumberScanner<'a> {
+    chars: &'a [char],
+    current: usize,
+    /// The digits of the literal with the separators removed, in a format accepted by `str::parse`.
+    digits: String,
+    /// The base of the digits, which is set by the prefix of the literal.
+    radix: u32,
+    allow_separators: bool,
+}
+
+impl NumberScanner<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.current).copied()
+    }
+
+    fn peek_next(&self) -> Option<char> {
+        self.chars.get(self.current + 1).copied()
+    }
+
+    fn scan(&mut self) -> Result<(NumberLiteral, usize), (String, usize)> {
+        if self.peek() == Some('0') {
+            match self.peek_next() {
+                Some('x' | 'X') => return self.scan_prefixed(16, "hexadecimal"),
+                Some('b' | 'B') => return self.scan_prefixed(2, "binary"),
+                _ => {}
+            }
+        }
+
+        self.scan_digits()?;
+        let integer_length = self.digits.len();
+
+        // The fractional part is only consumed if the dot is followed by a digit
+        if self.peek() == Some('.') && self.peek_next().is_some_and(|ch| ch.is_ascii_digit()) {
+            self.current += 1;
+            self.digits.push('.');
+            self.scan_digits()?;
+        }
+
+        if let Some('e' | 'E') = self.peek() {
+            self.scan_exponent()?;
+        }
+
+        if self.digits.len() == integer_length {
+            if let Ok(value) = self.digits.parse::<i64>() {
+                return Ok((NumberLiteral::Integer(value), self.current));
+            }
+        }
+
+        let value = self
+            .digits
+            .parse::<f64>()
+            .expect("The scanned digits should always form a valid number");
+
+        Ok((NumberLiteral::Float(value), self.current))
+    }
+
+    /// Scans the exponent of a decimal literal, starting at the `e`, with an optional sign.
+    fn scan_exponent(&mut self) -> Result<(), (String, usize)> {
+        self.current += 1;
+        self.digits.push('e');
+
+        if let Some(sign @ ('+' | '-')) = self.peek() {
+            self.current += 1;
+            self.digits.push(sign);
+        }
+
+        if !self.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+            return Err(self.error("Expected digits in the exponent of the number literal."));
+        }
+        self.scan_digits()
+    }
+
+    /// Scans an integer literal in the provided base, after its two character prefix.
+    /// Letters and digits that are not valid in the base are reported as part of the literal.
+    fn scan_prefixed(
+        &mut self,
+        radix: u32,
+        base: &str,
+    ) -> Result<(NumberLiteral, usize), (String, usize)> {
+        self.radix = radix;
+        self.current = 2;
+
+        if self.peek() == Some('_') {
+            return Err(self.error("Underscores in number literals must be preceded by a digit."));
+        }
+        self.scan_digits()?;
+
+        if let Some(ch) = self.peek().filter(|ch| ch.is_ascii_alphanumeric()) {
+            return Err(self.error(&format!("Invalid digit '{}' in {} literal.", ch, base)));
+        }
+        if self.digits.is_empty() {
+            return Err(self.error(&format!(
+                "Expected {} digits after '{}'.",
+                base,
+                self.chars[..2].iter().collect::<String>()
+            )));
+        }
+
+        if let Ok(value) = i64::from_str_radix(&self.digits, radix) {
+            return Ok((NumberLiteral::Integer(value), self.current));
+        }
+
+        let value = self.digits.chars().fold(0.0, |value: f64, digit| {
+            value * radix as f64 + digit.to_digit(radix).unwrap_or_default() as f64
+        });
+
+        Ok((NumberLiteral::Float(value), self.current))
+    }
+
+    /// Scans a run of digits, along with the `_` separators between them if they are allowed.
+    fn scan_digits(&mut self) -> Result<(), (String, usize)> {
+        while let Some(ch) = self.peek() {
+            match ch {
+                ch if ch.is_digit(self.radix) => self.digits.push(ch),
+                '_' if self.allow_separators => match self.peek_next() {
+                    Some(ch) if ch.is_digit(self.radix) => {}
+                    Some('_') => {
+                        return Err(self
+                            .error("Consecutive underscores are not allowed in number literals."))
+                    }
+                    _ => {
+                        return Err(self
+                            .error("Underscores in number literals must be followed by a digit."))
+                    }
+                },
+                _ => break,
+            }
+
+            self.current += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Creates an error with the provided message, which spans the rest of the malformed literal
+    /// so that the lexer does not report the trailing characters as separate tokens.
+    /// The literals with a prefix also span the letters, which can be digits in their base.
+    fn error(&self, message: &str) -> (String, usize) {
+        let length = self.chars[self.current..]
+            .iter()
+            .take_while(|ch| match self.radix {
+                10 => ch.is_ascii_digit() || **ch == '_',
+                _ => ch.is_ascii_alphanumeric() || **ch == '_',
+            })
+            .count();
+
+        (message.to_string(), self.current + length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_literal() {
+        assert_eq!(format_literal(5.0), "5.0");
+        assert_eq!(format_literal(0.0), "0.0");
+        assert_eq!(format_literal(12.5), "12.5");
+        assert_eq!(format_literal(1234567.0), "1234567.0");
+        assert_eq!(format_literal(1e10), "10000000000.0");
+    }
+
+    #[test]
+    fn test_scan_number() {
+        use NumberLiteral::*;
+
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            allow_separators: bool,
+            expected: Result<(NumberLiteral, usize), (&'a str, usize)>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Integer",
+                input: "123",
+                allow_separators: false,
+                expected: Ok((Integer(123), 3)),
+            },
+            TestCase {
+                name: "Decimal",
+                input: "12.5",
+                allow_separators: false,
+                expected: Ok((Float(12.5), 4)),
+            },
+            TestCase {
+                name: "Trailing dot is not part of the number",
+                input: "12.",
+                allow_separators: false,
+                expected: Ok((Integer(12), 2)),
+            },
+            TestCase {
+                name: "Method call on a number",
+                input: "12.foo",
+                allow_separators: false,
+                expected: Ok((Integer(12), 2)),
+            },
+            TestCase {
+                name: "Leading zeros",
+                input: "007",
+                allow_separators: false,
+                expected: Ok((Integer(7), 3)),
+            },
+            TestCase {
+                name: "Integer larger than usize",
+                input: "123456789012345678901234567890",
+                allow_separators: false,
+                expected: Ok((Float(123456789012345678901234567890.0), 30)),
+            },
+            TestCase {
+                name: "Separators are not part of the number by default",
+                input: "1_000",
+                allow_separators: false,
+                expected: Ok((Integer(1), 1)),
+            },
+            TestCase {
+                name: "Separators",
+                input: "1_000_000",
+                allow_separators: true,
+                expected: Ok((Integer(1000000), 9)),
+            },
+            TestCase {
+                name: "Separators in the fractional part",
+                input: "1_0.2_5",
+                allow_separators: true,
+                expected: Ok((Float(10.25), 7)),
+            },
+            TestCase {
+                name: "Consecutive separators",
+                input: "1__0 + 2",
+                allow_separators: true,
+                expected: Err((
+                    "Consecutive underscores are not allowed in number literals.",
+                    4,
+                )),
+            },
+            TestCase {
+                name: "Trailing separator",
+                input: "10_ + 2",
+                allow_separators: true,
+                expected: Err((
+                    "Underscores in number literals must be followed by a digit.",
+                    3,
+                )),
+            },
+            TestCase {
+                name: "Exponent",
+                input: "1.5e3",
+                allow_separators: false,
+                expected: Ok((Float(1500.0), 5)),
+            },
+            TestCase {
+                name: "Signed exponent with an uppercase marker",
+                input: "2E-4 + 1",
+                allow_separators: false,
+                expected: Ok((Float(0.0002), 4)),
+            },
+            TestCase {
+                name: "Exponent of an integer",
+                input: "3e+2",
+                allow_separators: false,
+                expected: Ok((Float(300.0), 4)),
+            },
+            TestCase {
+                name: "Exponent out of range",
+                input: "1e309",
+                allow_separators: false,
+                expected: Ok((Float(f64::INFINITY), 5)),
+            },
+            TestCase {
+                name: "Separators in the exponent",
+                input: "1e1_0",
+                allow_separators: true,
+                expected: Ok((Float(1e10), 5)),
+            },
+            TestCase {
+                name: "Missing exponent digits",
+                input: "1e + 2",
+                allow_separators: false,
+                expected: Err(("Expected digits in the exponent of the number literal.", 2)),
+            },
+            TestCase {
+                name: "Missing exponent digits after the sign",
+                input: "1.5e-x",
+                allow_separators: false,
+                expected: Err(("Expected digits in the exponent of the number literal.", 5)),
+            },
+            TestCase {
+                name: "Hexadecimal",
+                input: "0x1F + 1",
+                allow_separators: false,
+                expected: Ok((Integer(31), 4)),
+            },
+            TestCase {
+                name: "Hexadecimal with uppercase prefix and lowercase digits",
+                input: "0Xff",
+                allow_separators: false,
+                expected: Ok((Integer(255), 4)),
+            },
+            TestCase {
+                name: "Binary",
+                input: "0b1010",
+                allow_separators: false,
+                expected: Ok((Integer(10), 6)),
+            },
+            TestCase {
+                name: "Prefixed literals are integers",
+                input: "0x10.5",
+                allow_separators: false,
+                expected: Ok((Integer(16), 4)),
+            },
+            TestCase {
+                name: "Prefixed literal larger than an i64",
+                input: "0x10000000000000000",
+                allow_separators: false,
+                expected: Ok((Float(18446744073709551616.0), 19)),
+            },
+            TestCase {
+                name: "Separators in a prefixed literal",
+                input: "0b1111_0000",
+                allow_separators: true,
+                expected: Ok((Integer(240), 11)),
+            },
+            TestCase {
+                name: "Separator after the prefix",
+                input: "0x_1",
+                allow_separators: true,
+                expected: Err((
+                    "Underscores in number literals must be preceded by a digit.",
+                    4,
+                )),
+            },
+            TestCase {
+                name: "Invalid binary digit",
+                input: "0b102 + 1",
+                allow_separators: false,
+                expected: Err(("Invalid digit '2' in binary literal.", 5)),
+            },
+            TestCase {
+                name: "Invalid hexadecimal digit",
+                input: "0x1g",
+                allow_separators: false,
+                expected: Err(("Invalid digit 'g' in hexadecimal literal.", 4)),
+            },
+            TestCase {
+                name: "Missing digits",
+                input: "0x;",
+                allow_separators: false,
+                expected: Err(("Expected hexadecimal digits after '0x'.", 2)),
+            },
+            TestCase {
+                name: "Separator before the dot",
+                input: "10_.5",
+                allow_separators: true,
+                expected: Err((
+                    "Underscores in number literals must be followed by a digit.",
+                    3,
+                )),
+            },
+        ];
+
+        for test_case in test_cases {
+            let chars = test_case.input.chars().collect::<Vec<_>>();
+            let result = scan_number(&chars, test_case.allow_separators);
+
+            assert_eq!(
+                result,
+                test_case
+                    .expected
+                    .map_err(|(message, length)| (message.to_string(), length)),
+                "{}",
+                test_case.name
+            );
+        }
+    }
+}
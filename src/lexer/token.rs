@@ -1,4 +1,4 @@
-use super::types::TokenType;
+use super::{number::format_literal, types::TokenType};
 use std::fmt;
 
 #[derive(PartialEq, Clone)]
@@ -7,97 +7,70 @@ pub struct Token {
     pub lexeme: String,
     pub line: usize,
     pub character: usize,
+    /// Set for tokens that were not written by the user, but created by a transform of the
+    /// syntax tree. They carry the position of the source code they were derived from.
+    pub synthetic: bool,
 }
 
 impl Token {
     /// Creates a new token with the given token type, lexeme, line, and character values.
-    pub fn new(token_type: TokenType, lexeme: &str, line: usize, character: usize) -> Self {
+    /// The lexeme is moved into the token when it is an owned string.
+    pub fn new(
+        token_type: TokenType,
+        lexeme: impl Into<String>,
+        line: usize,
+        character: usize,
+    ) -> Self {
         Token {
             token_type,
-            lexeme: lexeme.to_string(),
+            lexeme: lexeme.into(),
             line,
             character,
+            synthetic: false,
+        }
+    }
+
+    /// Creates a synthetic token with the given token type and lexeme, positioned at this token,
+    /// so that errors caused by code generated from this token are reported where the user wrote it.
+    pub fn synthesize(&self, token_type: TokenType, lexeme: &str) -> Self {
+        Token {
+            token_type,
+            lexeme: lexeme.to_string(),
+            line: self.line,
+            character: self.character,
+            synthetic: true,
         }
     }
 
     /// Creates a new token with the default line and character values of 0.
     /// This is intended to be only used for testing purposes.
+    #[cfg(test)]
     pub fn new_default(token_type: TokenType, lexeme: &str) -> Self {
         Token {
             token_type,
             lexeme: lexeme.to_string(),
             line: 0,
             character: 0,
+            synthetic: false,
         }
     }
 
-    /// Returns the value of the token as a string.
-    pub fn get_value(&self) -> String {
+    /// Returns the name the token refers to. For identifiers this is the name without the
+    /// backticks of the raw identifier syntax, and for all other tokens it is the lexeme.
+    pub fn name(&self) -> &str {
         match &self.token_type {
-            TokenType::Number(val) => {
-                // If the number is an integer, display it with one decimal place
-                if val.fract() == 0.0 {
-                    format!("{:.1}", val)
-                } else {
-                    format!("{}", val)
-                }
-            },
-            TokenType::String(val) => val.to_string(),
-            _ => "null".to_string(),
+            TokenType::Identifier(name) => name,
+            _ => &self.lexeme,
         }
     }
 
-    /// Returns the name of the token type as a string.
-    pub fn get_name(&self) -> String {
+    /// Returns the value of the token as a string.
+    pub fn get_value(&self) -> String {
         match &self.token_type {
-            TokenType::EOF => "EOF".to_string(),
-
-            TokenType::LeftParen => "LEFT_PAREN".to_string(),
-            TokenType::RightParen => "RIGHT_PAREN".to_string(),
-            TokenType::LeftBrace => "LEFT_BRACE".to_string(),
-            TokenType::RightBrace => "RIGHT_BRACE".to_string(),
-
-            TokenType::Star => "STAR".to_string(),
-            TokenType::Dot => "DOT".to_string(),
-            TokenType::Comma => "COMMA".to_string(),
-            TokenType::Semicolon => "SEMICOLON".to_string(),
-            TokenType::Plus => "PLUS".to_string(),
-            TokenType::Minus => "MINUS".to_string(),
-            TokenType::Slash => "SLASH".to_string(),
-
-            TokenType::Bang => "BANG".to_string(),
-            TokenType::Equal => "EQUAL".to_string(),
-            TokenType::EqualEqual => "EQUAL_EQUAL".to_string(),
-            TokenType::BangEqual => "BANG_EQUAL".to_string(),
-
-            TokenType::Greater => "GREATER".to_string(),
-            TokenType::GreaterEqual => "GREATER_EQUAL".to_string(),
-            TokenType::Less => "LESS".to_string(),
-            TokenType::LessEqual => "LESS_EQUAL".to_string(),
-
-            TokenType::String(_) => "STRING".to_string(),
-            TokenType::Number(_) => "NUMBER".to_string(),
-            TokenType::Identifier(_) => "IDENTIFIER".to_string(),
-
-            TokenType::AND => "AND".to_string(),    
-            TokenType::CLASS => "CLASS".to_string(),
-            TokenType::ELSE => "ELSE".to_string(),
-            TokenType::FALSE => "FALSE".to_string(),
-            TokenType::FOR => "FOR".to_string(),
-            TokenType::FUN => "FUN".to_string(),
-            TokenType::IF => "IF".to_string(),
-            TokenType::NIL => "NIL".to_string(),
-            TokenType::OR => "OR".to_string(),
-            TokenType::PRINT => "PRINT".to_string(),
-            TokenType::RETURN => "RETURN".to_string(),
-            TokenType::SUPER => "SUPER".to_string(),
-            TokenType::THIS => "THIS".to_string(),
-            TokenType::TRUE => "TRUE".to_string(),
-            TokenType::VAR => "VAR".to_string(),
-            TokenType::WHILE => "WHILE".to_string(),
-
-            TokenType::UnterminatedString(_) => "UnterminatedString".to_string(),
-            TokenType::Unknown => "Unknown".to_string(),
+            TokenType::Number(val) => format_literal(*val),
+            TokenType::Integer(val) => format_literal(*val as f64),
+            TokenType::String(val) => val.to_string(),
+            _ => "null".to_string(),
         }
     }
 
@@ -105,34 +78,34 @@ impl Token {
     /// <token_type> <lexeme> <literal>
     /// This is used for the "tokenize" command.
     pub fn tokenized_string(&self) -> String {
-        match &self.token_type {
-            // Errors
-            TokenType::UnterminatedString(_) => {
-                format!("[line {}] Error: Unterminated string.", self.line)
-            }
-            TokenType::Unknown => {
-                format!(
-                    "[line {}] Error: Unexpected character: {}",
-                    self.line, self.lexeme
-                )
-            }
+        match self.error_message() {
+            Some(message) => format!("[line {}] {}", self.line, message),
+            None => format!("{} {} {}", self.token_type, self.lexeme, self.get_value()),
+        }
+    }
 
-            _ => {
-                format!(
-                    "{} {} {}",
-                    self.get_name(),
-                    self.lexeme,
-                    self.get_value()
-                )
-            }
+    /// Returns the message of the lexical error that the token is, without its line, or `None`
+    /// if the token is not an error. The parser reports error tokens with the same message.
+    pub fn error_message(&self) -> Option<String> {
+        match &self.token_type {
+            TokenType::UnterminatedString(_) => Some("Error: Unterminated string.".to_string()),
+            TokenType::Unknown => Some(format!("Error: Unexpected character: {}", self.lexeme)),
+            TokenType::InvalidNumber(message)
+            | TokenType::InvalidRawIdentifier(message)
+            | TokenType::InvalidEscape(message) => Some(format!("Error: {}", message)),
+            _ => None,
         }
     }
 
     pub fn is_error(&self) -> bool {
-        match self.token_type {
-            TokenType::Unknown | TokenType::UnterminatedString(_) => true,
-            _ => false,
-        }
+        matches!(
+            self.token_type,
+            TokenType::Unknown
+                | TokenType::UnterminatedString(_)
+                | TokenType::InvalidNumber(_)
+                | TokenType::InvalidRawIdentifier(_)
+                | TokenType::InvalidEscape(_)
+        )
     }
 }
 
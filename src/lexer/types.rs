@@ -1,3 +1,6 @@
+use std::fmt;
+use std::str::FromStr;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenType {
     EOF,
@@ -8,11 +11,15 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
 
     // Operators
     Star,
     Dot,
+    DotDotDot,
     Comma,
+    Colon,
     Semicolon,
     Plus,
     Minus,
@@ -34,47 +41,391 @@ pub enum TokenType {
     String(String),
     Identifier(String),
     UnterminatedString(String),
-    Number(f32),
+    InvalidNumber(String),
+    InvalidRawIdentifier(String),
+    InvalidEscape(String),
+    Number(f64),
+    Integer(i64),
 
     // Keywords
     AND,
+    ASSERT,
+    BREAK,
+    CASE,
+    CATCH,
     CLASS,
+    CONST,
+    CONTINUE,
+    DEFAULT,
+    DO,
     ELSE,
     FALSE,
+    FINALLY,
     FOR,
     FUN,
     IF,
+    IN,
+    IS,
     NIL,
     OR,
     PRINT,
     RETURN,
     SUPER,
+    SWITCH,
     THIS,
+    THROW,
     TRUE,
+    TRY,
+    TYPEOF,
     VAR,
     WHILE,
 }
 
+/// The token types that do not carry a value, which are the ones that can be parsed back from
+/// their name.
+const NAMED: &[TokenType] = &[
+    TokenType::EOF,
+    TokenType::LeftParen,
+    TokenType::RightParen,
+    TokenType::LeftBrace,
+    TokenType::RightBrace,
+    TokenType::LeftBracket,
+    TokenType::RightBracket,
+    TokenType::Star,
+    TokenType::Dot,
+    TokenType::DotDotDot,
+    TokenType::Comma,
+    TokenType::Colon,
+    TokenType::Semicolon,
+    TokenType::Plus,
+    TokenType::Minus,
+    TokenType::Slash,
+    TokenType::Bang,
+    TokenType::Equal,
+    TokenType::EqualEqual,
+    TokenType::BangEqual,
+    TokenType::Greater,
+    TokenType::GreaterEqual,
+    TokenType::Less,
+    TokenType::LessEqual,
+    TokenType::AND,
+    TokenType::ASSERT,
+    TokenType::BREAK,
+    TokenType::CASE,
+    TokenType::CATCH,
+    TokenType::CLASS,
+    TokenType::CONST,
+    TokenType::CONTINUE,
+    TokenType::DEFAULT,
+    TokenType::DO,
+    TokenType::ELSE,
+    TokenType::FALSE,
+    TokenType::FINALLY,
+    TokenType::FOR,
+    TokenType::FUN,
+    TokenType::IF,
+    TokenType::IN,
+    TokenType::IS,
+    TokenType::NIL,
+    TokenType::OR,
+    TokenType::PRINT,
+    TokenType::RETURN,
+    TokenType::SUPER,
+    TokenType::SWITCH,
+    TokenType::THIS,
+    TokenType::THROW,
+    TokenType::TRUE,
+    TokenType::TRY,
+    TokenType::TYPEOF,
+    TokenType::VAR,
+    TokenType::WHILE,
+    TokenType::Unknown,
+];
+
+/// The reserved words of the language, in alphabetical order.
+pub const KEYWORDS: &[&str] = &[
+    "and", "assert", "break", "case", "catch", "class", "const", "continue", "default", "do",
+    "else", "false", "finally", "for", "fun", "if", "in", "is", "nil", "or", "print", "return",
+    "super", "switch", "this", "throw", "true", "try", "typeof", "var", "while",
+];
+
 impl TokenType {
-    pub fn check_keyword(str: &str) -> Option<Self> {
-        match str {
-            "and" => Some(Self::AND),
-            "class" => Some(Self::CLASS),
-            "else" => Some(Self::ELSE),
-            "false" => Some(Self::FALSE),
-            "for" => Some(Self::FOR),
-            "fun" => Some(Self::FUN),
-            "if" => Some(Self::IF),
-            "nil" => Some(Self::NIL),
-            "or" => Some(Self::OR),
-            "print" => Some(Self::PRINT),
-            "return" => Some(Self::RETURN),
-            "super" => Some(Self::SUPER),
-            "this" => Some(Self::THIS),
-            "true" => Some(Self::TRUE),
-            "var" => Some(Self::VAR),
-            "while" => Some(Self::WHILE),
+    /// Returns the name of the token type, as printed by the `tokenize` command.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::EOF => "EOF",
+
+            Self::LeftParen => "LEFT_PAREN",
+            Self::RightParen => "RIGHT_PAREN",
+            Self::LeftBrace => "LEFT_BRACE",
+            Self::RightBrace => "RIGHT_BRACE",
+            Self::LeftBracket => "LEFT_BRACKET",
+            Self::RightBracket => "RIGHT_BRACKET",
+
+            Self::Star => "STAR",
+            Self::Dot => "DOT",
+            Self::DotDotDot => "DOT_DOT_DOT",
+            Self::Comma => "COMMA",
+            Self::Colon => "COLON",
+            Self::Semicolon => "SEMICOLON",
+            Self::Plus => "PLUS",
+            Self::Minus => "MINUS",
+            Self::Slash => "SLASH",
+
+            Self::Bang => "BANG",
+            Self::Equal => "EQUAL",
+            Self::EqualEqual => "EQUAL_EQUAL",
+            Self::BangEqual => "BANG_EQUAL",
+
+            Self::Greater => "GREATER",
+            Self::GreaterEqual => "GREATER_EQUAL",
+            Self::Less => "LESS",
+            Self::LessEqual => "LESS_EQUAL",
+
+            Self::String(_) => "STRING",
+            Self::Number(_) | Self::Integer(_) => "NUMBER",
+            Self::Identifier(_) => "IDENTIFIER",
+
+            Self::AND => "AND",
+            Self::ASSERT => "ASSERT",
+            Self::BREAK => "BREAK",
+            Self::CASE => "CASE",
+            Self::CATCH => "CATCH",
+            Self::CLASS => "CLASS",
+            Self::CONST => "CONST",
+            Self::CONTINUE => "CONTINUE",
+            Self::DEFAULT => "DEFAULT",
+            Self::DO => "DO",
+            Self::ELSE => "ELSE",
+            Self::FALSE => "FALSE",
+            Self::FINALLY => "FINALLY",
+            Self::FOR => "FOR",
+            Self::FUN => "FUN",
+            Self::IF => "IF",
+            Self::IN => "IN",
+            Self::IS => "IS",
+            Self::NIL => "NIL",
+            Self::OR => "OR",
+            Self::PRINT => "PRINT",
+            Self::RETURN => "RETURN",
+            Self::SUPER => "SUPER",
+            Self::SWITCH => "SWITCH",
+            Self::THIS => "THIS",
+            Self::THROW => "THROW",
+            Self::TRUE => "TRUE",
+            Self::TRY => "TRY",
+            Self::TYPEOF => "TYPEOF",
+            Self::VAR => "VAR",
+            Self::WHILE => "WHILE",
+
+            Self::UnterminatedString(_) => "UnterminatedString",
+            Self::InvalidNumber(_) => "InvalidNumber",
+            Self::InvalidRawIdentifier(_) => "InvalidRawIdentifier",
+            Self::InvalidEscape(_) => "InvalidEscape",
+            Self::Unknown => "Unknown",
+        }
+    }
+
+    /// Returns true for the reserved words of the language.
+    pub fn is_keyword(&self) -> bool {
+        matches!(
+            self,
+            Self::AND
+                | Self::ASSERT
+                | Self::BREAK
+                | Self::CASE
+                | Self::CATCH
+                | Self::CLASS
+                | Self::CONST
+                | Self::CONTINUE
+                | Self::DEFAULT
+                | Self::DO
+                | Self::ELSE
+                | Self::FALSE
+                | Self::FINALLY
+                | Self::FOR
+                | Self::FUN
+                | Self::IF
+                | Self::IN
+                | Self::IS
+                | Self::NIL
+                | Self::OR
+                | Self::PRINT
+                | Self::RETURN
+                | Self::SUPER
+                | Self::SWITCH
+                | Self::THIS
+                | Self::THROW
+                | Self::TRUE
+                | Self::TRY
+                | Self::TYPEOF
+                | Self::VAR
+                | Self::WHILE
+        )
+    }
+
+    /// Returns true for the tokens that are parsed as a literal expression on their own.
+    pub fn is_literal(&self) -> bool {
+        matches!(
+            self,
+            Self::String(_)
+                | Self::Number(_)
+                | Self::Integer(_)
+                | Self::TRUE
+                | Self::FALSE
+                | Self::NIL
+        )
+    }
+
+    /// Returns true for the tokens that operate on the expressions around them, including
+    /// assignment and the logical keywords.
+    pub fn is_operator(&self) -> bool {
+        self.binding_power().is_some() || matches!(self, Self::Bang | Self::Equal | Self::TYPEOF)
+    }
+
+    /// Returns how tightly the token binds its operands when used as a binary operator, or `None`
+    /// if it is not one. Operators with a higher power are applied first, and operators with the
+    /// same power are applied from left to right.
+    pub fn binding_power(&self) -> Option<u8> {
+        match self {
+            Self::OR => Some(1),
+            Self::AND => Some(2),
+            Self::EqualEqual | Self::BangEqual => Some(3),
+            Self::Greater | Self::GreaterEqual | Self::Less | Self::LessEqual | Self::IS => Some(4),
+            Self::Plus | Self::Minus => Some(5),
+            Self::Star | Self::Slash => Some(6),
             _ => None,
         }
     }
+
+    /// Returns the keyword spelled by the string, or `None` if it is not a keyword. Most
+    /// identifiers are not keywords, so the string is first dispatched on its length and first
+    /// letter, and only compared with the keyword that shares them, if any.
+    pub fn check_keyword(str: &str) -> Option<Self> {
+        let first = *str.as_bytes().first()?;
+
+        let (keyword, token_type) = match (str.len(), first) {
+            (3, b'a') => ("and", Self::AND),
+            (6, b'a') => ("assert", Self::ASSERT),
+            (5, b'b') => ("break", Self::BREAK),
+            (4, b'c') => ("case", Self::CASE),
+            (5, b'c') => match str {
+                "catch" => ("catch", Self::CATCH),
+                "class" => ("class", Self::CLASS),
+                _ => ("const", Self::CONST),
+            },
+            (8, b'c') => ("continue", Self::CONTINUE),
+            (7, b'd') => ("default", Self::DEFAULT),
+            (2, b'd') => ("do", Self::DO),
+            (4, b'e') => ("else", Self::ELSE),
+            (5, b'f') => ("false", Self::FALSE),
+            (7, b'f') => ("finally", Self::FINALLY),
+            (3, b'f') => match str {
+                "for" => ("for", Self::FOR),
+                _ => ("fun", Self::FUN),
+            },
+            (2, b'i') => match str {
+                "if" => ("if", Self::IF),
+                "in" => ("in", Self::IN),
+                _ => ("is", Self::IS),
+            },
+            (3, b'n') => ("nil", Self::NIL),
+            (2, b'o') => ("or", Self::OR),
+            (5, b'p') => ("print", Self::PRINT),
+            (6, b'r') => ("return", Self::RETURN),
+            (5, b's') => ("super", Self::SUPER),
+            (6, b's') => ("switch", Self::SWITCH),
+            (4, b't') => match str {
+                "this" => ("this", Self::THIS),
+                _ => ("true", Self::TRUE),
+            },
+            (5, b't') => ("throw", Self::THROW),
+            (3, b't') => ("try", Self::TRY),
+            (6, b't') => ("typeof", Self::TYPEOF),
+            (3, b'v') => ("var", Self::VAR),
+            (5, b'w') => ("while", Self::WHILE),
+            _ => return None,
+        };
+
+        (str == keyword).then_some(token_type)
+    }
+}
+
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The error returned when parsing a name that does not belong to a token type without a value.
+#[derive(Debug, PartialEq, thiserror::Error)]
+#[error("Unknown token type: {0}")]
+pub struct UnknownTokenType(pub String);
+
+impl FromStr for TokenType {
+    type Err = UnknownTokenType;
+
+    /// Parses the name of a token type that does not carry a value. Literals and identifiers need
+    /// their value, so their names are not parsed.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        NAMED
+            .iter()
+            .find(|token_type| token_type.name() == name)
+            .cloned()
+            .ok_or_else(|| UnknownTokenType(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keywords() {
+        for keyword in KEYWORDS {
+            assert!(TokenType::check_keyword(keyword).is_some(), "{}", keyword);
+        }
+        assert!(KEYWORDS.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_categories() {
+        for keyword in KEYWORDS {
+            let token_type = TokenType::check_keyword(keyword).unwrap();
+            assert!(token_type.is_keyword(), "{}", keyword);
+            assert_eq!(token_type.name(), keyword.to_uppercase());
+        }
+        assert_eq!(
+            NAMED
+                .iter()
+                .filter(|token_type| token_type.is_keyword())
+                .count(),
+            KEYWORDS.len()
+        );
+
+        assert!(TokenType::Integer(1).is_literal());
+        assert!(TokenType::NIL.is_literal());
+        assert!(!TokenType::Identifier("a".to_string()).is_literal());
+
+        assert!(TokenType::AND.is_operator());
+        assert!(TokenType::Equal.is_operator());
+        assert!(!TokenType::Dot.is_operator());
+
+        assert!(TokenType::Star.binding_power() > TokenType::Plus.binding_power());
+        assert!(TokenType::Less.binding_power() > TokenType::EqualEqual.binding_power());
+        assert!(TokenType::AND.binding_power() > TokenType::OR.binding_power());
+        assert_eq!(TokenType::Bang.binding_power(), None);
+    }
+
+    #[test]
+    fn test_names() {
+        for token_type in NAMED {
+            assert_eq!(token_type.to_string().parse(), Ok(token_type.clone()));
+        }
+        assert_eq!(TokenType::Integer(1).to_string(), "NUMBER");
+        assert_eq!(
+            "STRING".parse::<TokenType>(),
+            Err(UnknownTokenType("STRING".to_string()))
+        );
+    }
 }
@@ -1,14 +1,34 @@
+// The generated AST visitors take `&Box<Expr>` and `&Vec<Expr>` arguments, and the keyword token
+// types mirror their uppercase names in the Lox grammar.
+#![allow(clippy::borrowed_box, clippy::ptr_arg, clippy::upper_case_acronyms)]
+#![allow(clippy::module_inception)]
+
+use std::cell::RefCell;
 use std::env;
 use std::fs;
 use std::io::{stderr, Write};
+use std::path::Path;
+use std::rc::Rc;
 
 mod ast;
+mod bench;
+mod config;
 mod lexer;
+mod repl;
 
+use ast::desugar::{lower, lower_expr};
+use ast::error::RuntimeError;
+use ast::fold::fold;
+use ast::hoist::Hoister;
 use ast::interpreter::Interpreter;
+use ast::ir;
 use ast::printer::AstPrinter;
-use ast::syntax_tree::SyntaxTree;
+use ast::stmt::Stmt;
+use ast::syntax_tree::{ParserError, SyntaxTree};
+use ast::value::Value;
+use config::Config;
 use lexer::lexer::Lexer;
+use repl::Repl;
 
 const EXIT_FILE_ERROR: i32 = 1;
 const EXIT_LEXICAL_ERROR: i32 = 65;
@@ -16,8 +36,30 @@ const RUNTIME_ERROR: i32 = 70;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
+
+    // The REPL and the benchmarks are the only commands that do not read a single source file
+    if args.get(1).is_some_and(|command| command == "repl") {
+        let config = Config::from_flags(&args[2..]).unwrap_or_else(|err| {
+            writeln!(stderr(), "{}", err).expect("Failed to write to stderr");
+            std::process::exit(EXIT_FILE_ERROR);
+        });
+        Repl::new(config).run();
+        return;
+    }
+
+    if args.get(1).is_some_and(|command| command == "bench") {
+        let report = bench::Command::from_args(&args[2..])
+            .and_then(|command| command.run(Path::new(bench::BENCH_DIR)))
+            .unwrap_or_else(|err| {
+                writeln!(stderr(), "{}", err).expect("Failed to write to stderr");
+                std::process::exit(EXIT_FILE_ERROR);
+            });
+        print!("{}", report);
+        return;
+    }
+
     if args.len() < 3 {
-        writeln!(stderr(), "Usage: {} <command> <filename>", args[0])
+        writeln!(stderr(), "Usage: {} <command> <filename> [flags]", args[0])
             .expect("Failed to write to stderr");
         std::process::exit(EXIT_FILE_ERROR);
     }
@@ -25,26 +67,59 @@ fn main() {
     let command = &args[1];
     let filename = &args[2];
 
+    // Flags that change the output of a command are handled here, while the rest configure the
+    // language
+    let (flags, script_args) = split_script_args(&args[3..]);
+    let (command_flags, config_flags): (Vec<String>, Vec<String>) =
+        flags.into_iter().partition(|flag| {
+            [
+                "--desugared",
+                "--verbose",
+                "--dump-opt",
+                "--stream",
+                "--fidelity",
+            ]
+            .contains(&flag.as_str())
+        });
+    let desugared = command_flags.iter().any(|flag| flag == "--desugared");
+    let verbose = command_flags.iter().any(|flag| flag == "--verbose");
+    let dump_opt = command_flags.iter().any(|flag| flag == "--dump-opt");
+    let stream = command_flags.iter().any(|flag| flag == "--stream");
+    let fidelity = command_flags.iter().any(|flag| flag == "--fidelity");
+
+    let config = Config::from_flags(&config_flags).unwrap_or_else(|err| {
+        writeln!(stderr(), "{}", err).expect("Failed to write to stderr");
+        std::process::exit(EXIT_FILE_ERROR);
+    });
+
     let file_contents = fs::read_to_string(filename).unwrap_or_else(|err| {
         writeln!(stderr(), "Failed to read file {}: {}", filename, err)
             .expect("Failed to write to stderr");
         String::new()
     });
-    let mut lexer = Lexer::new(&file_contents);
+    let mut lexer = Lexer::with_config(&file_contents, config.clone());
 
     match command.as_str() {
         "tokenize" => {
             let mut has_lexical_error = false;
 
-            lexer.get_tokens().iter().for_each(|token| {
-                if token.is_error() {
-                    writeln!(stderr(), "{}", token.tokenized_string())
-                        .expect("Failed to write to stderr");
-                    has_lexical_error = true;
-                } else {
-                    println!("{}", token.tokenized_string());
-                }
-            });
+            lexer
+                .get_tokens_with_trivia()
+                .iter()
+                .for_each(|(trivia, token)| {
+                    // Trivia is escaped so that each of them is printed on a single line
+                    if fidelity && !trivia.is_empty() {
+                        println!("TRIVIA {:?} null", trivia);
+                    }
+
+                    if token.is_error() {
+                        writeln!(stderr(), "{}", token.tokenized_string())
+                            .expect("Failed to write to stderr");
+                        has_lexical_error = true;
+                    } else {
+                        println!("{}", token.tokenized_string());
+                    }
+                });
 
             if has_lexical_error {
                 std::process::exit(EXIT_LEXICAL_ERROR);
@@ -55,8 +130,21 @@ fn main() {
             let tokens = lexer.get_tokens();
             let mut parser = SyntaxTree::new(tokens);
 
+            // The desugared output is of a complete program, rather than of a single expression
+            if desugared {
+                match parser.parse() {
+                    Ok(stmts) => println!("{}", AstPrinter::print_stmts(&lower(stmts))),
+                    Err(err) => {
+                        writeln!(stderr(), "[line {}] {}", err.line, err.message)
+                            .expect("Failed to write to stderr");
+                        std::process::exit(EXIT_LEXICAL_ERROR);
+                    }
+                }
+                return;
+            }
+
             match parser.expression() {
-                Ok(expr) => println!("{}", AstPrinter::print(expr)),
+                Ok(expr) => println!("{}", AstPrinter::print(&expr)),
                 Err(err) => {
                     writeln!(stderr(), "[line {}] {}", err.line, err.message)
                         .expect("Failed to write to stderr");
@@ -76,14 +164,58 @@ fn main() {
                 std::process::exit(EXIT_LEXICAL_ERROR);
             }
 
-            let interpreter = Interpreter::new(expr.unwrap());
-            match interpreter.evaluate() {
+            let interpreter = Interpreter::new(config);
+            match interpreter.evaluate(&lower_expr(expr.unwrap())) {
                 Ok(val) => println!("{}", val),
-                Err(err) => {
-                    writeln!(stderr(), "{}", err).expect("Failed to write to stderr");
-                    std::process::exit(RUNTIME_ERROR);
+                Err(err) => exit_on_runtime_error(err),
+            }
+        }
+
+        "run" => {
+            let interpreter = Interpreter::new(config.clone());
+            let argv = script_args.into_iter().map(Value::String).collect();
+            interpreter.define_variable("argv", Value::List(Rc::new(RefCell::new(argv))));
+            let mut hoister = Hoister::new();
+            let exit_on_parse_error = |err: ParserError| -> ! {
+                writeln!(stderr(), "[line {}] {}", err.line, err.message)
+                    .expect("Failed to write to stderr");
+                std::process::exit(EXIT_LEXICAL_ERROR);
+            };
+            let execute = |stmts: Vec<ir::Stmt>| {
+                if let Err(err) = interpreter.interpret(&stmts) {
+                    exit_on_runtime_error(err);
                 }
+            };
+
+            // In streaming mode, each top-level declaration runs as soon as it is parsed
+            if stream {
+                let mut parser = SyntaxTree::streaming(lexer.into_tokens());
+                while let Some(declared) = parser.next_declaration() {
+                    let stmts = declared.unwrap_or_else(|err| exit_on_parse_error(err));
+                    execute(optimize(
+                        stmts,
+                        &config,
+                        &mut hoister,
+                        verbose,
+                        dump_opt,
+                        &mut |_| {},
+                    ));
+                }
+                return;
             }
+
+            let tokens = lexer.get_tokens();
+            let stmts = SyntaxTree::new(tokens)
+                .parse()
+                .unwrap_or_else(|err| exit_on_parse_error(err));
+            execute(optimize(
+                stmts,
+                &config,
+                &mut hoister,
+                verbose,
+                dump_opt,
+                &mut |_| {},
+            ));
         }
 
         _ => {
@@ -91,3 +223,81 @@ fn main() {
         }
     }
 }
+
+/// Exits with the status the program asked for with `exit`, or reports the runtime error that
+/// ended it and exits with the status of runtime errors.
+fn exit_on_runtime_error(err: anyhow::Error) -> ! {
+    if let Some(RuntimeError::Exit { code }) = err.downcast_ref() {
+        std::process::exit(*code);
+    }
+
+    writeln!(stderr(), "{}", err).expect("Failed to write to stderr");
+    std::process::exit(RUNTIME_ERROR);
+}
+
+/// Separates the flags of the interpreter from the arguments passed to the script, which are the
+/// ones that do not start with `--`, and all the ones after a `--` argument.
+fn split_script_args(args: &[String]) -> (Vec<String>, Vec<String>) {
+    let (flags, rest) = match args.iter().position(|arg| arg == "--") {
+        Some(separator) => (&args[..separator], &args[separator + 1..]),
+        None => (args, &[][..]),
+    };
+
+    let (flags, mut script_args): (Vec<String>, Vec<String>) =
+        flags.iter().cloned().partition(|arg| arg.starts_with("--"));
+    script_args.extend(rest.iter().cloned());
+    (flags, script_args)
+}
+
+/// Prepares the parsed statements of the `run` command to be executed, by folding, lowering and
+/// hoisting them. The reports requested by the flags are written to stderr. The callback is
+/// called with the name of each of these phases once it is done, so that `bench` can time them.
+fn optimize(
+    stmts: Vec<Stmt>,
+    config: &Config,
+    hoister: &mut Hoister,
+    verbose: bool,
+    dump_opt: bool,
+    on_phase: &mut dyn FnMut(&'static str),
+) -> Vec<ir::Stmt> {
+    let (stmts, folded) = fold(stmts, config);
+    on_phase("fold");
+    if verbose {
+        for folded in folded {
+            writeln!(stderr(), "{}", folded.report()).expect("Failed to write to stderr");
+        }
+    }
+
+    let stmts = lower(stmts);
+    on_phase("lower");
+    let before = dump_opt.then(|| AstPrinter::print_stmts(&stmts));
+    let stmts = hoister.hoist_stmts(stmts);
+    on_phase("hoist");
+    if let Some(before) = before {
+        let after = AstPrinter::print_stmts(&stmts);
+        writeln!(stderr(), "Before:\n{}\nAfter:\n{}", before, after)
+            .expect("Failed to write to stderr");
+    }
+
+    stmts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_script_args() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            split_script_args(&args(&["a", "--verbose", "b", "--", "--c", "d"])),
+            (args(&["--verbose"]), args(&["a", "b", "--c", "d"]))
+        );
+        assert_eq!(
+            split_script_args(&args(&["--stream", "--"])),
+            (args(&["--stream"]), args(&[]))
+        );
+        assert_eq!(split_script_args(&[]), (args(&[]), args(&[])));
+    }
+}
@@ -0,0 +1,478 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{stderr, stdin, stdout, BufRead, IsTerminal, Write};
+use std::process::{self, Command};
+
+use anyhow::Error;
+
+use crate::ast::desugar::{lower, lower_expr};
+use crate::ast::error::RuntimeError;
+use crate::ast::help::help_text;
+use crate::ast::interpreter::Interpreter;
+use crate::ast::stmt::Stmt;
+use crate::ast::syntax_tree::SyntaxTree;
+use crate::config::Config;
+use crate::lexer::lexer::Lexer;
+use crate::lexer::types::KEYWORDS;
+
+/// The markers that terminals put around pasted text when bracketed paste is enabled.
+const PASTE_START: &str = "\x1b[200~";
+const PASTE_END: &str = "\x1b[201~";
+
+/// What the REPL should do after a line of input.
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    Continue,
+    Quit,
+    /// The program called `exit`, which ends the session with the exit status.
+    Exit(i32),
+}
+
+/// An interactive session, where the declarations of every line are kept for the next ones.
+/// The value of every expression is bound to `_`, and to `_1`, `_2`, ... in the order they
+/// were evaluated, so that earlier results can be reused.
+pub struct Repl {
+    config: Config,
+    interpreter: Interpreter,
+    /// The number of results that have been bound to history variables.
+    results: usize,
+    /// The source of the input that last declared each global variable, which `:edit` opens.
+    declarations: HashMap<String, String>,
+}
+
+impl Repl {
+    /// Creates a new session that writes its output to the standard output.
+    pub fn new(config: Config) -> Self {
+        Self::with_output(config, Box::new(stdout()))
+    }
+
+    /// Creates a new session that writes its output to the provided writer.
+    pub fn with_output(config: Config, output: Box<dyn Write>) -> Self {
+        Repl {
+            interpreter: Interpreter::with_output(config.clone(), output),
+            config,
+            results: 0,
+            declarations: HashMap::new(),
+        }
+    }
+
+    /// Reads and evaluates lines from the standard input until it is closed or `:quit` is entered.
+    /// Text pasted into a terminal is evaluated as a single batch, instead of line by line.
+    /// Errors are written to the standard error, and do not end the session.
+    pub fn run(&mut self) {
+        let terminal = stdin().is_terminal() && stdout().is_terminal();
+        if terminal {
+            print!("\x1b[?2004h");
+        }
+
+        let mut lines = stdin().lock().lines().map_while(Result::ok);
+        let mut exit_code = None;
+
+        loop {
+            print!("> ");
+            stdout().flush().expect("Failed to write to stdout");
+
+            let input = match read_input(&mut lines) {
+                Some(input) => input,
+                None => break,
+            };
+
+            match self.eval_line(&input) {
+                Ok(Action::Continue) => {}
+                Ok(Action::Quit) => break,
+                Ok(Action::Exit(code)) => {
+                    exit_code = Some(code);
+                    break;
+                }
+                Err(err) => writeln!(stderr(), "{}", err).expect("Failed to write to stderr"),
+            }
+        }
+
+        if terminal {
+            print!("\x1b[?2004l");
+            stdout().flush().expect("Failed to write to stdout");
+        }
+        if let Some(code) = exit_code {
+            process::exit(code);
+        }
+    }
+
+    /// Evaluates a line of input, which is either a command starting with `:`, or Lox code.
+    /// The value of a line with a single expression is written to the output.
+    /// Input spanning several lines is Lox code, which is only run if all of it parses, and
+    /// all its syntax errors are reported with lines relative to the start of the input.
+    pub fn eval_line(&mut self, line: &str) -> Result<Action, String> {
+        let line = line.trim();
+
+        match line.strip_prefix(':') {
+            Some(command) if !line.contains('\n') => self.eval_command(command),
+            None if line.is_empty() => Ok(Action::Continue),
+            _ => match self.eval_source(line) {
+                Ok(()) => Ok(Action::Continue),
+                Err(err) => match err.downcast_ref() {
+                    Some(RuntimeError::Exit { code }) => Ok(Action::Exit(*code)),
+                    _ => Err(err.to_string()),
+                },
+            },
+        }
+    }
+
+    fn eval_command(&mut self, command: &str) -> Result<Action, String> {
+        let (name, argument) = match command.split_once(' ') {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+
+        match (name, argument) {
+            ("quit", "") => Ok(Action::Quit),
+            ("help", "") => {
+                self.write_line(
+                    ":help <name>      Shows the reference of a native, keyword or operator\n\
+                     :complete <text>  Lists the completions of the name at the end of the text\n\
+                     :edit [name]      Opens $EDITOR on the declaration of the name, and runs the saved file\n\
+                     :clear            Removes all the variables of the session\n\
+                     :quit             Ends the session",
+                )?;
+                Ok(Action::Continue)
+            }
+            ("help", name) => {
+                self.write_line(&help_text(name))?;
+                Ok(Action::Continue)
+            }
+            ("clear", "") => {
+                self.interpreter.reset();
+                self.results = 0;
+                self.declarations.clear();
+                Ok(Action::Continue)
+            }
+            ("edit", name) => {
+                let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+                self.edit(name, &editor)
+            }
+            ("complete", text) => {
+                self.write_line(&self.complete(text).join(" "))?;
+                Ok(Action::Continue)
+            }
+            _ => Err(format!(
+                "Unknown command: :{}. Use :help to list the commands.",
+                command
+            )),
+        }
+    }
+
+    /// Returns the completions of the partial name at the end of the input, which are the
+    /// keywords and the variables of the session (including the natives) that start with it.
+    /// Each completion is the whole input with the name completed, in alphabetical order.
+    pub fn complete(&self, input: &str) -> Vec<String> {
+        let start = input
+            .rfind(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+            .map_or(0, |index| index + 1);
+        let (head, partial) = input.split_at(start);
+
+        // Names can not start with a digit, and members of instances are not known statically
+        if partial.is_empty() || partial.starts_with(|ch: char| ch.is_ascii_digit()) {
+            return Vec::new();
+        }
+
+        let mut names = self.interpreter.variable_names();
+        names.extend(KEYWORDS.iter().map(|keyword| keyword.to_string()));
+        names.sort();
+        names.dedup();
+
+        names
+            .into_iter()
+            .filter(|name| name.starts_with(partial))
+            .map(|name| format!("{}{}", head, name))
+            .collect()
+    }
+
+    /// Opens the editor on a temporary file, which holds the input that declared the name if
+    /// one is provided, and evaluates the file once the editor exits if it was saved.
+    /// The editor is a command, which is run with the path of the file as its last argument.
+    pub fn edit(&mut self, name: &str, editor: &str) -> Result<Action, String> {
+        let source = match name {
+            "" => "",
+            name => self
+                .declarations
+                .get(name)
+                .ok_or_else(|| format!("'{}' was not declared in this session.", name))?,
+        };
+
+        let path = std::env::temp_dir().join(format!("lox-edit-{}.lox", process::id()));
+        fs::write(&path, source).map_err(|err| err.to_string())?;
+        let modified = fs::metadata(&path).and_then(|metadata| metadata.modified());
+
+        let mut words = editor.split_whitespace();
+        let status = match words.next() {
+            Some(program) => Command::new(program).args(words).arg(&path).status(),
+            None => return Err("The editor command is empty.".to_string()),
+        };
+        let edited = fs::read_to_string(&path);
+        let saved = fs::metadata(&path).and_then(|metadata| metadata.modified());
+        let _ = fs::remove_file(&path);
+
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => return Err(format!("The editor exited with {}.", status)),
+            Err(err) => return Err(format!("Failed to run the editor '{}': {}", editor, err)),
+        }
+
+        let edited = edited.map_err(|err| err.to_string())?;
+        let saved = matches!((modified, saved), (Ok(before), Ok(after)) if before != after);
+        match saved || edited != source {
+            true => self.eval_line(&edited),
+            false => Ok(Action::Continue),
+        }
+    }
+
+    /// Runs the Lox code, returning the runtime errors as they are so that exiting the program
+    /// can be told apart from them.
+    fn eval_source(&mut self, source: &str) -> Result<(), Error> {
+        let tokens = Lexer::with_config(source, self.config.clone()).get_tokens();
+
+        // A line with a single expression does not need a trailing semicolon
+        if let Ok(expr) = SyntaxTree::new(tokens.clone()).parse_expression() {
+            let value = self.interpreter.evaluate(&lower_expr(expr))?;
+
+            self.results += 1;
+            self.interpreter
+                .define_variable(&format!("_{}", self.results), value.clone());
+            self.interpreter.define_variable("_", value.clone());
+
+            return self.write_line(&value.to_string()).map_err(Error::msg);
+        }
+
+        let stmts = SyntaxTree::new(tokens).parse_all().map_err(|errors| {
+            Error::msg(
+                errors
+                    .iter()
+                    .map(|err| format!("[line {}] {}", err.line, err.message))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+        })?;
+
+        for stmt in &stmts {
+            let names = match stmt {
+                Stmt::Var(name, _) | Stmt::Const(name, _) => std::slice::from_ref(name),
+                Stmt::VarTuple(_, names, _) => names.as_slice(),
+                _ => &[],
+            };
+            for name in names {
+                self.declarations
+                    .insert(name.name().to_string(), source.to_string());
+            }
+        }
+
+        self.interpreter.interpret(&lower(stmts))
+    }
+
+    fn write_line(&self, line: &str) -> Result<(), String> {
+        self.interpreter
+            .write_line(line)
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// Reads the next input from the lines, which is either a single line, or all the lines of
+/// a bracketed paste joined together without the markers.
+fn read_input(lines: &mut impl Iterator<Item = String>) -> Option<String> {
+    let line = lines.next()?;
+    let Some(start) = line.find(PASTE_START) else {
+        return Some(line);
+    };
+
+    let mut input = line[..start].to_string() + &line[start + PASTE_START.len()..];
+    while !input.contains(PASTE_END) {
+        match lines.next() {
+            Some(line) => {
+                input.push('\n');
+                input.push_str(&line);
+            }
+            None => break,
+        }
+    }
+
+    // Anything typed after the paste on the same line is kept
+    Some(input.replacen(PASTE_END, "", 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::interpreter::SharedOutput;
+
+    /// Evaluates the lines in a new session, and returns the output along with the errors.
+    fn session(lines: &[&str]) -> (String, Vec<String>) {
+        let output = SharedOutput::default();
+        let mut repl = Repl::with_output(Config::default(), Box::new(output.clone()));
+
+        let errors = lines
+            .iter()
+            .filter_map(|line| repl.eval_line(line).err())
+            .collect();
+        (output.contents(), errors)
+    }
+
+    #[test]
+    fn test_session() {
+        let (output, errors) = session(&["var a = 1;", "a + 1", "print a;", "", "a = 5", "a"]);
+        assert_eq!(output, "2\n1\n5\n5\n");
+        assert!(errors.is_empty());
+
+        // Errors do not end the session
+        let (output, errors) = session(&["print b;", "var b = 2;", "b"]);
+        assert_eq!(output, "2\n");
+        assert_eq!(errors, vec!["[line 1] Undefined variable 'b'."]);
+    }
+
+    #[test]
+    fn test_commands() {
+        let (output, errors) = session(&[":help +", "help(\"while\")", "help(debug)"]);
+        assert_eq!(
+            output,
+            "a + b: Adds two numbers or concatenates two strings, or a string and a number with --conversions=lenient.\n\
+             while (condition) body: Runs the body while the condition is truthy.\n\
+             nil\n\
+             debug(value): Prints a developer oriented representation of the value, tagged with its type.\n\
+             nil\n"
+        );
+        assert!(errors.is_empty());
+
+        let output = SharedOutput::default();
+        let mut repl = Repl::with_output(Config::default(), Box::new(output));
+        assert_eq!(repl.eval_line(":quit"), Ok(Action::Quit));
+        assert!(repl.eval_line(":unknown").is_err());
+    }
+
+    #[test]
+    fn test_complete() {
+        let output = SharedOutput::default();
+        let mut repl = Repl::with_output(Config::default(), Box::new(output.clone()));
+        repl.eval_line("var counter = 1; var count = 2; { var inner = 3; }")
+            .unwrap();
+
+        assert_eq!(repl.complete("cou"), vec!["count", "counter"]);
+        assert_eq!(
+            repl.complete("print co"),
+            vec![
+                "print const",
+                "print contains",
+                "print continue",
+                "print count",
+                "print counter"
+            ]
+        );
+        assert_eq!(repl.complete("de"), vec!["debug", "default"]);
+        assert_eq!(repl.complete("wh"), vec!["while"]);
+        assert!(repl.complete("inn").is_empty());
+        assert!(repl.complete("1 + ").is_empty());
+        assert!(repl.complete("").is_empty());
+
+        repl.eval_line(":complete var cou").unwrap();
+        assert_eq!(output.contents(), "var count var counter\n");
+    }
+
+    #[test]
+    fn test_history() {
+        let (output, errors) =
+            session(&["1 + 1", "_ * 10", "_1 + _2", "print _;", "var a = _3;", "a"]);
+        assert_eq!(output, "2\n20\n22\n22\n22\n");
+        assert!(errors.is_empty());
+
+        // Failed expressions are not recorded
+        let (output, errors) = session(&["1", "-nil", "_2"]);
+        assert_eq!(output, "1\n");
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_clear() {
+        let (output, errors) =
+            session(&["var a = 1;", "2", ":clear", "a", "_1", "debug", "3", "_1"]);
+        // The numbering of the results starts over
+        assert_eq!(output, "2\n<native fn debug>\n3\n<native fn debug>\n");
+        assert_eq!(
+            errors,
+            vec![
+                "[line 1] Undefined variable 'a'.",
+                "[line 1] Undefined variable '_1'."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_input() {
+        let mut lines = [
+            "1 + 1",
+            "\x1b[200~var a = 1;",
+            "print a;\x1b[201~",
+            "\x1b[200~print 2;\x1b[201~ 3",
+            "\x1b[200~print 4;",
+        ]
+        .map(String::from)
+        .into_iter();
+
+        assert_eq!(read_input(&mut lines).unwrap(), "1 + 1");
+        assert_eq!(read_input(&mut lines).unwrap(), "var a = 1;\nprint a;");
+        assert_eq!(read_input(&mut lines).unwrap(), "print 2; 3");
+        // A paste cut short by the end of the input is still read
+        assert_eq!(read_input(&mut lines).unwrap(), "print 4;");
+        assert_eq!(read_input(&mut lines), None);
+    }
+
+    #[test]
+    fn test_batch() {
+        let (output, errors) =
+            session(&["var a = 1;\nwhile (a < 3) {\n  print a;\n  a = a + 1;\n}"]);
+        assert_eq!(output, "1\n2\n");
+        assert!(errors.is_empty());
+
+        // Nothing is run unless the whole batch parses, and every error is reported
+        let (output, errors) = session(&[
+            "print 1;\nprint (2;\nprint 3;\nvar = 4;\nprint 5;",
+            "print 6;",
+        ]);
+        assert_eq!(output, "6\n");
+        assert_eq!(errors.len(), 1);
+        let lines: Vec<_> = errors[0].lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("[line 2] "), "{}", lines[0]);
+        assert!(lines[1].starts_with("[line 4] "), "{}", lines[1]);
+    }
+
+    #[test]
+    fn test_edit() {
+        let output = SharedOutput::default();
+        let mut repl = Repl::with_output(Config::default(), Box::new(output.clone()));
+        repl.eval_line("var a = 1;\nvar b = a + 1;").unwrap();
+
+        // The input that declared the name is opened, and evaluated once saved
+        assert_eq!(repl.edit("b", "sed -i s/1/10/"), Ok(Action::Continue));
+        repl.eval_line("print a + b;").unwrap();
+        assert_eq!(output.contents(), "30\n");
+
+        // Nothing is evaluated if the editor leaves the file as is
+        repl.eval_line("a = 5;").unwrap();
+        repl.edit("a", "true").unwrap();
+        repl.eval_line("print a;").unwrap();
+        assert_eq!(output.contents(), "30\n5\n");
+
+        assert_eq!(
+            repl.edit("missing", "true"),
+            Err("'missing' was not declared in this session.".to_string())
+        );
+        assert!(repl.edit("a", "false").is_err());
+        assert!(repl.edit("", "lox-missing-editor").is_err());
+    }
+
+    #[test]
+    fn test_exit() {
+        let output = SharedOutput::default();
+        let mut repl = Repl::with_output(Config::default(), Box::new(output.clone()));
+
+        assert_eq!(repl.eval_line("print 1; exit(2);"), Ok(Action::Exit(2)));
+        assert_eq!(repl.eval_line("exit(0)"), Ok(Action::Exit(0)));
+        assert_eq!(output.contents(), "1\n");
+    }
+}